@@ -0,0 +1,80 @@
+//! Git smart http protocol, served by `git http-backend`
+
+use http::{Request, Response};
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// true if `path` (without `/a`) is a request of the git smart http protocol
+pub fn is_git_path(path: &str) -> bool {
+    path.ends_with("/info/refs") || path.ends_with("/git-upload-pack") || path.ends_with("/git-receive-pack")
+}
+
+/// answers a git request by running `git http-backend` as CGI script
+///
+/// Repositories are looked up below `root`. `user` is passed as `REMOTE_USER`, which allows
+/// pushes of authenticated users.
+pub fn serve(root: &Path, request: &Request, path: &str, user: Option<&str>) -> Response {
+    match run_backend(root, request, path, user) {
+        Ok(response) => response,
+        Err(x) => Response::text(500, &format!("git http-backend failed: {}", x)),
+    }
+}
+
+fn run_backend(root: &Path, request: &Request, path: &str, user: Option<&str>) -> io::Result<Response> {
+    let header = |name: &str| request.headers.get(name).cloned().unwrap_or_default();
+
+    let mut command = Command::new("git");
+    command.arg("http-backend")
+        .env("GIT_PROJECT_ROOT", root)
+        .env("GIT_HTTP_EXPORT_ALL", "1")
+        .env("PATH_INFO", path)
+        .env("REQUEST_METHOD", &request.method)
+        .env("QUERY_STRING", request.query.clone().unwrap_or_default())
+        .env("CONTENT_TYPE", header("content-type"))
+        .env("CONTENT_LENGTH", request.body.len().to_string())
+        .env("HTTP_CONTENT_ENCODING", header("content-encoding"))
+        .env("GIT_PROTOCOL", header("git-protocol"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(user) = user {
+        command.env("REMOTE_USER", user);
+    }
+
+    let mut child = command.spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(&request.body)?;
+    }
+    let output = child.wait_with_output()?;
+
+    parse_cgi(&output.stdout)
+}
+
+/// converts the output of a CGI script to a response
+fn parse_cgi(output: &[u8]) -> io::Result<Response> {
+    let end = output.windows(4).position(|w| w == b"\r\n\r\n").map(|p| (p, p + 4))
+        .or_else(|| output.windows(2).position(|w| w == b"\n\n").map(|p| (p, p + 2)))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no CGI header"))?;
+
+    let mut response = Response {
+        status: 200,
+        headers: Vec::new(),
+        body: output[end.1..].to_vec(),
+    };
+
+    for line in String::from_utf8_lossy(&output[..end.0]).lines() {
+        let index = match line.find(':') {
+            Some(index) => index,
+            None => continue,
+        };
+        let (key, value) = (line[..index].trim(), line[index + 1..].trim());
+        if key.eq_ignore_ascii_case("status") {
+            response.status = value.split_whitespace().next().and_then(|s| s.parse().ok()).unwrap_or(500);
+        } else {
+            response.headers.push((key.to_string(), value.to_string()));
+        }
+    }
+
+    Ok(response)
+}
@@ -0,0 +1,368 @@
+//! Minimal HTTP/1.1 handling: request parsing, responses and authentication
+
+use md5;
+use serde_json;
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+
+/// realm of the digest and basic authentication
+const REALM: &str = "Gerrit Code Review";
+
+/// A received http request
+#[derive(Debug, Clone)]
+pub struct Request {
+    /// http method like `GET`
+    pub method: String,
+    /// request target without the query
+    pub path: String,
+    /// raw query without the leading `?`
+    pub query: Option<String>,
+    /// header names are lower case
+    pub headers: HashMap<String, String>,
+    /// request body, chunked transfer encoding is already decoded
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// reads one request from `stream`
+    ///
+    /// An `Expect: 100-continue` is answered before the body is read.
+    pub fn read(stream: &mut TcpStream) -> io::Result<Request> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let mut parts = line.split_whitespace();
+        let method = parts.next().unwrap_or("").to_string();
+        let mut target = parts.next().unwrap_or("/").to_string();
+        // absolute form of requests through a proxy, the server answers as if it was the target
+        if let Some(index) = target.find("://") {
+            target = match target[index + 3..].find('/') {
+                Some(slash) => target[index + 3 + slash..].to_string(),
+                None => "/".to_string(),
+            };
+        }
+        if method.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "empty request"));
+        }
+
+        let mut headers = HashMap::new();
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 {
+                break;
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(index) = line.find(':') {
+                headers.insert(line[..index].trim().to_lowercase(), line[index + 1..].trim().to_string());
+            }
+        }
+
+        if headers.get("expect").is_some_and(|e| e.eq_ignore_ascii_case("100-continue")) {
+            stream.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+        }
+
+        let mut body = Vec::new();
+        if headers.get("transfer-encoding").is_some_and(|t| t.eq_ignore_ascii_case("chunked")) {
+            loop {
+                let mut size = String::new();
+                reader.read_line(&mut size)?;
+                let size = usize::from_str_radix(size.trim().split(';').next().unwrap_or("0"), 16)
+                    .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "bad chunk size"))?;
+                let mut chunk = vec![0; size + 2];
+                reader.read_exact(&mut chunk)?;
+                if size == 0 {
+                    break;
+                }
+                body.extend_from_slice(&chunk[..size]);
+            }
+        } else if let Some(length) = headers.get("content-length").and_then(|l| l.parse::<usize>().ok()) {
+            body.resize(length, 0);
+            reader.read_exact(&mut body)?;
+        }
+
+        let (path, query) = match target.find('?') {
+            Some(index) => (target[..index].to_string(), Some(target[index + 1..].to_string())),
+            None => (target, None),
+        };
+
+        Ok(Request {
+            method,
+            path,
+            query,
+            headers,
+            body,
+        })
+    }
+
+    /// all values of the query parameter `name`, decoded
+    pub fn query_values(&self, name: &str) -> Vec<String> {
+        self.query.as_ref().map_or(Vec::new(), |query| {
+            query.split('&')
+                .filter_map(|pair| {
+                    let mut kv = pair.splitn(2, '=');
+                    let key = kv.next().unwrap_or("");
+                    if key == name {
+                        Some(decode_form(kv.next().unwrap_or("")))
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        })
+    }
+
+    /// the first value of the query parameter `name`
+    pub fn query_value(&self, name: &str) -> Option<String> {
+        self.query_values(name).into_iter().next()
+    }
+
+    /// the body parsed as json, `null` for an empty body
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap_or(serde_json::Value::Null)
+    }
+}
+
+/// A http response
+#[derive(Debug, Clone)]
+pub struct Response {
+    /// status code
+    pub status: u32,
+    /// additional headers
+    pub headers: Vec<(String, String)>,
+    /// body
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// response with a plain text body
+    pub fn text(status: u32, text: &str) -> Response {
+        Response {
+            status,
+            headers: vec!(("Content-Type".into(), "text/plain; charset=UTF-8".into())),
+            body: text.as_bytes().to_vec(),
+        }
+    }
+
+    /// response with a json body, prefixed with the XSSI protection of gerrit
+    pub fn json(status: u32, json: &serde_json::Value) -> Response {
+        Response {
+            status,
+            headers: vec!(("Content-Type".into(), "application/json; charset=UTF-8".into())),
+            body: format!(")]}}'\n{}\n", json).into_bytes(),
+        }
+    }
+
+    /// response without a body
+    pub fn no_content() -> Response {
+        Response {
+            status: 204,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// writes the response to `stream`, the connection is closed afterwards
+    pub fn write(&self, stream: &mut TcpStream) -> io::Result<()> {
+        let mut out = format!("HTTP/1.1 {} {}\r\n", self.status, reason(self.status));
+        for (key, value) in &self.headers {
+            out.push_str(&format!("{}: {}\r\n", key, value));
+        }
+        out.push_str(&format!("Content-Length: {}\r\nConnection: close\r\n\r\n", self.body.len()));
+
+        stream.write_all(out.as_bytes())?;
+        stream.write_all(&self.body)?;
+        stream.flush()
+    }
+}
+
+/// reason phrase of a status code
+fn reason(status: u32) -> &'static str {
+    match status {
+        100 => "Continue",
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        412 => "Precondition Failed",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+/// decodes a `application/x-www-form-urlencoded` value
+pub fn decode_form(value: &str) -> String {
+    decode_percent(&value.replace('+', " "))
+}
+
+/// decodes the `%XX` escapes of a path segment or query value
+pub fn decode_percent(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        match bytes[index] {
+            b'%' if index + 2 < bytes.len() => {
+                let hex = |b: u8| (b as char).to_digit(16);
+                match (hex(bytes[index + 1]), hex(bytes[index + 2])) {
+                    (Some(high), Some(low)) => {
+                        out.push((high * 16 + low) as u8);
+                        index += 2;
+                    },
+                    _ => out.push(b'%'),
+                }
+            },
+            byte => out.push(byte),
+        }
+        index += 1;
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Authentication scheme of the fake server
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuthScheme {
+    /// `WWW-Authenticate: Digest`, like gerrit before 2.14
+    Digest,
+    /// `WWW-Authenticate: Basic`, like gerrit since 2.14
+    Basic,
+}
+
+/// challenge for an unauthorized request
+pub fn challenge(scheme: AuthScheme) -> Response {
+    let mut response = Response::text(401, "Unauthorized");
+    let value = match scheme {
+        AuthScheme::Digest => format!("Digest realm=\"{}\", nonce=\"{:x}\", qop=\"auth\", algorithm=MD5", REALM, md5::compute(REALM)),
+        AuthScheme::Basic => format!("Basic realm=\"{}\"", REALM),
+    };
+    response.headers.push(("WWW-Authenticate".into(), value));
+    response
+}
+
+/// returns the authenticated user of `request`
+///
+/// `users` maps usernames to http passwords, `tokens` bearer tokens to usernames. Bearer tokens are
+/// accepted with every scheme.
+pub fn authenticate(request: &Request, scheme: AuthScheme, users: &HashMap<String, String>, tokens: &HashMap<String, String>) -> Option<String> {
+    let authorization = request.headers.get("authorization")?;
+
+    if let Some(token) = authorization.strip_prefix("Bearer ") {
+        return tokens.get(token.trim()).cloned();
+    }
+
+    match scheme {
+        AuthScheme::Basic => {
+            if !authorization.starts_with("Basic ") {
+                return None;
+            }
+            let decoded = decode_base64(authorization["Basic ".len()..].trim())?;
+            let decoded = String::from_utf8(decoded).ok()?;
+            let mut userpass = decoded.splitn(2, ':');
+            let user = userpass.next().unwrap_or("");
+            let pass = userpass.next().unwrap_or("");
+
+            if users.get(user).is_some_and(|p| p == pass) {
+                Some(user.to_string())
+            } else {
+                None
+            }
+        },
+        AuthScheme::Digest => {
+            if !authorization.starts_with("Digest ") {
+                return None;
+            }
+            let params = parse_digest(&authorization["Digest ".len()..]);
+            let param = |name: &str| params.get(name).map(|s| &s[..]).unwrap_or("");
+
+            let user = param("username");
+            let password = users.get(user)?;
+
+            let ha1 = format!("{:x}", md5::compute(format!("{}:{}:{}", user, param("realm"), password)));
+            let ha2 = format!("{:x}", md5::compute(format!("{}:{}", request.method, param("uri"))));
+            let expected = if param("qop").is_empty() {
+                format!("{:x}", md5::compute(format!("{}:{}:{}", ha1, param("nonce"), ha2)))
+            } else {
+                format!("{:x}", md5::compute(format!("{}:{}:{}:{}:{}:{}", ha1, param("nonce"), param("nc"), param("cnonce"), param("qop"), ha2)))
+            };
+
+            if expected == param("response") {
+                Some(user.to_string())
+            } else {
+                None
+            }
+        },
+    }
+}
+
+/// parses the `key=value` and `key="value"` list of a digest authorization header
+fn parse_digest(value: &str) -> HashMap<String, String> {
+    let mut params = HashMap::new();
+    let mut rest = value.trim();
+
+    while !rest.is_empty() {
+        let eq = match rest.find('=') {
+            Some(eq) => eq,
+            None => break,
+        };
+        let key = rest[..eq].trim().to_lowercase();
+        rest = rest[eq + 1..].trim_start();
+
+        let value;
+        if rest.starts_with('"') {
+            let end = rest[1..].find('"').map_or(rest.len(), |e| e + 1);
+            value = rest[1..end].to_string();
+            rest = if end < rest.len() { &rest[end + 1..] } else { "" };
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            value = rest[..end].trim().to_string();
+            rest = &rest[end..];
+        }
+        rest = rest.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+
+        params.insert(key, value);
+    }
+
+    params
+}
+
+/// decodes standard base64 with padding
+fn decode_base64(value: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for c in value.bytes() {
+        let sextet = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | u32::from(sextet);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    Some(out)
+}
@@ -0,0 +1,288 @@
+//! In-process fake gerrit server for integration tests
+//!
+//! `FakeGerrit` listens on a random local port and answers a subset of the gerrit REST api from an
+//! in-memory `Model`, plus the git smart http protocol through `git http-backend`. Requests below
+//! `/a/` need digest or basic authentication (see `AuthScheme`) or a bearer token, all other
+//! requests are anonymous and read only. The server also answers requests for other hosts if it is
+//! used as http proxy.
+//!
+//! ```no_run
+//! use fakegerrit::{AuthScheme, FakeGerrit};
+//!
+//! let server = FakeGerrit::start(r#"{"version": "2.13.5", "users": {"admin": "secret"}}"#,
+//!                                AuthScheme::Digest).unwrap();
+//! server.create_project("tools").unwrap();
+//! println!("gerrit at {}", server.url());
+//! ```
+//!
+//! The seed json may use `${URL}` as placeholder for the url of the server.
+
+extern crate md5;
+extern crate serde_json;
+
+mod git;
+mod http;
+mod model;
+
+pub use http::AuthScheme;
+pub use model::Model;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// counter for unique names of temporary directories
+static TEMPDIR_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A temporary directory, removed with all its content on drop
+#[derive(Debug)]
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// creates a new, empty directory in the temporary directory of the system
+    pub fn new(prefix: &str) -> io::Result<TempDir> {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.subsec_nanos()).unwrap_or(0);
+        let path = ::std::env::temp_dir().join(format!("{}-{}-{}-{}", prefix, ::std::process::id(), nanos,
+                                                       TEMPDIR_COUNTER.fetch_add(1, Ordering::SeqCst)));
+        fs::create_dir_all(&path)?;
+        Ok(TempDir { path })
+    }
+
+    /// path of the directory
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// state shared by all connections
+struct State {
+    model: Mutex<Model>,
+    scheme: AuthScheme,
+    git_root: PathBuf,
+    requests: Mutex<Vec<String>>,
+    headers: Mutex<Vec<HashMap<String, String>>>,
+    failures: Mutex<Vec<http::Response>>,
+    session_cookie: Mutex<Option<String>>,
+}
+
+/// A running fake gerrit server
+///
+/// The server is stopped and its repositories are removed on drop.
+pub struct FakeGerrit {
+    url: String,
+    state: Arc<State>,
+    shutdown: Arc<AtomicBool>,
+    git_root: TempDir,
+}
+
+impl FakeGerrit {
+    /// starts a server seeded with the json `seed`, see `Model` for the format
+    pub fn start(seed: &str, scheme: AuthScheme) -> io::Result<FakeGerrit> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("http://127.0.0.1:{}", listener.local_addr()?.port());
+
+        let model = Model::from_json(&seed.replace("${URL}", &url))
+            .map_err(|x| io::Error::new(io::ErrorKind::InvalidData, x))?;
+        let git_root = TempDir::new("fakegerrit")?;
+
+        let state = Arc::new(State {
+            model: Mutex::new(model),
+            scheme,
+            git_root: git_root.path().to_path_buf(),
+            requests: Mutex::new(Vec::new()),
+            headers: Mutex::new(Vec::new()),
+            failures: Mutex::new(Vec::new()),
+            session_cookie: Mutex::new(None),
+        });
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let thread_state = state.clone();
+        let thread_shutdown = shutdown.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if thread_shutdown.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    let state = thread_state.clone();
+                    thread::spawn(move || handle(stream, &state));
+                }
+            }
+        });
+
+        Ok(FakeGerrit {
+            url,
+            state,
+            shutdown,
+            git_root,
+        })
+    }
+
+    /// url of the server, like `http://127.0.0.1:34567`
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// directory of the bare git repositories
+    pub fn git_root(&self) -> &Path {
+        self.git_root.path()
+    }
+
+    /// creates an empty bare repository `name`, cloneable from `<url>/<name>`
+    pub fn create_project(&self, name: &str) -> io::Result<PathBuf> {
+        let path = self.git_root.path().join(name);
+        let status = Command::new("git").arg("init").arg("--bare").arg("--quiet").arg(&path).status()?;
+        if !status.success() {
+            return Err(io::Error::other(format!("git init of {} failed", name)));
+        }
+        Ok(path)
+    }
+
+    /// the model, to inspect or change the data of the server
+    pub fn model(&self) -> MutexGuard<'_, Model> {
+        lock(&self.state.model)
+    }
+
+    /// answers the next `count` requests with `status`, with a `Retry-After` header if
+    /// `retry_after` (in seconds) is given
+    pub fn fail_next(&self, count: usize, status: u32, retry_after: Option<u64>) {
+        let mut response = http::Response::text(status, "temporary failure");
+        if let Some(seconds) = retry_after {
+            response.headers.push(("Retry-After".into(), seconds.to_string()));
+        }
+        lock(&self.state.failures).extend(vec![response; count]);
+    }
+
+    /// all received requests as `METHOD path?query`
+    pub fn requests(&self) -> Vec<String> {
+        lock(&self.state.requests).clone()
+    }
+
+    /// the value of the header `name` of all received requests, in the order of `requests`
+    pub fn request_headers(&self, name: &str) -> Vec<Option<String>> {
+        lock(&self.state.headers).iter().map(|headers| headers.get(&name.to_lowercase()).cloned()).collect()
+    }
+
+    /// sends `cookie` (e.g. `GerritAccount=abc`) with a `Set-Cookie` header in all responses
+    pub fn set_session_cookie(&self, cookie: &str) {
+        *lock(&self.state.session_cookie) = Some(cookie.into());
+    }
+}
+
+impl Drop for FakeGerrit {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // wake up the accept loop
+        let _ = TcpStream::connect(&self.url["http://".len()..]);
+    }
+}
+
+/// locks a mutex, a panic of another connection doesn't matter
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// answers one connection
+fn handle(mut stream: TcpStream, state: &State) {
+    let request = match http::Request::read(&mut stream) {
+        Ok(request) => request,
+        Err(_) => return,
+    };
+
+    {
+        // both in one lock order, so the indices of requests and headers are the same
+        let mut requests = lock(&state.requests);
+        requests.push(match request.query {
+            Some(ref query) => format!("{} {}?{}", request.method, request.path, query),
+            None => format!("{} {}", request.method, request.path),
+        });
+        lock(&state.headers).push(request.headers.clone());
+    }
+
+    let failure = {
+        let mut failures = lock(&state.failures);
+        if failures.is_empty() { None } else { Some(failures.remove(0)) }
+    };
+    let mut response = failure.unwrap_or_else(|| route(&request, state));
+    if let Some(ref cookie) = *lock(&state.session_cookie) {
+        response.headers.push(("Set-Cookie".into(), format!("{}; Path=/", cookie)));
+    }
+    let _ = response.write(&mut stream);
+}
+
+/// authenticates and dispatches a request to git or the REST model
+fn route(request: &http::Request, state: &State) -> http::Response {
+    let (authenticated, path) = if request.path.starts_with("/a/") {
+        (true, &request.path[2..])
+    } else {
+        (false, &request.path[..])
+    };
+
+    let user = if authenticated {
+        let (users, tokens) = {
+            let model = lock(&state.model);
+            (model.users.clone(), model.tokens.clone())
+        };
+        match http::authenticate(request, state.scheme, &users, &tokens) {
+            Some(user) => Some(user),
+            None => return http::challenge(state.scheme),
+        }
+    } else {
+        None
+    };
+
+    if git::is_git_path(path) {
+        return git::serve(&state.git_root, request, path, user.as_ref().map(|u| &u[..]));
+    }
+
+    if !authenticated && request.method != "GET" {
+        return http::Response::text(403, "Authentication required");
+    }
+
+    lock(&state.model).handle(request, path, user.as_ref().map(|u| &u[..]))
+}
+
+#[test]
+fn test_git_push_and_clone() {
+    let server = FakeGerrit::start(r#"{"users": {"admin": "secret"}}"#, AuthScheme::Digest).unwrap();
+    server.create_project("tools").unwrap();
+    let work = TempDir::new("fakegerrit-work").unwrap();
+
+    let git = |dir: &Path, args: &[&str]| {
+        let status = Command::new("git").current_dir(dir)
+            .args(["-c", "user.name=Admin", "-c", "user.email=admin@example.com"])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {:?} failed", args);
+    };
+
+    let push_url = server.url().replace("http://", "http://admin:secret@") + "/a/tools";
+    git(work.path(), &["init", "--quiet", "local"]);
+    fs::write(work.path().join("local/README"), "fake\n").unwrap();
+    git(&work.path().join("local"), &["add", "README"]);
+    git(&work.path().join("local"), &["commit", "--quiet", "-m", "initial"]);
+    git(&work.path().join("local"), &["push", "--quiet", &push_url, "HEAD:refs/heads/master"]);
+
+    // anonymous clone without `/a`
+    git(work.path(), &["clone", "--quiet", &format!("{}/tools", server.url()), "clone"]);
+    assert_eq!(fs::read_to_string(work.path().join("clone/README")).unwrap(), "fake\n");
+}
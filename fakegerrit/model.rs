@@ -0,0 +1,446 @@
+//! In-memory model of the gerrit REST api
+//!
+//! The model is seeded from json:
+//!
+//! ```json
+//! {
+//!     "version": "2.13.5",
+//!     "server_info": { "auth": { "auth_type": "HTTP" }, "download": { "schemes": {} }, ... },
+//!     "users": { "admin": "secret" },
+//!     "tokens": { "token-of-admin": "admin" },
+//!     "accounts": [ { "_account_id": 1000000, "name": "Admin", "username": "admin" } ],
+//!     "changes": [ { "id": "tools~master~I0123", "project": "tools", ... } ],
+//!     "reviewers": { "tools~master~I0123": [ { "_account_id": 1000001, "approvals": {} } ] }
+//! }
+//! ```
+//!
+//! `users` maps usernames to http passwords and `tokens` bearer tokens to usernames. `changes` are
+//! `ChangeInfo` and `reviewers` `ReviewerInfo` entities as returned by gerrit. Without
+//! `server_info` the server has no download schemes, like a gerrit without the `download-commands`
+//! plugin.
+
+use http::{Request, Response};
+use serde_json::{self, Value};
+use std::collections::HashMap;
+
+/// default page size of change queries
+const QUERY_LIMIT: usize = 500;
+
+/// Data of the fake server
+#[derive(Debug, Clone, Default)]
+pub struct Model {
+    /// returned by `/config/server/version`
+    pub version: String,
+    /// `ServerInfo` entity returned by `/config/server/info`
+    pub server_info: Value,
+    /// usernames and there http passwords
+    pub users: HashMap<String, String>,
+    /// bearer tokens and there usernames
+    pub tokens: HashMap<String, String>,
+    /// `AccountInfo` entities
+    pub accounts: Vec<Value>,
+    /// `ChangeInfo` entities
+    pub changes: Vec<Value>,
+    /// `ReviewerInfo` entities per change id
+    pub reviewers: HashMap<String, Vec<Value>>,
+}
+
+impl Model {
+    /// creates a model from a json seed
+    pub fn from_json(seed: &str) -> Result<Model, serde_json::Error> {
+        let seed: Value = serde_json::from_str(seed)?;
+
+        let users = string_map(&seed["users"]);
+        let tokens = string_map(&seed["tokens"]);
+
+        let reviewers = seed["reviewers"].as_object().map_or(HashMap::new(), |reviewers| {
+            reviewers.iter()
+                .map(|(id, list)| (id.clone(), list.as_array().cloned().unwrap_or_default()))
+                .collect()
+        });
+
+        Ok(Model {
+            version: seed["version"].as_str().unwrap_or("2.13.5").to_string(),
+            server_info: if seed["server_info"].is_object() { seed["server_info"].clone() } else { default_server_info() },
+            users,
+            tokens,
+            accounts: seed["accounts"].as_array().cloned().unwrap_or_default(),
+            changes: seed["changes"].as_array().cloned().unwrap_or_default(),
+            reviewers,
+        })
+    }
+
+    /// returns a change by its id, Change-Id or number
+    pub fn change(&self, id: &str) -> Option<&Value> {
+        self.changes.iter().find(|c| change_matches_id(c, id))
+    }
+
+    /// answers a REST request, `path` is without the `/a` prefix
+    pub fn handle(&mut self, request: &Request, path: &str, user: Option<&str>) -> Response {
+        let segments: Vec<String> = path.trim_matches('/')
+            .split('/')
+            .map(::http::decode_percent)
+            .collect();
+        let segments: Vec<&str> = segments.iter().map(|s| &s[..]).collect();
+        let method = &request.method[..];
+
+        match (method, &segments[..]) {
+            ("GET", ["config", "server", "version"]) => Response::json(200, &Value::String(self.version.clone())),
+            ("GET", ["config", "server", "info"]) => Response::json(200, &self.server_info),
+            ("GET", ["accounts", "self"]) if user.is_none() => Response::text(401, "Authentication required"),
+            ("GET", ["accounts", id]) => {
+                let id = if *id == "self" { user.unwrap_or("") } else { id };
+                match self.account(id) {
+                    Some(account) => Response::json(200, account),
+                    None => Response::text(404, "Not found"),
+                }
+            },
+            ("GET", ["changes"]) => self.query(request, user),
+            ("GET", ["changes", id]) | ("GET", ["changes", id, "detail"]) => {
+                match self.change(id) {
+                    Some(change) => Response::json(200, change),
+                    None => Response::text(404, "Not found"),
+                }
+            },
+            ("GET", ["changes", id, "reviewers"]) => {
+                match self.change(id).map(|c| c["id"].as_str().unwrap_or("").to_string()) {
+                    Some(id) => Response::json(200, &Value::Array(self.reviewers.get(&id).cloned().unwrap_or_default())),
+                    None => Response::text(404, "Not found"),
+                }
+            },
+            ("POST", ["changes", id, "reviewers"]) => self.add_reviewer(id, &request.json()),
+            ("DELETE", ["changes", id, "reviewers", account]) => self.delete_reviewer(id, account),
+            ("POST", ["changes", id, "abandon"]) => self.set_status(id, "NEW", "ABANDONED"),
+            ("POST", ["changes", id, "restore"]) => self.set_status(id, "ABANDONED", "NEW"),
+            ("POST", ["changes", id, "submit"]) => self.set_status(id, "NEW", "MERGED"),
+            ("POST", ["changes", id, "revisions", revision, "review"]) => self.review(id, revision, &request.json(), user),
+            _ => Response::text(404, "Not found"),
+        }
+    }
+
+    /// account by id, username, email or name
+    fn account(&self, id: &str) -> Option<&Value> {
+        self.accounts.iter().find(|a| {
+            a["_account_id"].as_u64().map(|n| n.to_string()) == Some(id.to_string())
+                || a["username"].as_str() == Some(id)
+                || a["email"].as_str() == Some(id)
+                || a["name"].as_str() == Some(id)
+        })
+    }
+
+    /// `GET /changes/?q=...`, several `q` are answered with a list of lists
+    fn query(&self, request: &Request, user: Option<&str>) -> Response {
+        let start = request.query_value("S").and_then(|s| s.parse().ok()).unwrap_or(0);
+        let limit = request.query_value("n").and_then(|n| n.parse().ok()).unwrap_or(QUERY_LIMIT);
+        let queries = request.query_values("q");
+
+        let mut results = Vec::new();
+        for query in &queries {
+            let mut matching = Vec::new();
+            for change in &self.changes {
+                match self.query_matches(change, query, user) {
+                    Ok(true) => matching.push(change.clone()),
+                    Ok(false) => {},
+                    Err(x) => return Response::text(400, &x),
+                }
+            }
+
+            let more = matching.len() > start + limit;
+            let mut page: Vec<Value> = matching.into_iter().skip(start).take(limit).collect();
+            for change in &mut page {
+                if let Some(change) = change.as_object_mut() {
+                    change.remove("_more_changes");
+                }
+            }
+            if more {
+                if let Some(last) = page.last_mut().and_then(|c| c.as_object_mut()) {
+                    last.insert("_more_changes".into(), Value::Bool(true));
+                }
+            }
+            results.push(Value::Array(page));
+        }
+
+        match results.len() {
+            0 => Response::json(200, &Value::Array(self.changes.iter().take(limit).cloned().collect())),
+            1 => Response::json(200, &results.remove(0)),
+            _ => Response::json(200, &Value::Array(results)),
+        }
+    }
+
+    /// checks all whitespace separated operators of `query`
+    fn query_matches(&self, change: &Value, query: &str, user: Option<&str>) -> Result<bool, String> {
+        for token in tokenize(query) {
+            if token == "AND" {
+                continue;
+            }
+
+            let mut opvalue = token.splitn(2, ':');
+            let operator = opvalue.next().unwrap_or("");
+            let value = match opvalue.next() {
+                Some(value) => value,
+                None => return Err(format!("unsupported query: {}", token)),
+            };
+
+            let status = change["status"].as_str().unwrap_or("");
+            let matches = match operator {
+                "topic" => change["topic"].as_str() == Some(value),
+                "project" => change["project"].as_str() == Some(value),
+                "branch" => change["branch"].as_str() == Some(value),
+                "change" => change_matches_id(change, value),
+                "status" | "is" => {
+                    match value {
+                        "open" | "new" | "pending" => status == "NEW",
+                        "closed" => status == "MERGED" || status == "ABANDONED",
+                        "merged" => status == "MERGED",
+                        "abandoned" => status == "ABANDONED",
+                        "draft" => status == "DRAFT",
+                        _ => return Err(format!("unsupported status: {}", value)),
+                    }
+                },
+                "owner" => {
+                    let value = if value == "self" { user.unwrap_or("") } else { value };
+                    let owner = change["owner"]["_account_id"].as_u64();
+                    self.account(value).and_then(|a| a["_account_id"].as_u64()).is_some_and(|id| Some(id) == owner)
+                },
+                _ => return Err(format!("unsupported operator: {}", operator)),
+            };
+
+            if !matches {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// `POST /changes/{change-id}/reviewers`
+    fn add_reviewer(&mut self, id: &str, input: &Value) -> Response {
+        let changeid = match self.change(id) {
+            Some(change) => change["id"].as_str().unwrap_or("").to_string(),
+            None => return Response::text(404, "Not found"),
+        };
+
+        let reviewer = input["reviewer"].as_str().unwrap_or("").to_string();
+        let mut account = match self.account(&reviewer) {
+            Some(account) => account.clone(),
+            None => {
+                let result = json_object(vec!(
+                    ("input", Value::String(reviewer.clone())),
+                    ("error", Value::String(format!("{} does not identify a registered user or group", reviewer))),
+                ));
+                return Response::json(200, &result);
+            },
+        };
+
+        let reviewers = self.reviewers.entry(changeid).or_default();
+        if !reviewers.iter().any(|r| r["_account_id"] == account["_account_id"]) {
+            if let Some(object) = account.as_object_mut() {
+                object.insert("approvals".into(), Value::Object(serde_json::Map::new()));
+            }
+            reviewers.push(account.clone());
+        }
+
+        let result = json_object(vec!(
+            ("input", Value::String(reviewer)),
+            ("reviewers", Value::Array(vec!(account))),
+        ));
+        Response::json(200, &result)
+    }
+
+    /// `DELETE /changes/{change-id}/reviewers/{account-id}`
+    fn delete_reviewer(&mut self, id: &str, account: &str) -> Response {
+        let changeid = match self.change(id) {
+            Some(change) => change["id"].as_str().unwrap_or("").to_string(),
+            None => return Response::text(404, "Not found"),
+        };
+        let accountid = match self.account(account) {
+            Some(account) => account["_account_id"].clone(),
+            None => return Response::text(404, "Not found"),
+        };
+
+        let reviewers = self.reviewers.entry(changeid).or_default();
+        let count = reviewers.len();
+        reviewers.retain(|r| r["_account_id"] != accountid);
+
+        if reviewers.len() == count {
+            Response::text(404, "Not found")
+        } else {
+            Response::no_content()
+        }
+    }
+
+    /// abandon, restore and submit of a change in state `from`
+    fn set_status(&mut self, id: &str, from: &str, to: &str) -> Response {
+        let change = match self.changes.iter_mut().find(|c| change_matches_id(c, id)) {
+            Some(change) => change,
+            None => return Response::text(404, "Not found"),
+        };
+
+        let status = change["status"].as_str().unwrap_or("").to_string();
+        if status != from {
+            return Response::text(409, &format!("change is {}", status.to_lowercase()));
+        }
+
+        change["status"] = Value::String(to.into());
+        Response::json(200, change)
+    }
+
+    /// `POST /changes/{change-id}/revisions/{revision-id}/review`, votes are stored as approvals
+    /// of the calling user
+    fn review(&mut self, id: &str, revision: &str, input: &Value, user: Option<&str>) -> Response {
+        let (changeid, known_revision) = match self.change(id) {
+            Some(change) => {
+                let known = revision == "current"
+                    || change["current_revision"].as_str() == Some(revision)
+                    || change["revisions"].get(revision).is_some();
+                (change["id"].as_str().unwrap_or("").to_string(), known)
+            },
+            None => return Response::text(404, "Not found"),
+        };
+        if !known_revision {
+            return Response::text(404, "Not found");
+        }
+
+        let labels = input["labels"].as_object().cloned().unwrap_or_default();
+
+        if !labels.is_empty() {
+            let mut account = match user.and_then(|u| self.account(u)) {
+                Some(account) => account.clone(),
+                None => return Response::text(403, "Authentication required"),
+            };
+
+            let approver = account.clone();
+            let reviewers = self.reviewers.entry(changeid.clone()).or_default();
+            let position = match reviewers.iter().position(|r| r["_account_id"] == account["_account_id"]) {
+                Some(position) => position,
+                None => {
+                    if let Some(object) = account.as_object_mut() {
+                        object.insert("approvals".into(), Value::Object(serde_json::Map::new()));
+                    }
+                    reviewers.push(account);
+                    reviewers.len() - 1
+                },
+            };
+
+            for (label, value) in &labels {
+                let value = value.as_i64().unwrap_or(0);
+                let value = if value > 0 { format!("+{}", value) } else if value == 0 { " 0".into() } else { value.to_string() };
+                reviewers[position]["approvals"][label] = Value::String(value);
+            }
+
+            if let Some(change) = self.changes.iter_mut().find(|c| c["id"].as_str() == Some(&changeid[..])) {
+                for (label, value) in &labels {
+                    set_label_vote(change, label, &approver, value.as_i64().unwrap_or(0));
+                }
+            }
+        }
+
+        Response::json(200, &json_object(vec!(("labels", Value::Object(labels)))))
+    }
+}
+
+/// stores the vote of `account` in the detailed `LabelInfo` of `label` and updates the summary
+/// fields `approved`, `rejected`, `recommended` and `disliked` for votes between -2 and +2
+fn set_label_vote(change: &mut Value, label: &str, account: &Value, value: i64) {
+    if !change["labels"].is_object() {
+        change["labels"] = json_object(vec!());
+    }
+    if !change["labels"][label].is_object() {
+        change["labels"][label] = json_object(vec!(("all", Value::Array(vec!()))));
+    }
+    let labelinfo = &mut change["labels"][label];
+
+    let mut approval = json_object(vec!(("value", Value::from(value))));
+    for field in &["_account_id", "name", "email", "username"] {
+        if !account[*field].is_null() {
+            approval[*field] = account[*field].clone();
+        }
+    }
+
+    let mut all = labelinfo["all"].as_array().cloned().unwrap_or_default();
+    all.retain(|a| a["_account_id"] != account["_account_id"]);
+    all.push(approval);
+
+    for (field, matches) in [("approved", 2), ("rejected", -2), ("recommended", 1), ("disliked", -1)] {
+        match all.iter().find(|a| a["value"].as_i64() == Some(matches)) {
+            Some(a) => labelinfo[field] = json_object(vec!(("_account_id", a["_account_id"].clone()))),
+            None => {
+                if let Some(object) = labelinfo.as_object_mut() {
+                    object.remove(field);
+                }
+            },
+        }
+    }
+    labelinfo["all"] = Value::Array(all);
+}
+
+/// true if `id` is the id, Change-Id or number of `change`
+fn change_matches_id(change: &Value, id: &str) -> bool {
+    // the `id` of a change is percent encoded, `id` is an already decoded path segment
+    change["id"].as_str().map(::http::decode_percent) == Some(id.to_string())
+        || change["change_id"].as_str() == Some(id)
+        || change["_number"].as_u64().map(|n| n.to_string()) == Some(id.to_string())
+}
+
+/// `ServerInfo` of a gerrit with http authentication and without plugins
+fn default_server_info() -> Value {
+    json_object(vec!(
+        ("auth", json_object(vec!(("auth_type", Value::String("HTTP".into()))))),
+        ("change", json_object(vec!(("large_change", Value::from(500)), ("update_delay", Value::from(30))))),
+        ("download", json_object(vec!(("schemes", json_object(vec!())), ("archives", Value::Array(vec!()))))),
+        ("gerrit", json_object(vec!(("all_projects", Value::String("All-Projects".into())),
+                                    ("all_users", Value::String("All-Users".into()))))),
+    ))
+}
+
+/// converts a json object with string values to a map
+fn string_map(json: &Value) -> HashMap<String, String> {
+    json.as_object().map_or(HashMap::new(), |map| {
+        map.iter()
+            .map(|(key, value)| (key.clone(), value.as_str().unwrap_or("").to_string()))
+            .collect()
+    })
+}
+
+/// creates a json object from key value pairs
+fn json_object(pairs: Vec<(&str, Value)>) -> Value {
+    let mut map = serde_json::Map::new();
+    for (key, value) in pairs {
+        map.insert(key.into(), value);
+    }
+    Value::Object(map)
+}
+
+/// splits a query on whitespace, `"..."` and `{...}` are one value without the quotes
+fn tokenize(query: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut closing: Option<char> = None;
+
+    for c in query.chars() {
+        match closing {
+            Some(close) if c == close => closing = None,
+            Some(_) => token.push(c),
+            None if c == '"' => closing = Some('"'),
+            None if c == '{' => closing = Some('}'),
+            None if c.is_whitespace() => {
+                if !token.is_empty() {
+                    tokens.push(token.clone());
+                    token.clear();
+                }
+            },
+            None => token.push(c),
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+
+    tokens
+}
+
+#[test]
+fn test_tokenize() {
+    assert_eq!(tokenize("topic:\"a b+c\"  status:open"), vec!("topic:a b+c", "status:open"));
+    assert_eq!(tokenize("topic:{say \"hi\"}"), vec!("topic:say \"hi\""));
+}
@@ -0,0 +1,183 @@
+//! Implements the accounts endpoint
+
+use call;
+use entities;
+use error::GGRError;
+use error::GGRResult;
+use error::GerritError;
+use url;
+
+const ENDPOINT: &'static str = "/accounts";
+
+/// Interface to retrieve Accounts information from gerrit server
+pub struct Accounts {
+    call: call::Call,
+}
+
+impl Accounts {
+    /// create a new `Accounts` instance
+    pub fn new(url: &url::Url) -> Accounts {
+        Accounts {
+            call: call::Call::new(url),
+        }
+    }
+
+    /// create a new `Accounts` instance which sends its requests through `call`
+    pub fn with_call(call: call::Call) -> Accounts {
+        Accounts {
+            call: call,
+        }
+    }
+
+    /// api function 'GET /accounts/?q={query}'
+    ///
+    /// All pages are received, the query follows `_more_accounts` of the last returned account.
+    /// `options` is a list of `DETAILS` and/or `ALL_EMAILS`.
+    pub fn query_accounts(&mut self, query: &str, options: Option<Vec<&str>>) -> GGRResult<Vec<entities::AccountInfo>> {
+        if query.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::QueryEmpty));
+        }
+
+        let mut optionstring = String::new();
+        for option in options.unwrap_or_default() {
+            optionstring.push_str(&format!("&o={}", option));
+        }
+
+        let path = format!("{}/", ENDPOINT);
+        let mut accounts: Vec<entities::AccountInfo> = Vec::new();
+
+        loop {
+            let querystring = format!("q={}&S={}{}", call::encode_query_value(query), accounts.len(), optionstring);
+            self.call.set_url_query(Some(&querystring));
+
+            let mut page = self.call.execute::<(),Vec<entities::AccountInfo>>("query accounts", &path, call::CallMethod::Get, None)?;
+            let more = page.last().and_then(|a| a._more_accounts).unwrap_or(false);
+            let received = page.len();
+            accounts.append(&mut page);
+
+            if !more || received == 0 {
+                break;
+            }
+        }
+
+        self.call.set_url_query(None);
+        Ok(accounts)
+    }
+
+    /// api function 'GET /accounts/{account-id}'
+    ///
+    /// `accountid` can be a numeric account id, a username, an email address or `self`.
+    pub fn get_account(&self, accountid: &str) -> GGRResult<entities::AccountInfo> {
+        if accountid.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::AccountIDEmpty));
+        }
+
+        let path = format!("{}/{}", ENDPOINT, call::encode_path_segment(accountid));
+
+        self.call.execute::<(),entities::AccountInfo>("get account", &path, call::CallMethod::Get, None)
+    }
+
+    /// convenience function for 'GET /accounts/self'
+    ///
+    /// Returns the account of the calling user, useful to validate credentials.
+    pub fn get_self(&self) -> GGRResult<entities::AccountInfo> {
+        self.get_account("self")
+    }
+
+    /// api function 'GET /accounts/{account-id}/name'
+    ///
+    /// Returns an empty string if the account has no name set.
+    pub fn get_name(&self, accountid: &str) -> GGRResult<String> {
+        if accountid.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::AccountIDEmpty));
+        }
+
+        let path = format!("{}/{}/name", ENDPOINT, call::encode_path_segment(accountid));
+
+        // 204 (no content) is returned if the account has no name
+        self.call.execute::<(),Option<String>>("get account name", &path, call::CallMethod::Get, None)
+            .map(|name| name.unwrap_or_default())
+    }
+
+    /// api function 'PUT /accounts/{account-id}/name'
+    ///
+    /// A `None` or empty `name` deletes the name of the account. The new name is returned.
+    pub fn set_name(&self, accountid: &str, name: Option<&str>) -> GGRResult<String> {
+        if accountid.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::AccountIDEmpty));
+        }
+
+        let path = format!("{}/{}/name", ENDPOINT, call::encode_path_segment(accountid));
+
+        let nameinput = entities::AccountNameInput {
+            name: name.map(|s| s.to_string()),
+        };
+
+        // 204 (no content) is returned if the name was deleted
+        self.call.execute::<&entities::AccountNameInput,Option<String>>("set account name", &path, call::CallMethod::Put, Some(&&nameinput))
+            .map(|name| name.unwrap_or_default())
+    }
+
+    /// api function 'GET /accounts/{account-id}/emails'
+    pub fn get_emails(&self, accountid: &str) -> GGRResult<Vec<entities::EmailInfo>> {
+        if accountid.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::AccountIDEmpty));
+        }
+
+        let path = format!("{}/{}/emails", ENDPOINT, call::encode_path_segment(accountid));
+
+        self.call.execute::<(),Vec<entities::EmailInfo>>("get account emails", &path, call::CallMethod::Get, None)
+    }
+
+    /// convenience function to get the preferred email address of an account
+    ///
+    /// Returns `None` if no preferred email address is set.
+    pub fn get_preferred_email(&self, accountid: &str) -> GGRResult<Option<String>> {
+        let emails = self.get_emails(accountid)?;
+
+        Ok(emails.into_iter()
+           .find(|e| e.preferred.unwrap_or(false))
+           .map(|e| e.email))
+    }
+
+    /// api function 'PUT /accounts/{account-id}/emails/{email-id}/preferred'
+    ///
+    /// The email address needs to be a registered email address of the account.
+    pub fn set_preferred_email(&self, accountid: &str, email: &str) -> GGRResult<()> {
+        if accountid.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::AccountIDEmpty));
+        }
+        if email.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::EmailEmpty));
+        }
+
+        let path = format!("{}/{}/emails/{}/preferred", ENDPOINT, call::encode_path_segment(accountid), call::encode_path_segment(email));
+
+        // 200 (already preferred) and 201 are returned without body content
+        self.call.execute_nocontent::<()>("set preferred email", &path, call::CallMethod::Put, None)
+    }
+}
+
+#[test]
+fn test_set_preferred_email_empty() {
+    let accounts = Accounts::new(&url::Url::parse("http://localhost:8080").unwrap());
+
+    match accounts.set_preferred_email("self", "") {
+        Err(GGRError::GerritApiError(GerritError::EmailEmpty)) => {},
+        x => panic!("expected an empty email, got {:?}", x),
+    }
+    match accounts.set_preferred_email("", "jane@example.com") {
+        Err(GGRError::GerritApiError(GerritError::AccountIDEmpty)) => {},
+        x => panic!("expected an empty account id, got {:?}", x),
+    }
+}
+
+#[test]
+fn test_query_accounts_empty() {
+    let mut accounts = Accounts::new(&url::Url::parse("http://localhost:8080").unwrap());
+
+    match accounts.query_accounts("", None) {
+        Err(GGRError::GerritApiError(GerritError::QueryEmpty)) => {},
+        x => panic!("expected an empty query, got {:?}", x),
+    }
+}
@@ -1,60 +1,40 @@
-use std::io::{Read, Write};
+use std::cmp;
 use std::fmt;
-use std::cell::{RefMut, RefCell};
-use std::ascii::AsciiExt;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
 use serde::Serialize;
 use serde::de::DeserializeOwned;
 use serde_json;
-use curl;
 use url;
 
+use config::ServerCache;
+use transport::CurlTransport;
+use transport::Request;
+use transport::Transport;
+
 use error::GGRResult;
 use error::GGRError;
+use error::GerritError;
 
-/// interface function for `handle_req`, set some fields if body has content
-fn send_req<W: Write>(handle: &mut curl::easy::Easy,
-                      out: &mut W,
-                      body: Option<Vec<u8>>)
-                      -> GGRResult<(u32, Vec<String>)> {
-    match body {
-        Some(body) => {
-            let mut body = &body[..];
-            handle.upload(true)?;
-            handle.in_filesize(body.len() as u64)?;
-            handle_req(handle, out, &mut |buf| body.read(buf).unwrap_or(0))
-        }
-        None => handle_req(handle, out, &mut |_| 0),
-    }
+/// percent encode a single path segment, e.g. a `/` within a project name becomes `%2F`
+pub fn encode_path_segment(segment: &str) -> String {
+    url::percent_encoding::utf8_percent_encode(segment, url::percent_encoding::PATH_SEGMENT_ENCODE_SET).to_string()
 }
 
-/// do the curl request
-fn handle_req<W: Write>(handle: &mut curl::easy::Easy,
-                        out: &mut W,
-                        read: &mut FnMut(&mut [u8]) -> usize)
-                        -> GGRResult<(u32, Vec<String>)> {
-    let mut headers = Vec::new();
-    {
-        let mut handle = handle.transfer();
-        handle.read_function(|buf| Ok(read(buf)))?;
-        handle.write_function(|data| {
-                Ok(match out.write_all(data) {
-                    Ok(_) => data.len(),
-                    Err(_) => 0,
-                })
-            })?;
-        handle.header_function(|data| {
-                headers.push(String::from_utf8_lossy(data).into_owned());
-                true
-            })?;
-        handle.perform()?;
-    }
-
-    Ok((handle.response_code()?, headers))
+/// decodes the `%XX` escapes of a path segment, e.g. `%2F` becomes `/`
+pub fn decode_path_segment(segment: &str) -> String {
+    url::percent_encoding::percent_decode(segment.as_bytes()).decode_utf8_lossy().into_owned()
+}
+
+/// percent encode a value of a query parameter, e.g. a space becomes `+` and a `+` becomes `%2B`
+pub fn encode_query_value(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
 }
 
 /// https actions
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone, Copy)]
 pub enum CallMethod {
     Get,
     Post,
@@ -73,21 +53,154 @@ impl fmt::Display for CallMethod {
     }
 }
 
+/// Authentication of the requests of a `Call`
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum AuthMode {
+    /// authenticated if the transport has credentials for the server, anonymous otherwise
+    Auto,
+    /// all requests are authenticated
+    Authenticated,
+    /// all requests are anonymous and read only
+    Anonymous,
+}
+
+impl Default for AuthMode {
+    fn default() -> AuthMode {
+        AuthMode::Auto
+    }
+}
+
+/// Retries of requests which failed temporarily
+///
+/// `GET` requests are repeated after errors of the transport (e.g. timeouts) and on `5xx`
+/// responses, all requests on `429 Too Many Requests`. The delay starts with `backoff` and doubles
+/// on every retry up to `max_backoff`. A `Retry-After` header of the server replaces the delay, if
+/// the server asks to wait longer than `max_backoff` the response is returned without retry.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// number of retries after the first request
+    pub retries: u32,
+    /// delay before the first retry
+    pub backoff: Duration,
+    /// maximum delay between two requests
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// no retries at all
+    pub fn none() -> RetryPolicy {
+        RetryPolicy {
+            retries: 0,
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// delay before retry number `attempt` (starting at 0) of a request with `method`, `None` if
+    /// the request is not repeated
+    ///
+    /// `response` is `None` if the transport returned an error.
+    fn delay(&self, method: CallMethod, attempt: u32, response: Option<&CallResponse>) -> Option<Duration> {
+        if attempt >= self.retries {
+            return None;
+        }
+
+        let backoff = self.backoff.checked_mul(1 << cmp::min(attempt, 16)).unwrap_or(self.max_backoff);
+        let backoff = cmp::min(backoff, self.max_backoff);
+
+        let response = match response {
+            Some(response) => response,
+            None if method == CallMethod::Get => return Some(backoff),
+            None => return None,
+        };
+
+        match response.status() {
+            429 => {},
+            500..=599 if method == CallMethod::Get => {},
+            _ => return None,
+        }
+
+        match response.get_header("Retry-After").and_then(|x| x.parse::<u64>().ok()) {
+            Some(seconds) if Duration::from_secs(seconds) > self.max_backoff => None,
+            Some(seconds) => Some(Duration::from_secs(seconds)),
+            None => Some(backoff),
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    /// 3 retries, starting with half a second up to 10 seconds
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            retries: 3,
+            backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
 /// Interface to talk with a http server
+///
+/// The requests are send through a `transport::Transport`, per default a `CurlTransport`.
+/// Authenticated requests get the `/a` prefix of gerrit before the endpoint path, anonymous
+/// requests are only allowed for `GET`. Temporary failures are retried, see `RetryPolicy`.
+#[derive(Clone)]
 pub struct Call {
-    shared_handle: RefCell<curl::easy::Easy>,
+    transport: Arc<dyn Transport>,
     base: url::Url,
+    auth: AuthMode,
+    retry: RetryPolicy,
+    server: Arc<ServerCache>,
 }
 
 impl Call {
     /// create a new call object with url as endpoint
     pub fn new(url: &url::Url) -> Call {
+        Call::with_transport(url, Arc::new(CurlTransport::new()))
+    }
+
+    /// create a new call object with url as endpoint which sends all requests through `transport`
+    pub fn with_transport(url: &url::Url, transport: Arc<dyn Transport>) -> Call {
         Call {
-            shared_handle: RefCell::new(curl::easy::Easy::new()),
+            transport: transport,
             base: url.clone(),
+            auth: AuthMode::default(),
+            retry: RetryPolicy::default(),
+            server: Arc::new(ServerCache::new()),
+        }
+    }
+
+    /// set the authentication mode
+    pub fn set_auth_mode(&mut self, auth: AuthMode) {
+        self.auth = auth;
+    }
+
+    /// true if requests are sent authenticated
+    ///
+    /// With `AuthMode::Auto` the transport decides without asking the user, the transport caches
+    /// this decision.
+    pub fn is_authenticated(&self) -> bool {
+        match self.auth {
+            AuthMode::Auto => self.transport.authenticates(&self.base),
+            AuthMode::Authenticated => true,
+            AuthMode::Anonymous => false,
         }
     }
 
+    /// set the retries of temporary failed requests
+    pub fn set_retry_policy(&mut self, retry: RetryPolicy) {
+        self.retry = retry;
+    }
+
+    /// share the cached server version and info with other call objects
+    pub fn set_server_cache(&mut self, server: Arc<ServerCache>) {
+        self.server = server;
+    }
+
+    /// return the cache of server version and info
+    pub fn get_server_cache(&self) -> Arc<ServerCache> {
+        self.server.clone()
+    }
+
     /// change url objects query information
     pub fn set_url_query(&mut self, q: Option<&str>) {
         self.base.set_query(q);
@@ -98,47 +211,132 @@ impl Call {
         &self.base
     }
 
+    /// return the transport of this call object
+    pub fn get_transport(&self) -> Arc<dyn Transport> {
+        self.transport.clone()
+    }
+
     // Low Level Methods
 
-    /// set cookie and netrc options and returnes a CallRequest
-    fn do_request(&self, method: &CallMethod, url: &str) -> GGRResult<CallRequest> {
-        let mut handle = self.shared_handle.borrow_mut();
-        try!(handle.cookie_session(true));
-        try!(handle.netrc(curl::easy::NetRc::Required));
+    /// sends a request with an optional json body through the transport and returns a CallResponse
+    ///
+    /// Anonymous requests other than `GET` return a `GerritError::AnonymousReadOnly`. If the
+    /// transport has no credentials, it is asked to `login` only when a request needs them: for
+    /// a `POST`, `PUT` or `DELETE` with `AuthMode::Auto` and after a `401` response, then the
+    /// request is repeated authenticated. `AuthMode::Anonymous` never asks.
+    pub fn request<S: Serialize>(&self, method: CallMethod, path: &str, body: Option<&S>) -> GGRResult<CallResponse> {
+        let mut authenticated = self.is_authenticated();
+        if !authenticated && method != CallMethod::Get {
+            if self.auth != AuthMode::Auto || !self.transport.login(&self.base) {
+                return Err(GGRError::GerritApiError(GerritError::AnonymousReadOnly(format!("{} {}", method, path))));
+            }
+            authenticated = true;
+        }
+
+        let body = match body {
+            Some(body) => {
+                let body_bytes = serde_json::to_vec(&body)?;
+                debug!("sending JSON data ({} bytes) '{:?}'", body_bytes.len(), String::from_utf8_lossy(&body_bytes));
+                Some(body_bytes)
+            },
+            None => None,
+        };
+
+        let mut call_response = self.send(method, path, &body, authenticated)?;
+        if call_response.status() == 401 && self.auth != AuthMode::Anonymous
+            && !self.transport.authenticates(&self.base) && self.transport.login(&self.base) {
+            debug!("{} {} needs an authentication, repeat it with credentials", method, path);
+            call_response = self.send(method, path, &body, true)?;
+        }
+
+        debug!("return-from-server: {:?}", call_response);
+
+        // cut first 4 bytes from output stream
+        // **NOTICE**: The first 4 characters are cutted from the returned content. We want only
+        // json data which has a prevention against XSSI attacks. More here:
+        // <https://gerrit-documentation.storage.googleapis.com/Documentation/2.12.3/rest-api.html#output>
+        if let Some(ref mut body) = call_response.body {
+            if body.starts_with(b")]}'") {
+                body.drain(..4);
+            }
+        }
 
-        CallRequest::new(handle, method, url)
+        Ok(call_response)
     }
 
-    /// call the do_request function two times. One with digest and the other with basic http
-    /// authentication methods. The first success returnes a CallResponse
-    pub fn request<S: Serialize>(&self, method: CallMethod, path: &str, body: Option<&S>) -> GGRResult<CallResponse> {
+    /// sends one request through the transport, with retries of temporary failures
+    fn send(&self, method: CallMethod, path: &str, body: &Option<Vec<u8>>, authenticated: bool) -> GGRResult<CallResponse> {
         let mut sendurl = self.base.clone();
+        let prefix = if authenticated { "/a" } else { "" };
         // double replace for pathes with three ///.
-        let complete_path = format!("{}/{}", sendurl.path(), path).replace("//", "/").replace("//", "/");
+        let complete_path = format!("{}/{}/{}", sendurl.path(), prefix, path).replace("//", "/").replace("//", "/");
         sendurl.set_path(&complete_path);
 
         debug!("url-to-send: {:?}", sendurl);
 
-        for am in vec!(
-            curl::easy::Auth::new().digest(true),
-            curl::easy::Auth::new().basic(true),
-        ) {
-            let mut call_request = try!(self.do_request(&method, &sendurl.to_owned().into_string()));
-            if let Some(body) = body {
-                call_request.with_json_body(&body).ok();
-            }
+        let request = Request {
+            method: method,
+            url: sendurl,
+            headers: Vec::new(),
+            body: body.clone(),
+            authenticated: authenticated,
+        };
 
-            try!(call_request.handle.http_auth(am));
-            let call_response = call_request.send()?;
+        let mut attempt = 0;
+        loop {
+            let response = self.transport.send(&request);
+
+            let delay = match response {
+                Ok(ref response) => self.retry.delay(method, attempt, Some(response)),
+                Err(GGRError::Curl(_)) | Err(GGRError::StdIo(_)) => self.retry.delay(method, attempt, None),
+                Err(_) => None,
+            };
+
+            match delay {
+                Some(delay) => {
+                    debug!("temporary failure of {} {}, retry in {:?}", method, request.url, delay);
+                    thread::sleep(delay);
+                    attempt += 1;
+                },
+                None => return response,
+            }
+        }
+    }
 
-            if call_response.status() == 401 /* Unauthorized */ {
-                debug!("status 401 ... try other http method if available");
-                continue;
+    /// generic helper function for the endpoint objects
+    ///
+    /// The `desc` parameter is a short description for  error messages, its embedded into 'Problem
+    /// '...' with <DESC>'.
+    /// The call is executed with the `path` parameter and the `httpmethod` with `uploaddata` for
+    /// `Put` and `Post` http methods. Responses other than `2xx` return the matching
+    /// `GerritError`, see `GerritError::from_status`.
+    pub fn execute<INPUT,OUTPUT>(&self, desc: &str, path: &str, httpmethod: CallMethod, uploaddata: Option<&INPUT>) -> GGRResult<OUTPUT>
+    where INPUT: Serialize + fmt::Debug,
+          OUTPUT: DeserializeOwned
+    {
+        match self.request(httpmethod, path, uploaddata) {
+            Ok(cr) => cr.convert::<OUTPUT>(),
+            Err(GGRError::GerritApiError(x)) => Err(GGRError::GerritApiError(x)),
+            Err(x) => {
+                Err(GGRError::General(format!("Problem '{}' with {}", x, desc)))
             }
-            return Ok(call_response);
         }
+    }
 
-        Err(GGRError::General("No Authentication algorithm found for your gerrit server. 'basic' and 'digest' tested".into()))
+    /// generic helper function for the endpoint objects on calls without returned content
+    ///
+    /// Same as `execute` but for api functions where the gerrit server returns no body content,
+    /// like `204 No Content` on delete operations.
+    pub fn execute_nocontent<INPUT>(&self, desc: &str, path: &str, httpmethod: CallMethod, uploaddata: Option<&INPUT>) -> GGRResult<()>
+    where INPUT: Serialize + fmt::Debug
+    {
+        match self.request(httpmethod, path, uploaddata) {
+            Ok(cr) => cr.to_result().map(|_| ()),
+            Err(GGRError::GerritApiError(x)) => Err(GGRError::GerritApiError(x)),
+            Err(x) => {
+                Err(GGRError::General(format!("Problem '{}' with {}", x, desc)))
+            }
+        }
     }
 
     /// Convenience method that performs a `GET` request.
@@ -183,105 +381,6 @@ impl<'a> Iterator for Headers<'a> {
     }
 }
 
-/// present a http request
-pub struct CallRequest<'a> {
-    handle: RefMut<'a, curl::easy::Easy>,
-    headers: curl::easy::List,
-    body: Option<Vec<u8>>,
-}
-
-impl<'a> CallRequest<'a> {
-    /// create a call request
-    fn new(mut handle: RefMut<'a, curl::easy::Easy>,
-           method: &CallMethod,
-           url: &str)
-           -> GGRResult<CallRequest<'a>> {
-        debug!("request {} {}", method, url);
-
-        let mut headers = curl::easy::List::new();
-        headers.append("Accept: application/json").ok();
-
-        match *method {
-            CallMethod::Get => try!(handle.get(true)),
-            CallMethod::Post => try!(handle.custom_request("POST")),
-            CallMethod::Put => try!(handle.custom_request("PUT")),
-            CallMethod::Delete => try!(handle.custom_request("DELETE")),
-        }
-
-        handle.url(url)?;
-
-        Ok(CallRequest {
-            handle: handle,
-            headers: headers,
-            body: None,
-        })
-    }
-
-    /// adds a specific header to the request
-    pub fn with_header(mut self, key: &str, value: &str) -> GGRResult<CallRequest<'a>> {
-        self.headers.append(&format!("{}: {}", key, value))?;
-        Ok(self)
-    }
-
-    /// sets the JSON request body for the request.
-    pub fn with_json_body<S: Serialize>(&mut self, body: &S) -> GGRResult<&mut CallRequest<'a>> {
-        let mut body_bytes: Vec<u8> = vec![];
-        serde_json::to_writer(&mut body_bytes, &body)?;
-        debug!("sending JSON data ({} bytes) '{:?}'", body_bytes.len(), String::from_utf8_lossy(&body_bytes));
-        self.body = Some(body_bytes);
-        self.headers.append("Content-Type: application/json")?;
-        Ok(self)
-    }
-
-    /// attaches some form data to the request.
-    pub fn with_form_data(&mut self, form: curl::easy::Form) -> GGRResult<&mut CallRequest<'a>> {
-        debug!("sending form data");
-        self.handle.httppost(form)?;
-        self.body = None;
-        Ok(self)
-    }
-
-    /// enables or disables redirects.  The default is off.
-    pub fn follow_location(&mut self, val: bool) -> GGRResult<&mut CallRequest<'a>> {
-        debug!("follow redirects: {}", val);
-        self.handle.follow_location(val)?;
-        Ok(self)
-    }
-
-    /// Sends the request and writes response data into the given file
-    /// instead of the response object's in memory buffer.
-    pub fn send_into<W: Write>(mut self, out: &mut W) -> GGRResult<CallResponse> {
-        self.handle.http_headers(self.headers)?;
-        let local_body = self.body.clone();
-        let (status, headers) = send_req(&mut self.handle, out, local_body)?;
-        debug!("response: {}", status);
-        Ok(CallResponse {
-            status: status,
-            headers: headers,
-            body: None,
-        })
-    }
-
-    /// Sends the request and reads the response body into the response object.
-    pub fn send(self) -> GGRResult<CallResponse> {
-        let mut out = vec![];
-        let mut rv = self.send_into(&mut out)?;
-
-        debug!("return-from-server: {:?}", rv);
-
-        // cut first 4 bytes from output stream
-        // **NOTICE**: The first 4 characters are cutted from the returned content. We want only
-        // json data which has a prevention against XSSI attacks. More here:
-        // <https://gerrit-documentation.storage.googleapis.com/Documentation/2.12.3/rest-api.html#output>
-        if out.starts_with(b")]}'") {
-            out = out[4..].into();
-        }
-
-        rv.body = Some(out);
-        Ok(rv)
-    }
-}
-
 /// represent a http resonse
 #[derive(Clone, Debug)]
 pub struct CallResponse {
@@ -291,6 +390,16 @@ pub struct CallResponse {
 }
 
 impl CallResponse {
+    /// create a response from the status code, the raw header lines and the body of a http
+    /// response, used by `transport::Transport` implementations
+    pub fn new(status: u32, headers: Vec<String>, body: Vec<u8>) -> CallResponse {
+        CallResponse {
+            status: status,
+            headers: headers,
+            body: Some(body),
+        }
+    }
+
     /// Returns the status code of the response
     pub fn status(&self) -> u32 {
         self.status
@@ -306,8 +415,8 @@ impl CallResponse {
         !self.failed()
     }
 
-    /// Converts the response into a result object.  This also converts non okay response codes
-    /// into errors.
+    /// Converts the response into a result object.  This also converts response codes other than
+    /// `2xx` into errors, see `GerritError::from_status`.
     pub fn to_result(&self) -> GGRResult<&CallResponse> {
         debug!("headers:");
         for (header_key, header_value) in self.headers() {
@@ -318,19 +427,30 @@ impl CallResponse {
         if let Some(ref body) = self.body {
             debug!("body: {}", String::from_utf8_lossy(body));
         }
-        if self.ok() {
+        if self.status >= 200 && self.status < 300 {
             return Ok(self);
         }
-        Err(GGRError::General(format!("generic error: {}", self.status())))
+        Err(GGRError::GerritApiError(GerritError::from_status(self.status(), self.message())))
+    }
+
+    /// the message of the server in the body, for error responses
+    fn message(&self) -> String {
+        match self.body {
+            Some(ref body) if !body.is_empty() => String::from_utf8_lossy(body).into_owned(),
+            _ => "no cause from server".into(),
+        }
     }
 
     /// Deserializes the response body into the given type
+    ///
+    /// An empty body (like on `204 No Content`) is deserialized as json `null`, which is `()` or
+    /// `None` of an `Option`.
     pub fn deserialize<T>(&self) -> GGRResult<T>
         where T: DeserializeOwned
     {
         let body = match self.body {
-            Some(ref body) => body,
-            None => &b""[..],
+            Some(ref body) if !body.iter().all(|b| b.is_ascii_whitespace()) => body,
+            _ => &b"null"[..],
         };
 
         let x = serde_json::from_reader(body)?;
@@ -0,0 +1,398 @@
+//! Record and replay of http interactions
+//!
+//! A cassette is a json file with a list of request/response pairs. `RecordingTransport` wraps
+//! another transport (e.g. `CurlTransport` against a test server) and writes every interaction to
+//! a cassette, `ReplayTransport` serves the responses of a cassette without any server. Secrets
+//! like `Authorization` and cookie headers, password fields of json bodies, access tokens in
+//! the query and the whole response of password and token endpoints are redacted before anything
+//! is written.
+//!
+//! ```no_run
+//! use libgerrit::cassette::{self, Mode};
+//! use libgerrit::gerrit::Gerrit;
+//!
+//! let transport = cassette::transport(Mode::Replay, "tests/fixtures/changes.json").unwrap();
+//! let gerrit = Gerrit::with_transport("http://localhost:8080", transport);
+//! let changes = gerrit.changes().query_changes(Some(vec!("status:open")), None).unwrap();
+//! ```
+//!
+//! Applications can offer the switch `GGR_CASSETTE=record:<path>` or `GGR_CASSETTE=replay:<path>`
+//! (see `parse_switch` and `wrap`), `ggr` does this for all commands.
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use serde_json;
+use url;
+
+use call::CallResponse;
+use error::GGRError;
+use error::GGRResult;
+use transport::CurlTransport;
+use transport::Request;
+use transport::Transport;
+
+/// environment variable with the cassette switch, `record:<path>` or `replay:<path>`
+pub const ENV_CASSETTE: &'static str = "GGR_CASSETTE";
+
+/// replacement of redacted values
+const REDACTED: &'static str = "REDACTED";
+
+/// headers which values are never written to a cassette
+const SECRET_HEADERS: &'static [&'static str] = &["authorization", "proxy-authorization", "cookie", "set-cookie"];
+
+/// json fields and query parameters which values are never written to a cassette
+const SECRET_FIELDS: &'static [&'static str] = &["password", "http_password", "token", "access_token", "secret"];
+
+/// path segments of endpoints which response bodies are never written to a cassette, e.g. the
+/// generated password of `PUT /accounts/self/password.http` is a bare json string
+const SECRET_PATHS: &'static [&'static str] = &["password.http", "tokens"];
+
+/// A recorded request
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct RecordedRequest {
+    /// http method, e.g. `GET`
+    pub method: String,
+    /// path of the url
+    pub path: String,
+    /// query of the url
+    pub query: Option<String>,
+    /// json body of the request
+    pub body: Option<serde_json::Value>,
+}
+
+/// A recorded response
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RecordedResponse {
+    /// http status code
+    pub status: u32,
+    /// raw header lines
+    pub headers: Vec<String>,
+    /// body as received, including the XSSI prefix
+    pub body: String,
+}
+
+/// One request and its response
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Interaction {
+    /// the request
+    pub request: RecordedRequest,
+    /// the response of the server
+    pub response: RecordedResponse,
+}
+
+/// A list of interactions, stored as json file
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct Cassette {
+    /// all interactions in the recorded order
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    /// load a cassette from a json file
+    pub fn load<P: AsRef<Path>>(path: P) -> GGRResult<Cassette> {
+        let file = File::open(path)?;
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    /// save the cassette as pretty printed json file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> GGRResult<()> {
+        let mut file = File::create(path)?;
+        serde_json::to_writer_pretty(&mut file, self)?;
+        Ok(())
+    }
+}
+
+/// converts a request to its recorded and redacted form
+fn record_request(request: &Request) -> RecordedRequest {
+    let query = request.url.query().map(|query| {
+        let pairs: Vec<String> = query.split('&').map(|pair| {
+            let name = pair.splitn(2, '=').next().unwrap_or("");
+            if SECRET_FIELDS.contains(&&name.to_lowercase()[..]) {
+                format!("{}={}", name, REDACTED)
+            } else {
+                pair.to_string()
+            }
+        }).collect();
+        pairs.join("&")
+    });
+
+    let body = request.body.as_ref().map(|body| {
+        match serde_json::from_slice::<serde_json::Value>(body) {
+            Ok(mut json) => {
+                redact_json(&mut json);
+                json
+            },
+            Err(_) => serde_json::Value::String(String::from_utf8_lossy(body).into_owned()),
+        }
+    });
+
+    RecordedRequest {
+        method: request.method.to_string(),
+        path: request.url.path().into(),
+        query: query,
+        body: body,
+    }
+}
+
+/// converts a response to `request` to its recorded and redacted form
+fn record_response(request: &Request, response: &CallResponse) -> RecordedResponse {
+    let headers = response.headers()
+        .filter(|&(key, _)| !key.is_empty())
+        .map(|(key, value)| {
+            if SECRET_HEADERS.contains(&&key.to_lowercase()[..]) {
+                format!("{}: {}", key, REDACTED)
+            } else if value.is_empty() {
+                // status line like `HTTP/1.1 200 OK`
+                key.to_string()
+            } else {
+                format!("{}: {}", key, value)
+            }
+        })
+        .collect();
+
+    let body = String::from_utf8_lossy(&response.get_body().unwrap_or_default()).into_owned();
+    let secret = request.url.path_segments().map_or(false, |mut segments| segments.any(|s| SECRET_PATHS.contains(&s)));
+    let body = match serde_json::from_str::<serde_json::Value>(body.trim_start_matches(")]}'")) {
+        Ok(_) if secret => format!(")]}}'\n{}", serde_json::Value::String(REDACTED.into())),
+        Err(_) if secret && !body.trim().is_empty() => REDACTED.to_string(),
+        Ok(mut json) => {
+            if redact_json(&mut json) {
+                format!(")]}}'\n{}", json)
+            } else {
+                body
+            }
+        },
+        Err(_) => body,
+    };
+
+    RecordedResponse {
+        status: response.status(),
+        headers: headers,
+        body: body,
+    }
+}
+
+/// replaces the values of secret fields in a json value, returns true if something was redacted
+fn redact_json(json: &mut serde_json::Value) -> bool {
+    let mut redacted = false;
+
+    match *json {
+        serde_json::Value::Object(ref mut map) => {
+            for (key, value) in map.iter_mut() {
+                if SECRET_FIELDS.contains(&&key.to_lowercase()[..]) && !value.is_null() {
+                    *value = serde_json::Value::String(REDACTED.into());
+                    redacted = true;
+                } else {
+                    redacted |= redact_json(value);
+                }
+            }
+        },
+        serde_json::Value::Array(ref mut array) => {
+            for value in array.iter_mut() {
+                redacted |= redact_json(value);
+            }
+        },
+        _ => {},
+    }
+
+    redacted
+}
+
+/// Transport which records all interactions of another transport to a cassette file
+///
+/// The cassette file is written after every request, so it is complete even if the process is
+/// stopped.
+pub struct RecordingTransport {
+    inner: Arc<dyn Transport>,
+    path: PathBuf,
+    cassette: Mutex<Cassette>,
+}
+
+impl RecordingTransport {
+    /// record all requests which are send through `inner` to the cassette file `path`
+    pub fn new<P: Into<PathBuf>>(inner: Arc<dyn Transport>, path: P) -> RecordingTransport {
+        RecordingTransport {
+            inner: inner,
+            path: path.into(),
+            cassette: Mutex::new(Cassette::default()),
+        }
+    }
+}
+
+impl Transport for RecordingTransport {
+    fn authenticates(&self, url: &url::Url) -> bool {
+        self.inner.authenticates(url)
+    }
+
+    fn login(&self, url: &url::Url) -> bool {
+        self.inner.login(url)
+    }
+
+    fn send(&self, request: &Request) -> GGRResult<CallResponse> {
+        let response = self.inner.send(request)?;
+
+        let mut cassette = match self.cassette.lock() {
+            Ok(cassette) => cassette,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        cassette.interactions.push(Interaction {
+            request: record_request(request),
+            response: record_response(request, &response),
+        });
+        cassette.save(&self.path)?;
+
+        Ok(response)
+    }
+}
+
+/// Transport which answers requests from a cassette
+///
+/// A request is answered with the first not yet used interaction with the same method, path,
+/// query and body. A request without a recorded interaction is an error.
+pub struct ReplayTransport {
+    interactions: Mutex<Vec<Option<Interaction>>>,
+}
+
+impl ReplayTransport {
+    /// replay the interactions of `cassette`
+    pub fn new(cassette: Cassette) -> ReplayTransport {
+        ReplayTransport {
+            interactions: Mutex::new(cassette.interactions.into_iter().map(Some).collect()),
+        }
+    }
+
+    /// replay the interactions of the cassette file `path`
+    pub fn load<P: AsRef<Path>>(path: P) -> GGRResult<ReplayTransport> {
+        Ok(ReplayTransport::new(Cassette::load(path)?))
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn send(&self, request: &Request) -> GGRResult<CallResponse> {
+        let recorded = record_request(request);
+
+        let mut interactions = match self.interactions.lock() {
+            Ok(interactions) => interactions,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        let position = interactions.iter().position(|i| {
+            i.as_ref().map_or(false, |i| i.request == recorded)
+        });
+
+        match position.and_then(|position| interactions[position].take()) {
+            Some(interaction) => {
+                let response = interaction.response;
+                Ok(CallResponse::new(response.status, response.headers, response.body.into_bytes()))
+            },
+            None => Err(GGRError::General(format!("no recorded interaction for {} {}{}",
+                                                  recorded.method, recorded.path,
+                                                  recorded.query.map(|q| format!("?{}", q)).unwrap_or_default()))),
+        }
+    }
+}
+
+/// Mode of the transport returned by `transport`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// send requests with curl and record them
+    Record,
+    /// answer requests from the cassette
+    Replay,
+}
+
+impl FromStr for Mode {
+    type Err = GGRError;
+
+    fn from_str(s: &str) -> Result<Mode, GGRError> {
+        match s {
+            "record" => Ok(Mode::Record),
+            "replay" => Ok(Mode::Replay),
+            _ => Err(GGRError::General(format!("cassette mode '{}' is not 'record' or 'replay'", s))),
+        }
+    }
+}
+
+/// parses a switch like `record:<path>` or `replay:<path>`, e.g. the value of `ENV_CASSETTE`
+pub fn parse_switch(switch: &str) -> GGRResult<(Mode, PathBuf)> {
+    let mut parts = switch.splitn(2, ':');
+    let mode = parts.next().unwrap_or("").parse()?;
+    match parts.next() {
+        Some(path) if !path.is_empty() => Ok((mode, PathBuf::from(path))),
+        _ => Err(GGRError::General(format!("cassette switch '{}' has no path, use '<mode>:<path>'", switch))),
+    }
+}
+
+/// records all requests through `inner` or replays them from the cassette file `path`
+pub fn wrap<P: AsRef<Path>>(mode: Mode, path: P, inner: Arc<dyn Transport>) -> GGRResult<Arc<dyn Transport>> {
+    match mode {
+        Mode::Record => Ok(Arc::new(RecordingTransport::new(inner, path.as_ref()))),
+        Mode::Replay => Ok(Arc::new(ReplayTransport::load(path)?)),
+    }
+}
+
+/// returns a recording curl transport or a replay transport for the cassette file `path`
+pub fn transport<P: AsRef<Path>>(mode: Mode, path: P) -> GGRResult<Arc<dyn Transport>> {
+    wrap(mode, path, Arc::new(CurlTransport::new()))
+}
+
+#[test]
+fn test_parse_switch() {
+    assert_eq!(parse_switch("record:tests/fixtures/a.json").unwrap(), (Mode::Record, PathBuf::from("tests/fixtures/a.json")));
+    assert_eq!(parse_switch("replay:C:/a.json").unwrap(), (Mode::Replay, PathBuf::from("C:/a.json")));
+    assert!(parse_switch("replay").is_err());
+    assert!(parse_switch("replay:").is_err());
+    assert!(parse_switch("play:a.json").is_err());
+}
+
+#[test]
+fn test_redact() {
+    use call::CallMethod;
+    use url;
+
+    let request = Request {
+        method: CallMethod::Put,
+        url: url::Url::parse("http://localhost/a/accounts/self/password.http?access_token=abc&pp=0").unwrap(),
+        headers: Vec::new(),
+        body: Some(br#"{"http_password":"secret1","generate":false}"#.to_vec()),
+        authenticated: true,
+    };
+    let recorded = record_request(&request);
+    assert_eq!(recorded.query, Some("access_token=REDACTED&pp=0".into()));
+    assert_eq!(recorded.body.unwrap()["http_password"], "REDACTED");
+
+    let response = CallResponse::new(200,
+                                     vec!("Set-Cookie: GerritAccount=abc\r\n".into(), "Content-Type: application/json\r\n".into()),
+                                     b")]}'\n{\"password\":\"secret2\"}".to_vec());
+    let recorded = record_response(&request, &response);
+    assert_eq!(recorded.headers, vec!("Set-Cookie: REDACTED", "Content-Type: application/json"));
+    assert!(!recorded.body.contains("secret2"));
+    assert!(recorded.body.starts_with(")]}'"));
+
+    // the generated password is a bare json string
+    let response = CallResponse::new(200, Vec::new(), b")]}'\n\"generated1\"".to_vec());
+    assert_eq!(record_response(&request, &response).body, ")]}'\n\"REDACTED\"");
+    let request = Request {
+        method: CallMethod::Get,
+        url: url::Url::parse("http://localhost/a/accounts/self/tokens").unwrap(),
+        headers: Vec::new(),
+        body: None,
+        authenticated: true,
+    };
+    let response = CallResponse::new(200, Vec::new(), b")]}'\n[{\"id\":\"ci\",\"value\":\"tok1\"}]".to_vec());
+    assert!(!record_response(&request, &response).body.contains("tok1"));
+
+    // other endpoints keep their bare strings
+    let request = Request {
+        method: CallMethod::Get,
+        url: url::Url::parse("http://localhost/a/config/server/version").unwrap(),
+        headers: Vec::new(),
+        body: None,
+        authenticated: true,
+    };
+    let response = CallResponse::new(200, Vec::new(), b")]}'\n\"2.13.5\"".to_vec());
+    assert!(record_response(&request, &response).body.contains("2.13.5"));
+}
@@ -5,11 +5,13 @@ use error::GGRError;
 use error::GGRResult;
 use error::GerritError;
 use entities;
-use serde;
+use ids::{ChangeId, RevisionId};
+use query::Query;
 use std;
+use std::collections::HashMap;
 use url;
 
-const ENDPOINT: &'static str = "/a/changes";
+const ENDPOINT: &'static str = "/changes";
 
 /// Interface to retrieve Changes information from gerrit server
 pub struct Changes {
@@ -23,24 +25,28 @@ impl<'de> Changes {
         }
     }
 
-    fn build_query_string<S>(querylist: Option<Vec<S>>) -> String
-        where S: Into<String>  {
+    /// create a new `Changes` instance which sends its requests through `call`
+    pub fn with_call(call: call::Call) -> Changes {
+        Changes {
+            call: call,
+        }
+    }
+
+    /// `Config` endpoint on the same server and transport, used for version checks
+    fn config(&self) -> config::Config {
+        let mut call = self.call.clone();
+        call.set_url_query(None);
+        config::Config::with_call(call)
+    }
+
+    /// builds the `q=` parameter, all elements of `querylist` have to match
+    fn build_query_string<Q>(querylist: Option<Vec<Q>>) -> String
+        where Q: Into<Query>  {
         let mut querystring = String::new();
         if let Some(querylist) = querylist {
             if ! querylist.is_empty() {
-                querystring.push_str("&q=");
-                let mut fragment = String::new();
-                for el in querylist {
-                    fragment.push_str(&el.into()[..]);
-                    fragment.push_str("+");
-                }
-                if let Some(x) = fragment.chars().last() {
-                    if x == '+' {
-                        fragment = fragment.trim_right_matches(x).to_string();
-                    }
-                };
-
-                querystring = format!("{}{}", querystring, fragment);
+                let query = Query::And(querylist.into_iter().map(Into::into).collect());
+                querystring = format!("&q={}", call::encode_query_value(&query.to_string()));
             };
         };
 
@@ -48,16 +54,16 @@ impl<'de> Changes {
         querystring
     }
 
-    fn build_label_string<S>(labellist: Option<Vec<S>>) -> String
-    where S: Into<String> {
+    /// builds the `o=` parameters for additional fields of `ChangeInfo`
+    fn build_label_string(labellist: Option<Vec<entities::ListChangesOption>>) -> String {
         let mut labelstring = String::new();
         if let Some(labellist) = labellist {
             if ! labellist.is_empty() {
                 for label in labellist {
                     if labelstring.is_empty() {
-                        labelstring = format!("o={}", label.into());
+                        labelstring = format!("o={}", label);
                     } else {
-                        labelstring = format!("{}&o={}", labelstring, label.into());
+                        labelstring = format!("{}&o={}", labelstring, label);
                     }
                 }
             }
@@ -67,54 +73,93 @@ impl<'de> Changes {
         labelstring
     }
 
-    /// generic helper function for calling of call object
+    /// converts a notify string (`none`, `owner`, `owner_reviewers` or `all`) to `NotifyHandling`
     ///
-    /// The `desc` parameter is a short description for  error messages, its embedded into 'Problem
-    /// '...' with <DESC>'.
-    /// The call is executed with the `path` parameter and the `httpmethod` with `uploaddata` for
-    /// `Put` and `Post` http methods.
-    fn execute<INPUT,OUTPUT>(c: &Changes, desc: &str, path: &str, httpmethod: call::CallMethod, uploaddata: Option<&INPUT>) -> GGRResult<OUTPUT>
-    where INPUT: serde::Serialize + std::fmt::Debug,
-          OUTPUT: serde::de::DeserializeOwned
-    {
-        match c.call.request(httpmethod, path, uploaddata) {
-            Ok(cr) => {
-                match cr.status() {
-                    200 | 201 | 202 | 203 | 205 => cr.convert::<OUTPUT>(),
-                    /*
-                     * We need handling of 204 returne code. 204 means no body text what we can
-                     * convert. But the converter try it and crashes with a json failure
-                     * "JsonError(ErrorImpl { code: EofWhileParsingValue, line: 1, column: 0 })"
-                     *
-                     * 204 => cr.convert::<OUTPUT>(),
-                     */
-                    status => { Err(GGRError::GerritApiError(GerritError::GerritApi(status, String::from_utf8(cr.get_body().unwrap_or_else(|| "no cause from server".into()))?))) },
-                }
-            },
-            Err(x) => {
-                Err(GGRError::General(format!("Problem '{}' with {}", x, desc)))
-            }
+    /// `owner_reviewer` is accepted too, other values return a `GerritError::InvalidNotify`.
+    fn build_notify(notify: Option<&str>) -> GGRResult<Option<entities::NotifyHandling>> {
+        match notify {
+            None => Ok(None),
+            Some("none") => Ok(Some(entities::NotifyHandling::NONE)),
+            Some("owner") => Ok(Some(entities::NotifyHandling::OWNER)),
+            Some("owner_reviewers") | Some("owner_reviewer") => Ok(Some(entities::NotifyHandling::OWNER_REVIEWERS)),
+            Some("all") => Ok(Some(entities::NotifyHandling::ALL)),
+            Some(x) => Err(GGRError::GerritApiError(GerritError::InvalidNotify(x.into()))),
         }
     }
 
     /// api function 'GET /changes/'
-    pub fn query_changes<S>(&mut self, querylist: Option<Vec<S>>, labellist: Option<Vec<S>>) -> GGRResult<Vec<entities::ChangeInfo>>
-    where S: Into<String> {
-        let mut querystring = format!("pp=0{}", Changes::build_query_string(querylist));
+    ///
+    /// All pages of the result are received and returned at once. Use `query_changes_iter` for
+    /// large results.
+    pub fn query_changes<Q>(&mut self, querylist: Option<Vec<Q>>, labellist: Option<Vec<entities::ListChangesOption>>) -> GGRResult<Vec<entities::ChangeInfo>>
+    where Q: Into<Query> {
+        self.query_changes_iter(querylist, labellist).collect()
+    }
+
+    /// api function 'GET /changes/?q={query}&q={query}...'
+    ///
+    /// Runs several independent queries in one request. Every entry of `queries` is one query,
+    /// its elements are joined like in `query_changes`. The result contains one list of changes
+    /// per query in the same order. Pages are not followed, check `_more_changes` of the last
+    /// change of a list. An empty query returns a `GerritError::QueryEmpty`.
+    pub fn query_changes_multi<Q>(&mut self, queries: Vec<Vec<Q>>, labellist: Option<Vec<entities::ListChangesOption>>) -> GGRResult<Vec<Vec<entities::ChangeInfo>>>
+    where Q: Into<Query> {
+        if queries.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let count = queries.len();
+        let mut querystring = String::from("pp=0");
+        for query in queries {
+            // without its `q=` the results of the other queries would be assigned to wrong queries
+            let query = Changes::build_query_string(Some(query));
+            if query.is_empty() {
+                return Err(GGRError::GerritApiError(GerritError::QueryEmpty));
+            }
+            querystring.push_str(&query);
+        }
         let labelstring = Changes::build_label_string(labellist);
         if ! labelstring.is_empty() {
             querystring = format!("{}&{}", querystring, labelstring);
         }
 
-        querystring = querystring.replace("//", "/");
-        querystring = querystring.replace("//", "/");
-        querystring = querystring.replace("//", "/");
-
         self.call.set_url_query(Some(&querystring));
 
         let path = format!("{}/", ENDPOINT);
 
-        Changes::execute::<(),Vec<entities::ChangeInfo>>(self, "query change", &path, call::CallMethod::Get, None)
+        let out = if count == 1 {
+            // gerrit returns a flat list for a single query
+            self.call.execute::<(),Vec<entities::ChangeInfo>>("query changes", &path, call::CallMethod::Get, None)
+                .map(|cis| vec!(cis))
+        } else {
+            self.call.execute::<(),Vec<Vec<entities::ChangeInfo>>>("query changes", &path, call::CallMethod::Get, None)
+        };
+        self.call.set_url_query(None);
+        out
+    }
+
+    /// api function 'GET /changes/' as iterator
+    ///
+    /// The returned `ChangeQuery` receives one page after another, following `_more_changes` of
+    /// the last change of a page. Only one page is held in memory.
+    pub fn query_changes_iter<Q>(&mut self, querylist: Option<Vec<Q>>, labellist: Option<Vec<entities::ListChangesOption>>) -> ChangeQuery
+    where Q: Into<Query> {
+        let mut querystring = format!("pp=0{}", Changes::build_query_string(querylist));
+        let labelstring = Changes::build_label_string(labellist);
+        if ! labelstring.is_empty() {
+            querystring = format!("{}&{}", querystring, labelstring);
+        }
+
+        ChangeQuery {
+            changes: self,
+            querystring: querystring,
+            start: 0,
+            limit: None,
+            pagesize: None,
+            returned: 0,
+            page: Vec::new().into_iter(),
+            more: true,
+        }
     }
 
     /// api function 'POST /changes'
@@ -125,58 +170,43 @@ impl<'de> Changes {
             return Err(GGRError::GerritApiError(GerritError::ChangeInputProblem));
         }
 
-        let config = config::Config::new(self.call.get_base());
-        if let Err(x) = config.check_version("POST /changes/".into(), "2.10.0".into()) {
-            return Err(x);
-        }
+        self.config().require_version("POST /changes/", "2.10.0")?;
 
-        Changes::execute(self, "change create", ENDPOINT, call::CallMethod::Post, Some(&ci))
+        self.call.execute("change create", ENDPOINT, call::CallMethod::Post, Some(&ci))
     }
 
     /// api function 'GET /changes/{change-id}'
-    pub fn get_change(&mut self, changeid: &str, features: Option<Vec<&str>>) -> GGRResult<entities::ChangeInfo> {
-        if changeid.is_empty() {
-            return Err(GGRError::GerritApiError(GerritError::ChangeIDEmpty));
-        }
-
+    pub fn get_change(&mut self, changeid: &ChangeId, features: Option<Vec<entities::ListChangesOption>>) -> GGRResult<entities::ChangeInfo> {
         let query = Changes::build_label_string(features);
 
-        let path = format!("{}/{}", ENDPOINT, changeid);
+        let path = format!("{}/{}", ENDPOINT, changeid.path_segment());
 
         self.call.set_url_query(Some(&query));
 
-        Changes::execute::<(),entities::ChangeInfo>(self, "get change", &path, call::CallMethod::Get, None)
+        self.call.execute::<(),entities::ChangeInfo>("get change", &path, call::CallMethod::Get, None)
     }
 
     /// api function 'GET /changes/{change-id}/detail'
-    pub fn get_change_detail(&self, changeid: &str) -> GGRResult<entities::ChangeInfo> {
-        if changeid.is_empty() {
-            return Err(GGRError::GerritApiError(GerritError::ChangeIDEmpty));
-        }
-
-        let path = format!("{}/{}/detail", ENDPOINT, changeid);
+    pub fn get_change_detail(&self, changeid: &ChangeId) -> GGRResult<entities::ChangeInfo> {
+        let path = format!("{}/{}/detail", ENDPOINT, changeid.path_segment());
 
-        Changes::execute::<(),entities::ChangeInfo>(self, "get change detail", &path, call::CallMethod::Get, None)
+        self.call.execute::<(),entities::ChangeInfo>("get change detail", &path, call::CallMethod::Get, None)
     }
 
     /// api function `GET /changes/{change-id}/reviewers/'
-    pub fn get_reviewers(&self, changeid: &str) -> GGRResult<Vec<entities::ReviewerInfo>> {
-        if changeid.is_empty() {
-            return Err(GGRError::GerritApiError(GerritError::ChangeIDEmpty));
-        }
-
-        let path = format!("{}/{}/reviewers/", ENDPOINT, changeid);
+    pub fn get_reviewers(&self, changeid: &ChangeId) -> GGRResult<Vec<entities::ReviewerInfo>> {
+        let path = format!("{}/{}/reviewers/", ENDPOINT, changeid.path_segment());
 
-        Changes::execute::<(),Vec<entities::ReviewerInfo>>(self, "receiving reviewer list", &path, call::CallMethod::Get, None)
+        self.call.execute::<(),Vec<entities::ReviewerInfo>>("receiving reviewer list", &path, call::CallMethod::Get, None)
     }
 
     /// api function 'POST /changes/{change-id}/reviewers'
-    pub fn add_reviewer(&self, changeid: &str, reviewer: &str) -> GGRResult<entities::AddReviewerResult> {
-        if changeid.is_empty() || reviewer.is_empty() {
-            return Err(GGRError::GerritApiError(GerritError::GetReviewerListProblem("changeid or reviewer is empty".into())));
+    pub fn add_reviewer(&self, changeid: &ChangeId, reviewer: &str) -> GGRResult<entities::AddReviewerResult> {
+        if reviewer.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::GetReviewerListProblem("reviewer is empty".into())));
         }
 
-        let path = format!("{}/{}/reviewers", ENDPOINT, changeid);
+        let path = format!("{}/{}/reviewers", ENDPOINT, changeid.path_segment());
 
         let reviewerinput = entities::ReviewerInput {
                 reviewer: reviewer.into(),
@@ -184,86 +214,301 @@ impl<'de> Changes {
                 state: None,
         };
 
-        Changes::execute::<&entities::ReviewerInput,entities::AddReviewerResult>(self, "add reviewer", &path, call::CallMethod::Post, Some(&&reviewerinput))
+        self.call.execute::<&entities::ReviewerInput,entities::AddReviewerResult>("add reviewer", &path, call::CallMethod::Post, Some(&&reviewerinput))
     }
 
     /// api function 'DELETE /changes/{change-id}/reviewers/{account-id}'
-    pub fn delete_reviewer(&self, changeid: &str, reviewer: &str) -> GGRResult<()> {
-        if changeid.is_empty() || reviewer.is_empty() {
-            return Err(GGRError::GerritApiError(GerritError::GetReviewerListProblem("changeid or reviewer is empty".into())));
+    pub fn delete_reviewer(&self, changeid: &ChangeId, reviewer: &str) -> GGRResult<()> {
+        if reviewer.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::GetReviewerListProblem("reviewer is empty".into())));
         }
 
-        let path = format!("{}/{}/reviewers/{}", ENDPOINT, changeid, reviewer);
+        let path = format!("{}/{}/reviewers/{}", ENDPOINT, changeid.path_segment(), call::encode_path_segment(reviewer));
 
-        Changes::execute::<(),()>(self, "deleting reviewer", &path, call::CallMethod::Delete, None)
+        self.call.execute_nocontent::<()>("deleting reviewer", &path, call::CallMethod::Delete, None)
     }
 
     /// api function 'POST /changes/{change-id}/abandon'
     ///
     /// notify is one of `none`, `owner`, `owner_reviewers` or `all`.
-    pub fn abandon_change(&self, changeid: &str, message: Option<&str>, notify: Option<&str>) -> GGRResult<entities::ChangeInfo> {
-        if changeid.is_empty() {
-            return Err(GGRError::GerritApiError(GerritError::ChangeIDEmpty));
+    pub fn abandon_change(&self, changeid: &ChangeId, message: Option<&str>, notify: Option<&str>) -> GGRResult<entities::ChangeInfo> {
+        let path = format!("{}/{}/abandon", ENDPOINT, changeid.path_segment());
+
+        let abandoninput = entities::AbandonInput {
+                message: message.map(|s| s.to_string()),
+                notify: Changes::build_notify(notify)?,
+        };
+
+        self.call.execute::<&entities::AbandonInput,entities::ChangeInfo>("abandon change", &path, call::CallMethod::Post, Some(&&abandoninput))
+    }
+
+    /// api function 'POST /changes/{change-id}/restore'
+    pub fn restore_change(&self, changeid: &ChangeId, message: Option<&str>) -> GGRResult<entities::ChangeInfo> {
+        let path = format!("{}/{}/restore", ENDPOINT, changeid.path_segment());
+
+        let restoreinput = entities::RestoreInput {
+            message: message.map(|s| s.to_string()),
+        };
+
+        self.call.execute::<&entities::RestoreInput,entities::ChangeInfo>("restore change", &path, call::CallMethod::Post, Some(&&restoreinput))
+    }
+
+    /// api function 'POST /changes/{change-id}/submit'
+    ///
+    /// notify is one of `none`, `owner`, `owner_reviewers` or `all`. A not submittable change
+    /// returns a `GerritError::Conflict`.
+    pub fn submit_change(&self, changeid: &ChangeId, on_behalf_of: Option<&str>, notify: Option<&str>) -> GGRResult<entities::ChangeInfo> {
+        let path = format!("{}/{}/submit", ENDPOINT, changeid.path_segment());
+
+        let submitinput = entities::SubmitInput {
+            on_behalf_of: on_behalf_of.map(|s| s.to_string()),
+            notify: Changes::build_notify(notify)?,
+        };
+
+        self.call.execute::<&entities::SubmitInput,entities::ChangeInfo>("submit change", &path, call::CallMethod::Post, Some(&&submitinput))
+    }
+
+    /// api function 'POST /changes/{change-id}/rebase'
+    ///
+    /// Without `base` the change is rebased on top of the target branch or the parent change. A
+    /// not possible rebase (eg. merge conflicts) returns a `GerritError::Conflict`.
+    pub fn rebase_change(&self, changeid: &ChangeId, base: Option<&str>) -> GGRResult<entities::ChangeInfo> {
+        let path = format!("{}/{}/rebase", ENDPOINT, changeid.path_segment());
+
+        let rebaseinput = entities::RebaseInput {
+            base: base.map(|s| s.to_string()),
+        };
+
+        self.call.execute::<&entities::RebaseInput,entities::ChangeInfo>("rebase change", &path, call::CallMethod::Post, Some(&&rebaseinput))
+    }
+
+    /// api function 'POST /changes/{change-id}/revisions/{revision-id}/cherrypick'
+    ///
+    /// Returns the newly created change on the `destination` branch. A cherry-pick with
+    /// conflicts returns a `GerritError::Conflict`.
+    pub fn cherrypick_revision(&self, changeid: &ChangeId, revisionid: &RevisionId, destination: &str, message: Option<&str>) -> GGRResult<entities::ChangeInfo> {
+        if destination.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::BranchNameEmpty));
         }
 
-        let path = format!("{}/{}/abandon", ENDPOINT, changeid);
+        let path = format!("{}/{}/revisions/{}/cherrypick", ENDPOINT, changeid.path_segment(), revisionid.path_segment());
 
-        let notify = match notify {
-            Some(notify) => {
-                match notify {
-                    "all" => Some(entities::AbandonInputNotify::ALL),
-                    "owner" => Some(entities::AbandonInputNotify::OWNER),
-                    "owner_reviewer" => Some(entities::AbandonInputNotify::OWNER_REVIEWERS),
-                    _ => Some(entities::AbandonInputNotify::NONE),
-                }
-            },
-            None => None
+        let cherrypickinput = entities::CherryPickInput {
+            message: message.map(|s| s.to_string()),
+            destination: destination.into(),
+            parent: None,
+            notify: None,
         };
 
-        let abandoninput = entities::AbandonInput {
-                message: message.map(|s| s.to_string()),
-                notify: notify,
+        self.call.execute::<&entities::CherryPickInput,entities::ChangeInfo>("cherry-pick revision", &path, call::CallMethod::Post, Some(&&cherrypickinput))
+    }
+
+    /// api function 'POST /changes/{change-id}/revert'
+    ///
+    /// Returns the newly created revert change. Only merged changes can be reverted, all others
+    /// return a `GerritError::Conflict`.
+    pub fn revert_change(&self, changeid: &ChangeId, message: Option<&str>) -> GGRResult<entities::ChangeInfo> {
+        let path = format!("{}/{}/revert", ENDPOINT, changeid.path_segment());
+
+        let revertinput = entities::RevertInput {
+            message: message.map(|s| s.to_string()),
         };
 
-        Changes::execute::<&entities::AbandonInput,entities::ChangeInfo>(self, "abandon change", &path, call::CallMethod::Post, Some(&&abandoninput))
+        self.call.execute::<&entities::RevertInput,entities::ChangeInfo>("revert change", &path, call::CallMethod::Post, Some(&&revertinput))
     }
 
-    /// api function 'POST /changes/{change-id}/restore'
-    pub fn restore_change(&self, changeid: &str, message: Option<&str>) -> GGRResult<entities::ChangeInfo> {
-        if changeid.is_empty() {
-            return Err(GGRError::GerritApiError(GerritError::ChangeIDEmpty));
+    /// api function 'POST /changes/{change-id}/move'
+    ///
+    /// A closed change or a change with the same destination branch returns
+    /// a `GerritError::Conflict`.
+    ///
+    /// V02.13
+    pub fn move_change(&self, changeid: &ChangeId, destination_branch: &str, message: Option<&str>) -> GGRResult<entities::ChangeInfo> {
+        if destination_branch.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::BranchNameEmpty));
         }
 
-        let path = format!("{}/{}/restore", ENDPOINT, changeid);
+        self.config().require_version("POST /changes/{change-id}/move", "2.13.0")?;
 
-        let restoreinput = entities::RestoreInput {
+        let path = format!("{}/{}/move", ENDPOINT, changeid.path_segment());
+
+        let moveinput = entities::MoveInput {
+            destination_branch: destination_branch.into(),
             message: message.map(|s| s.to_string()),
         };
 
-        Changes::execute::<&entities::RestoreInput,entities::ChangeInfo>(self, "restore change", &path, call::CallMethod::Post, Some(&&restoreinput))
+        self.call.execute::<&entities::MoveInput,entities::ChangeInfo>("move change", &path, call::CallMethod::Post, Some(&&moveinput))
     }
 
     /// api function 'POST /changes/{change-id}/revisions/{revision-id}/review'
-    pub fn set_review(&self, changeid: &str, revisionid: &str, message: Option<&str>, labels: Option<entities::ReviewInfo>) -> GGRResult<entities::ReviewInfo> {
-        if changeid.is_empty() || revisionid.is_empty() {
+    ///
+    /// Posts a message, votes and inline comments in one review.
+    pub fn set_review(&self, changeid: &ChangeId, revisionid: &RevisionId, review: &entities::ReviewInput) -> GGRResult<entities::ReviewInfo> {
+        let path = format!("{}/{}/revisions/{}/review", ENDPOINT, changeid.path_segment(), revisionid.path_segment());
+
+        self.call.execute::<&entities::ReviewInput,entities::ReviewInfo>("set review", &path, call::CallMethod::Post, Some(&review))
+    }
+
+    /// api function 'GET /changes/{change-id}/comments'
+    ///
+    /// Returns the published comments of all revisions as a map of file paths to comments.
+    pub fn list_comments(&self, changeid: &ChangeId) -> GGRResult<HashMap<String, Vec<entities::CommentInfo>>> {
+        let path = format!("{}/{}/comments", ENDPOINT, changeid.path_segment());
+
+        self.call.execute::<(),HashMap<String, Vec<entities::CommentInfo>>>("list comments", &path, call::CallMethod::Get, None)
+    }
+
+    /// api function 'GET /changes/{change-id}/revisions/{revision-id}/comments/'
+    pub fn list_revision_comments(&self, changeid: &ChangeId, revisionid: &RevisionId) -> GGRResult<HashMap<String, Vec<entities::CommentInfo>>> {
+        let path = format!("{}/{}/revisions/{}/comments/", ENDPOINT, changeid.path_segment(), revisionid.path_segment());
+
+        self.call.execute::<(),HashMap<String, Vec<entities::CommentInfo>>>("list revision comments", &path, call::CallMethod::Get, None)
+    }
+
+    /// api function 'GET /changes/{change-id}/revisions/{revision-id}/drafts/'
+    pub fn list_drafts(&self, changeid: &ChangeId, revisionid: &RevisionId) -> GGRResult<HashMap<String, Vec<entities::CommentInfo>>> {
+        let path = format!("{}/{}/revisions/{}/drafts/", ENDPOINT, changeid.path_segment(), revisionid.path_segment());
+
+        self.call.execute::<(),HashMap<String, Vec<entities::CommentInfo>>>("list drafts", &path, call::CallMethod::Get, None)
+    }
+
+    /// api function 'PUT /changes/{change-id}/revisions/{revision-id}/drafts'
+    ///
+    /// The `path` of the `CommentInput` is mandatory.
+    pub fn create_draft(&self, changeid: &ChangeId, revisionid: &RevisionId, comment: &entities::CommentInput) -> GGRResult<entities::CommentInfo> {
+        let path = format!("{}/{}/revisions/{}/drafts", ENDPOINT, changeid.path_segment(), revisionid.path_segment());
+
+        self.call.execute::<&entities::CommentInput,entities::CommentInfo>("create draft", &path, call::CallMethod::Put, Some(&comment))
+    }
+
+    /// api function 'PUT /changes/{change-id}/revisions/{revision-id}/drafts/{draft-id}'
+    pub fn update_draft(&self, changeid: &ChangeId, revisionid: &RevisionId, draftid: &str, comment: &entities::CommentInput) -> GGRResult<entities::CommentInfo> {
+        if draftid.is_empty() {
             return Err(GGRError::GerritApiError(GerritError::ChangeIDEmpty));
         }
 
-        let path = format!("{}/{}/revisions/{}/review", ENDPOINT, changeid, revisionid);
+        let path = format!("{}/{}/revisions/{}/drafts/{}", ENDPOINT, changeid.path_segment(), revisionid.path_segment(), call::encode_path_segment(draftid));
 
-        use std::collections::HashMap;
+        self.call.execute::<&entities::CommentInput,entities::CommentInfo>("update draft", &path, call::CallMethod::Put, Some(&comment))
+    }
 
-        #[derive(Serialize, Debug)]
-        struct Review {
-            message: Option<String>,
-            labels: HashMap<String, i8>,
-        };
+    /// api function 'DELETE /changes/{change-id}/revisions/{revision-id}/drafts/{draft-id}'
+    pub fn delete_draft(&self, changeid: &ChangeId, revisionid: &RevisionId, draftid: &str) -> GGRResult<()> {
+        if draftid.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::ChangeIDEmpty));
+        }
 
-        let review = Review {
-            message: message.map(|s| s.to_string()),
-            labels: labels.unwrap_or(entities::ReviewInfo{ labels: HashMap::new() }).labels,
+        let path = format!("{}/{}/revisions/{}/drafts/{}", ENDPOINT, changeid.path_segment(), revisionid.path_segment(), call::encode_path_segment(draftid));
+
+        self.call.execute_nocontent::<()>("delete draft", &path, call::CallMethod::Delete, None)
+    }
+}
+
+/// Iterator over the changes of a query
+///
+/// Created with `Changes::query_changes_iter`. Pages are received on demand with `S=` (start) and
+/// `n=` (page size) until the server reports no `_more_changes` or `limit` is reached. A failed
+/// request is returned as `Err` and ends the iteration.
+pub struct ChangeQuery<'a> {
+    changes: &'a mut Changes,
+    querystring: String,
+    start: usize,
+    limit: Option<usize>,
+    pagesize: Option<usize>,
+    returned: usize,
+    page: std::vec::IntoIter<entities::ChangeInfo>,
+    more: bool,
+}
+
+impl<'a> ChangeQuery<'a> {
+    /// skip the first `start` changes of the result
+    pub fn start(mut self, start: usize) -> Self {
+        self.start = start;
+        self
+    }
+
+    /// return at most `limit` changes
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// receive `pagesize` changes per request, without the server default limit is used
+    pub fn page_size(mut self, pagesize: usize) -> Self {
+        self.pagesize = Some(pagesize);
+        self
+    }
+
+    /// receive the next page from the server
+    fn next_page(&mut self) -> GGRResult<()> {
+        let mut querystring = format!("{}&S={}", self.querystring, self.start);
+
+        let remaining = self.limit.map(|limit| limit - self.returned);
+        let pagesize = match (self.pagesize, remaining) {
+            (Some(pagesize), Some(remaining)) => Some(std::cmp::min(pagesize, remaining)),
+            (pagesize, remaining) => pagesize.or(remaining),
         };
+        if let Some(pagesize) = pagesize {
+            querystring.push_str(&format!("&n={}", pagesize));
+        }
+
+        self.changes.call.set_url_query(Some(&querystring));
+
+        let path = format!("{}/", ENDPOINT);
+
+        let out = self.changes.call.execute::<(),Vec<entities::ChangeInfo>>("query change", &path, call::CallMethod::Get, None);
+        self.changes.call.set_url_query(None);
+        let page = out?;
+
+        // `_more_changes` is set on the last change, on older servers on the first one
+        self.more = page.last().and_then(|ci| ci._more_changes).unwrap_or(false)
+            || page.first().and_then(|ci| ci._more_changes).unwrap_or(false);
+        if page.is_empty() {
+            self.more = false;
+        }
+
+        self.start += page.len();
+        self.page = page.into_iter();
+
+        Ok(())
+    }
+}
+
+impl<'a> Iterator for ChangeQuery<'a> {
+    type Item = GGRResult<entities::ChangeInfo>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.limit.map_or(false, |limit| self.returned >= limit) {
+                return None;
+            }
+
+            if let Some(ci) = self.page.next() {
+                self.returned += 1;
+                return Some(Ok(ci));
+            }
+
+            if !self.more {
+                return None;
+            }
+
+            if let Err(x) = self.next_page() {
+                self.more = false;
+                return Some(Err(x));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_build_notify() {
+    assert_eq!(Changes::build_notify(None).unwrap(), None);
+    assert_eq!(Changes::build_notify(Some("owner_reviewers")).unwrap(), Some(entities::NotifyHandling::OWNER_REVIEWERS));
+    assert_eq!(Changes::build_notify(Some("owner_reviewer")).unwrap(), Some(entities::NotifyHandling::OWNER_REVIEWERS));
+    assert_eq!(Changes::build_notify(Some("none")).unwrap(), Some(entities::NotifyHandling::NONE));
 
-        Changes::execute::<&Review,entities::ReviewInfo>(self, "set review", &path, call::CallMethod::Post, Some(&&review))
+    match Changes::build_notify(Some("reviewers")) {
+        Err(GGRError::GerritApiError(GerritError::InvalidNotify(ref x))) => assert_eq!(x, "reviewers"),
+        x => panic!("expected an invalid notify, got {:?}", x),
     }
 }
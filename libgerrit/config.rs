@@ -1,13 +1,36 @@
 
 use call;
+use entities;
 use error::GGRError;
 use error::GGRResult;
 use error::GerritError;
 use semver;
+use std::sync::Mutex;
 use url;
 
 const ENDPOINT: &'static str = "/config/server";
 
+/// Server version and server info, fetched once and shared by all endpoints of a `Gerrit` object
+/// and its clones
+#[derive(Debug, Default)]
+pub struct ServerCache {
+    version: Mutex<Option<String>>,
+    info: Mutex<Option<entities::ServerInfo>>,
+}
+
+impl ServerCache {
+    /// create an empty cache
+    pub fn new() -> ServerCache {
+        ServerCache::default()
+    }
+
+    /// forget the cached values, the next access fetches them again from the server
+    pub fn clear(&self) {
+        *self.version.lock().unwrap_or_else(|x| x.into_inner()) = None;
+        *self.info.lock().unwrap_or_else(|x| x.into_inner()) = None;
+    }
+}
+
 pub struct Config {
     call: call::Call,
 }
@@ -20,42 +43,136 @@ impl Config {
         }
     }
 
-    /// returns the gerrit version
+    /// create a new `Config` instance which sends its requests through `call`
+    pub fn with_call(call: call::Call) -> Config {
+        Config {
+            call: call,
+        }
+    }
+
+    /// api function 'GET /config/server/version'
+    ///
+    /// The version is fetched once and then returned from the `ServerCache` of the call object.
     pub fn get_version(&self) -> GGRResult<String> {
+        let cache = self.call.get_server_cache();
+        let mut version = cache.version.lock().unwrap_or_else(|x| x.into_inner());
+        if let Some(ref version) = *version {
+            return Ok(version.clone());
+        }
+
         let path = format!("{}/version", ENDPOINT);
-        match self.call.get(&path) {
-            Ok(cr) => {
-                if cr.ok() {
-                    cr.convert::<String>()
-                } else {
-                    Err(GGRError::GerritApiError(GerritError::GerritApi(cr.status(), String::from_utf8(cr.get_body().unwrap()).unwrap())))
-                }
-            },
-            Err(x) => {
-                Err(GGRError::General(format!("call problem with: {} ({})", path, x)))
-            }
+        let fetched = self.call.execute::<(),String>("get version", &path, call::CallMethod::Get, None)?;
+
+        *version = Some(fetched.clone());
+        Ok(fetched)
+    }
+
+    /// api function 'GET /config/server/info'
+    ///
+    /// V02.10
+    ///
+    /// The info is fetched once and then returned from the `ServerCache` of the call object.
+    pub fn get_server_info(&self) -> GGRResult<entities::ServerInfo> {
+        let cache = self.call.get_server_cache();
+        let mut info = cache.info.lock().unwrap_or_else(|x| x.into_inner());
+        if let Some(ref info) = *info {
+            return Ok(info.clone());
         }
+
+        let path = format!("{}/info", ENDPOINT);
+        let fetched = self.call.execute::<(),entities::ServerInfo>("get server info", &path, call::CallMethod::Get, None)?;
+
+        *info = Some(fetched.clone());
+        Ok(fetched)
     }
 
-    /// convenience function to check version
-    pub fn check_version(&self, desc: String, since: String) -> GGRResult<()> {
-        if let Ok(version) = self.get_version() {
-            if semver::Version::parse(&version) < semver::Version::parse(&since) {
-                return Err(GGRError::GerritApiError(GerritError::UnsupportedVersion(desc.into(), version.into(), since.into())));
-            }
-        } else {
-            warn!("server version seems not supported, continuing");
+    /// checks that the server has at least version `since` for the api function `function`
+    ///
+    /// Returns `GerritError::UnsupportedVersion` for older servers and
+    /// `GerritError::UnparsableVersion` if the version of the server is not understood.
+    pub fn require_version(&self, function: &str, since: &str) -> GGRResult<()> {
+        let version = self.get_version()?;
+
+        let current = match parse_version(&version) {
+            Some(current) => current,
+            None => return Err(GGRError::GerritApiError(GerritError::UnparsableVersion(function.into(), version))),
+        };
+        let since_version = parse_version(since).expect("minimum version of an api function is not parsable");
+
+        if current < since_version {
+            return Err(GGRError::GerritApiError(GerritError::UnsupportedVersion(function.into(), version, since.into())));
         }
 
         Ok(())
     }
 }
 
+/// parses a gerrit version like `2.13.5`, `2.14` or `2.13.5-2617-gba50ae91fd`
+///
+/// Only the leading numbers are used, missing minor and patch numbers are zero.
+fn parse_version(version: &str) -> Option<semver::Version> {
+    let numbers: String = version.trim()
+        .chars()
+        .take_while(|c| c.is_digit(10) || *c == '.')
+        .collect();
+
+    let mut parts = numbers.split('.').filter(|x| !x.is_empty()).map(|x| x.parse::<u64>());
+    let major = match parts.next() {
+        Some(Ok(major)) => major,
+        _ => return None,
+    };
+    let minor = parts.next().and_then(|x| x.ok()).unwrap_or(0);
+    let patch = parts.next().and_then(|x| x.ok()).unwrap_or(0);
+
+    Some(semver::Version {
+        major: major,
+        minor: minor,
+        patch: patch,
+        pre: Vec::new(),
+        build: Vec::new(),
+    })
+}
+
 #[test]
 fn test_get_version() {
+    use call::CallResponse;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use transport::{Request, Transport};
+
+    struct VersionTransport(AtomicUsize);
+
+    impl Transport for VersionTransport {
+        fn send(&self, request: &Request) -> GGRResult<CallResponse> {
+            assert_eq!(request.method, call::CallMethod::Get);
+            assert_eq!(request.url.path(), "/a/config/server/version");
+            self.0.fetch_add(1, Ordering::SeqCst);
+
+            Ok(CallResponse::new(200, Vec::new(), b")]}'\n\"2.13.5\"".to_vec()))
+        }
+    }
+
+    let transport = Arc::new(VersionTransport(AtomicUsize::new(0)));
     let config = Config {
-        call: call::Call::new(&url::Url::parse("http://localhost:8080").unwrap()),
+        call: call::Call::with_transport(&url::Url::parse("http://localhost:8080").unwrap(), transport.clone()),
     };
 
     assert_eq!("2.13.5", config.get_version().unwrap());
+
+    // the version is cached
+    assert!(config.require_version("POST /changes/{change-id}/move", "2.13.0").is_ok());
+    match config.require_version("GET /introduced/in/2.14", "2.14.0") {
+        Err(GGRError::GerritApiError(GerritError::UnsupportedVersion(..))) => {},
+        x => panic!("expected an unsupported version, got {:?}", x),
+    }
+    assert_eq!(transport.0.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_parse_version() {
+    assert_eq!(parse_version("2.13.5"), semver::Version::parse("2.13.5").ok());
+    assert_eq!(parse_version("2.14"), semver::Version::parse("2.14.0").ok());
+    assert_eq!(parse_version("2.13.5-2617-gba50ae91fd"), semver::Version::parse("2.13.5").ok());
+    assert_eq!(parse_version("3.4.1"), semver::Version::parse("3.4.1").ok());
+    assert_eq!(parse_version("(dev)"), None);
 }
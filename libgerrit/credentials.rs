@@ -0,0 +1,652 @@
+//! Credentials for REST calls and git fetches
+//!
+//! A `CredentialProvider` returns the `Credential` for a url. Providers exist for `~/.netrc`,
+//! `~/.gitcookies` (or the `http.cookiefile` of git), the credential helpers of git, an explicit
+//! username and http password and a bearer token. `ChainProvider` asks several providers in
+//! order, `default_provider` is the chain used by `CurlTransport::new`:
+//!
+//! 1. bearer token from `GGR_TOKEN`
+//! 2. username and http password from `GGR_USERNAME` and `GGR_HTTP_PASSWORD`
+//! 3. `~/.netrc`
+//! 4. `git credential fill`, without prompts
+//! 5. gitcookies
+//!
+//! Credentials which the server accepts are approved and credentials which the server rejects
+//! with `401` are rejected, so a git credential helper stores new and forgets stale passwords.
+//! `Git2Credentials` provides the credential callback of git2.
+
+use std::env;
+use std::fmt;
+use std::fs::File;
+use std::cell::RefCell;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+
+use git2;
+use netrc;
+use url;
+
+use error::GGRError;
+use error::GGRResult;
+
+/// environment variable with a bearer token
+pub const ENV_TOKEN: &'static str = "GGR_TOKEN";
+/// environment variable with the username
+pub const ENV_USERNAME: &'static str = "GGR_USERNAME";
+/// environment variable with the http password
+pub const ENV_HTTP_PASSWORD: &'static str = "GGR_HTTP_PASSWORD";
+
+/// A credential for one server
+#[derive(Clone, PartialEq)]
+pub enum Credential {
+    /// username and http password, sent with digest or basic authentication
+    UserPassword {
+        /// username of the account
+        username: String,
+        /// http password of the account
+        password: String,
+    },
+    /// value of a `Cookie` header like `o=git-jane.example.com=1/abc`
+    Cookie(String),
+    /// token sent as `Authorization: Bearer <token>`
+    BearerToken(String),
+}
+
+impl fmt::Debug for Credential {
+    /// secrets are never printed
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Credential::UserPassword { ref username, .. } => write!(f, "UserPassword {{ username: {:?}, password: \"***\" }}", username),
+            Credential::Cookie(_) => write!(f, "Cookie(\"***\")"),
+            Credential::BearerToken(_) => write!(f, "BearerToken(\"***\")"),
+        }
+    }
+}
+
+/// Source of credentials
+pub trait CredentialProvider: Send + Sync {
+    /// returns the credential for `url` or `None` if this provider has none
+    fn credential(&self, url: &url::Url) -> GGRResult<Option<Credential>>;
+
+    /// returns the credential for `url` without asking the user, e.g. on the terminal
+    ///
+    /// `call::AuthMode::Auto` decides with this between anonymous and authenticated requests. The
+    /// default is `credential`.
+    fn stored_credential(&self, url: &url::Url) -> GGRResult<Option<Credential>> {
+        self.credential(url)
+    }
+
+    /// the server accepted `credential` for `url`
+    fn approve(&self, _url: &url::Url, _credential: &Credential) {}
+
+    /// the server rejected `credential` for `url` with `401`
+    fn reject(&self, _url: &url::Url, _credential: &Credential) {}
+}
+
+/// home directory of the current user
+fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME").map(PathBuf::from)
+}
+
+/// Credentials from a netrc file
+pub struct NetrcProvider {
+    path: PathBuf,
+}
+
+impl NetrcProvider {
+    /// credentials of the netrc file `path`
+    pub fn new<P: Into<PathBuf>>(path: P) -> NetrcProvider {
+        NetrcProvider {
+            path: path.into(),
+        }
+    }
+
+    /// credentials of `~/.netrc`
+    pub fn from_home() -> NetrcProvider {
+        NetrcProvider::new(home_dir().unwrap_or_default().join(".netrc"))
+    }
+}
+
+impl CredentialProvider for NetrcProvider {
+    fn credential(&self, url: &url::Url) -> GGRResult<Option<Credential>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+
+        let file = File::open(&self.path)?;
+        let netrc = netrc::Netrc::parse(BufReader::new(file))
+            .map_err(|x| GGRError::General(format!("problem with netrc file {:?}: {:?}", self.path, x)))?;
+
+        let host = url.host_str().unwrap_or("");
+        let machine = netrc.hosts.iter()
+            .find(|&&(ref machinehost, _)| machinehost == host)
+            .map(|&(_, ref machine)| machine)
+            .or_else(|| netrc.default.as_ref());
+
+        Ok(machine.and_then(|machine| {
+            machine.password.as_ref().map(|password| {
+                debug!("use netrc credentials of '{}' for {}", machine.login, host);
+                Credential::UserPassword {
+                    username: machine.login.clone(),
+                    password: password.clone(),
+                }
+            })
+        }))
+    }
+}
+
+/// Credentials from a gitcookies file in the netscape cookie format
+pub struct GitCookiesProvider {
+    /// `None` for the cookie file of the git configuration
+    path: Option<PathBuf>,
+}
+
+impl GitCookiesProvider {
+    /// credentials of the cookie file `path`
+    pub fn new<P: Into<PathBuf>>(path: P) -> GitCookiesProvider {
+        GitCookiesProvider {
+            path: Some(path.into()),
+        }
+    }
+
+    /// credentials of the `http.cookiefile` from the git configuration or of `~/.gitcookies`
+    ///
+    /// The git configuration is read on every lookup.
+    pub fn from_git_config() -> GitCookiesProvider {
+        GitCookiesProvider {
+            path: None,
+        }
+    }
+
+    /// path of the cookie file
+    fn path(&self) -> PathBuf {
+        if let Some(ref path) = self.path {
+            return path.clone();
+        }
+
+        let configured = Command::new("git")
+            .args(&["config", "--path", "--get", "http.cookiefile"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .filter(|path| !path.is_empty());
+
+        match configured {
+            Some(path) => PathBuf::from(path),
+            None => home_dir().unwrap_or_default().join(".gitcookies"),
+        }
+    }
+}
+
+impl CredentialProvider for GitCookiesProvider {
+    fn credential(&self, url: &url::Url) -> GGRResult<Option<Credential>> {
+        let path = self.path();
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let file = File::open(&path)?;
+        let cookies = cookies_for_url(BufReader::new(file), url)?;
+
+        if cookies.is_empty() {
+            Ok(None)
+        } else {
+            debug!("use gitcookies for {}", url.host_str().unwrap_or(""));
+            Ok(Some(Credential::Cookie(cookies.join("; "))))
+        }
+    }
+}
+
+/// returns all `name=value` cookies of a netscape cookie file which match host and path of `url`
+fn cookies_for_url<R: BufRead>(reader: R, url: &url::Url) -> GGRResult<Vec<String>> {
+    let host = url.host_str().unwrap_or("");
+    let mut cookies = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        // curl marks http only cookies with this prefix, all other `#` lines are comments
+        let line = line.trim_start_matches("#HttpOnly_");
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 7 {
+            continue;
+        }
+
+        let domain = fields[0].trim_start_matches('.');
+        let subdomains = fields[1] == "TRUE" || fields[0].starts_with('.');
+        let host_matches = host == domain || (subdomains && host.ends_with(&format!(".{}", domain)));
+        let secure_matches = fields[3] != "TRUE" || url.scheme() == "https";
+
+        if host_matches && secure_matches && url.path().starts_with(fields[2]) {
+            cookies.push(format!("{}={}", fields[5], fields[6]));
+        }
+    }
+
+    Ok(cookies)
+}
+
+/// An explicit username and http password
+pub struct UserPasswordProvider {
+    username: String,
+    password: String,
+}
+
+impl UserPasswordProvider {
+    /// credentials with `username` and `password` for all urls
+    pub fn new<S: Into<String>>(username: S, password: S) -> UserPasswordProvider {
+        UserPasswordProvider {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    /// credentials from the environment variables `GGR_USERNAME` and `GGR_HTTP_PASSWORD`
+    pub fn from_env() -> Option<UserPasswordProvider> {
+        match (env::var(ENV_USERNAME), env::var(ENV_HTTP_PASSWORD)) {
+            (Ok(username), Ok(password)) => Some(UserPasswordProvider::new(username, password)),
+            _ => None,
+        }
+    }
+}
+
+impl CredentialProvider for UserPasswordProvider {
+    fn credential(&self, _: &url::Url) -> GGRResult<Option<Credential>> {
+        Ok(Some(Credential::UserPassword {
+            username: self.username.clone(),
+            password: self.password.clone(),
+        }))
+    }
+}
+
+/// A bearer token
+pub struct BearerTokenProvider {
+    token: String,
+}
+
+impl BearerTokenProvider {
+    /// the token `token` for all urls
+    pub fn new<S: Into<String>>(token: S) -> BearerTokenProvider {
+        BearerTokenProvider {
+            token: token.into(),
+        }
+    }
+
+    /// the token from the environment variable `GGR_TOKEN`
+    pub fn from_env() -> Option<BearerTokenProvider> {
+        env::var(ENV_TOKEN).ok().map(BearerTokenProvider::new)
+    }
+}
+
+impl CredentialProvider for BearerTokenProvider {
+    fn credential(&self, _: &url::Url) -> GGRResult<Option<Credential>> {
+        Ok(Some(Credential::BearerToken(self.token.clone())))
+    }
+}
+
+/// Asks several providers in order, the first credential wins
+#[derive(Default)]
+pub struct ChainProvider {
+    providers: Vec<Arc<dyn CredentialProvider>>,
+}
+
+impl ChainProvider {
+    /// an empty chain without any credentials
+    pub fn new() -> ChainProvider {
+        ChainProvider::default()
+    }
+
+    /// appends `provider` to the chain
+    pub fn with<P: CredentialProvider + 'static>(mut self, provider: P) -> ChainProvider {
+        self.providers.push(Arc::new(provider));
+        self
+    }
+
+    /// appends `provider` to the chain if it is `Some`
+    pub fn with_opt<P: CredentialProvider + 'static>(self, provider: Option<P>) -> ChainProvider {
+        match provider {
+            Some(provider) => self.with(provider),
+            None => self,
+        }
+    }
+}
+
+impl CredentialProvider for ChainProvider {
+    fn credential(&self, url: &url::Url) -> GGRResult<Option<Credential>> {
+        for provider in &self.providers {
+            if let Some(credential) = provider.credential(url)? {
+                return Ok(Some(credential));
+            }
+        }
+        Ok(None)
+    }
+
+    fn stored_credential(&self, url: &url::Url) -> GGRResult<Option<Credential>> {
+        for provider in &self.providers {
+            if let Some(credential) = provider.stored_credential(url)? {
+                return Ok(Some(credential));
+            }
+        }
+        Ok(None)
+    }
+
+    /// approves `credential` at all providers
+    fn approve(&self, url: &url::Url, credential: &Credential) {
+        for provider in &self.providers {
+            provider.approve(url, credential);
+        }
+    }
+
+    /// rejects `credential` at all providers
+    fn reject(&self, url: &url::Url, credential: &Credential) {
+        for provider in &self.providers {
+            provider.reject(url, credential);
+        }
+    }
+}
+
+/// A result of `git credential fill`
+struct FilledCredential {
+    /// protocol and host of the url
+    key: String,
+    /// `None` if git has no credential
+    credential: Option<Credential>,
+    /// git was allowed to ask the user
+    prompted: bool,
+    approved: bool,
+}
+
+/// Credentials of the git credential helpers with `git credential fill/approve/reject`
+///
+/// Filled credentials, and that git has none, are cached per protocol and host. Only credentials
+/// which are filled by this provider are approved or rejected, each approved once. A rejected
+/// credential is removed from the cache and the next lookup asks git again. An interactive
+/// provider only prompts in `credential`, `stored_credential` asks the helpers only.
+pub struct GitCredentialProvider {
+    interactive: bool,
+    config: Vec<(String, String)>,
+    filled: Mutex<Vec<FilledCredential>>,
+}
+
+impl GitCredentialProvider {
+    /// a provider which never prompts for a username or password
+    pub fn new() -> GitCredentialProvider {
+        GitCredentialProvider {
+            interactive: false,
+            config: Vec::new(),
+            filled: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// with `interactive` git prompts on the terminal if no helper has a credential
+    pub fn interactive(mut self, interactive: bool) -> GitCredentialProvider {
+        self.interactive = interactive;
+        self
+    }
+
+    /// runs git with the additional configuration `config`, e.g. `credential.helper`, like
+    /// `git -c <key>=<value>`
+    pub fn with_git_config(mut self, config: &[(&str, &str)]) -> GitCredentialProvider {
+        self.config.extend(config.iter().map(|&(key, value)| (key.to_string(), value.to_string())));
+        self
+    }
+
+    /// runs `git credential <action>` with `input` and returns stdout on success. Without
+    /// `prompt` git never asks on the terminal.
+    fn run(&self, action: &str, input: &str, prompt: bool) -> Option<String> {
+        let mut command = Command::new("git");
+        for &(ref key, ref value) in &self.config {
+            command.arg("-c").arg(format!("{}={}", key, value));
+        }
+        if !prompt {
+            command.args(&["-c", "core.askPass="])
+                .env("GIT_TERMINAL_PROMPT", "0")
+                .env_remove("GIT_ASKPASS")
+                .env_remove("SSH_ASKPASS");
+        }
+        command.arg("credential").arg(action)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(x) => {
+                debug!("git credential {}: {}", action, x);
+                return None;
+            },
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(input.as_bytes());
+        }
+
+        match child.wait_with_output() {
+            Ok(ref output) if output.status.success() => Some(String::from_utf8_lossy(&output.stdout).into_owned()),
+            _ => None,
+        }
+    }
+
+    /// `git credential fill` for `url`, with `prompt` git may ask the user on the terminal
+    fn fill(&self, url: &url::Url, prompt: bool) -> GGRResult<Option<Credential>> {
+        let key = credential_key(url);
+        {
+            let filled = match self.filled.lock() {
+                Ok(filled) => filled,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+            // that git has none without a prompt doesn't answer a lookup with prompt
+            if let Some(f) = filled.iter().find(|f| f.key == key && (f.credential.is_some() || f.prompted || !prompt)) {
+                return Ok(f.credential.clone());
+            }
+        }
+
+        let mut input = credential_input(url);
+        if !url.username().is_empty() {
+            input.push_str(&format!("username={}\n", url.username()));
+        }
+
+        let output = self.run("fill", &input, prompt).unwrap_or_default();
+        let value = |name: &str| {
+            output.lines()
+                .find(|line| line.starts_with(&format!("{}=", name)))
+                .map(|line| line[name.len() + 1..].to_string())
+        };
+        let credential = match (value("username"), value("password")) {
+            (Some(username), Some(password)) => {
+                debug!("use credentials of git credential helper for {}", key);
+                Some(Credential::UserPassword { username: username, password: password })
+            },
+            _ => None,
+        };
+
+        let mut filled = match self.filled.lock() {
+            Ok(filled) => filled,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        filled.retain(|f| f.key != key);
+        filled.push(FilledCredential {
+            key: key,
+            credential: credential.clone(),
+            prompted: prompt,
+            approved: false,
+        });
+
+        Ok(credential)
+    }
+
+    /// sends `approve` or `reject` for a credential filled by this provider
+    fn report(&self, action: &str, url: &url::Url, credential: &Credential) {
+        let key = credential_key(url);
+        let mut filled = match self.filled.lock() {
+            Ok(filled) => filled,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        let position = match filled.iter().position(|f| f.key == key && f.credential.as_ref() == Some(credential)) {
+            Some(position) => position,
+            None => return,
+        };
+
+        if action == "approve" {
+            if filled[position].approved {
+                return;
+            }
+            filled[position].approved = true;
+        } else {
+            filled.remove(position);
+        }
+
+        if let Credential::UserPassword { ref username, ref password } = *credential {
+            debug!("git credential {} for {}", action, key);
+            self.run(action, &format!("{}username={}\npassword={}\n", credential_input(url), username, password), false);
+        }
+    }
+}
+
+impl Default for GitCredentialProvider {
+    fn default() -> GitCredentialProvider {
+        GitCredentialProvider::new()
+    }
+}
+
+impl CredentialProvider for GitCredentialProvider {
+    fn credential(&self, url: &url::Url) -> GGRResult<Option<Credential>> {
+        self.fill(url, self.interactive)
+    }
+
+    fn stored_credential(&self, url: &url::Url) -> GGRResult<Option<Credential>> {
+        self.fill(url, false)
+    }
+
+    fn approve(&self, url: &url::Url, credential: &Credential) {
+        self.report("approve", url, credential);
+    }
+
+    fn reject(&self, url: &url::Url, credential: &Credential) {
+        self.report("reject", url, credential);
+    }
+}
+
+/// `protocol://host:port` of `url`
+fn credential_key(url: &url::Url) -> String {
+    match url.port() {
+        Some(port) => format!("{}://{}:{}", url.scheme(), url.host_str().unwrap_or(""), port),
+        None => format!("{}://{}", url.scheme(), url.host_str().unwrap_or("")),
+    }
+}
+
+/// protocol and host of `url` in the input format of `git credential`
+fn credential_input(url: &url::Url) -> String {
+    match url.port() {
+        Some(port) => format!("protocol={}\nhost={}:{}\n", url.scheme(), url.host_str().unwrap_or(""), port),
+        None => format!("protocol={}\nhost={}\n", url.scheme(), url.host_str().unwrap_or("")),
+    }
+}
+
+/// the default chain of environment variables, `~/.netrc`, git credential helpers and gitcookies
+pub fn default_provider() -> ChainProvider {
+    ChainProvider::new()
+        .with_opt(BearerTokenProvider::from_env())
+        .with_opt(UserPasswordProvider::from_env())
+        .with(NetrcProvider::from_home())
+        .with(GitCredentialProvider::new())
+        .with(GitCookiesProvider::from_git_config())
+}
+
+/// Credential callback of git2 `RemoteCallbacks::credentials`
+///
+/// git2 calls the callback again if the server rejected the last credential. The last credential
+/// is then rejected at the provider, a provider which returns the same credential again ends the
+/// fetch with an error. After a successful fetch `approve` approves the last credential.
+///
+/// git2 can not send own headers, so a bearer token is used as password of a basic authentication
+/// with the username of the url. Cookies are not supported by git2.
+pub struct Git2Credentials<'a> {
+    provider: &'a dyn CredentialProvider,
+    last: RefCell<Option<(url::Url, Credential)>>,
+}
+
+impl<'a> Git2Credentials<'a> {
+    /// callback with credentials from `provider`
+    pub fn new(provider: &'a dyn CredentialProvider) -> Git2Credentials<'a> {
+        Git2Credentials {
+            provider: provider,
+            last: RefCell::new(None),
+        }
+    }
+
+    /// the credential callback, `username_from_url` is the username git2 passes
+    pub fn callback(&self, url: &str, username_from_url: Option<&str>) -> Result<git2::Cred, git2::Error> {
+        let parsed = url::Url::parse(url).map_err(|x| git2::Error::from_str(&format!("{}", x)))?;
+
+        let rejected = self.last.borrow_mut().take();
+        if let Some((ref lasturl, ref credential)) = rejected {
+            self.provider.reject(lasturl, credential);
+        }
+
+        let credential = match self.provider.credential(&parsed) {
+            Ok(Some(credential)) => credential,
+            Ok(None) => return Err(git2::Error::from_str(&format!("no credentials for repository {} found", url))),
+            Err(x) => return Err(git2::Error::from_str(&format!("{}", x))),
+        };
+        if rejected.as_ref().map_or(false, |&(_, ref r)| *r == credential) {
+            return Err(git2::Error::from_str(&format!("credentials for repository {} are rejected by the server", url)));
+        }
+
+        let cred = match credential {
+            Credential::UserPassword { ref username, ref password } => git2::Cred::userpass_plaintext(username, password),
+            Credential::BearerToken(ref token) => git2::Cred::userpass_plaintext(username_from_url.unwrap_or("git"), token),
+            Credential::Cookie(_) => return Err(git2::Error::from_str(&format!("cookie credentials for {} are not usable for git fetches, please use a http password", url))),
+        };
+
+        *self.last.borrow_mut() = Some((parsed, credential));
+        cred
+    }
+
+    /// approves the last credential after a successful fetch
+    pub fn approve(&self) {
+        if let Some((ref url, ref credential)) = *self.last.borrow() {
+            self.provider.approve(url, credential);
+        }
+    }
+}
+
+#[test]
+fn test_netrc_and_gitcookies() {
+    use std::fs;
+
+    let dir = env::temp_dir().join(format!("libgerrit-credentials-{}", ::std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = |name: &str| dir.join(name);
+
+    fs::write(path("netrc"), "machine review.example.com login jane password secret\ndefault login anon password guest\n").unwrap();
+    fs::write(path("gitcookies"), "# comment\n\
+                                   .example.com\tTRUE\t/\tTRUE\t2147483647\to\tgit-jane=1/abc\n\
+                                   #HttpOnly_other.org\tFALSE\t/\tTRUE\t2147483647\to\tgit-x=1/def\n").unwrap();
+
+    let review = url::Url::parse("https://review.example.com/a/changes/").unwrap();
+    let other = url::Url::parse("https://other.org/").unwrap();
+
+    let netrc = NetrcProvider::new(path("netrc"));
+    assert_eq!(netrc.credential(&review).unwrap(), Some(Credential::UserPassword { username: "jane".into(), password: "secret".into() }));
+    assert_eq!(netrc.credential(&other).unwrap(), Some(Credential::UserPassword { username: "anon".into(), password: "guest".into() }));
+
+    let cookies = GitCookiesProvider::new(path("gitcookies"));
+    assert_eq!(cookies.credential(&review).unwrap(), Some(Credential::Cookie("o=git-jane=1/abc".into())));
+    assert_eq!(cookies.credential(&other).unwrap(), Some(Credential::Cookie("o=git-x=1/def".into())));
+    assert_eq!(cookies.credential(&url::Url::parse("http://other.org/").unwrap()).unwrap(), None);
+
+    let chain = ChainProvider::new()
+        .with(GitCookiesProvider::new(path("missing")))
+        .with(BearerTokenProvider::new("token"))
+        .with(netrc);
+    assert_eq!(chain.credential(&review).unwrap(), Some(Credential::BearerToken("token".into())));
+    assert_eq!(format!("{:?}", Credential::BearerToken("token".into())), "BearerToken(\"***\")");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -8,7 +8,76 @@
 
 #![warn(missing_docs)]
 
+use error::GerritError;
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use serde_json;
 use std::collections::HashMap;
+use timestamp::Timestamp;
+use std::fmt;
+use std::str::FromStr;
+
+/// JSON fields of an entity which are unknown to libgerrit, e.g. of newer gerrit versions
+///
+/// Entities returned by `changes` keep these fields in their flattened `extra` field, so they are
+/// not lost when an entity is serialized again.
+pub type Extra = HashMap<String, serde_json::Value>;
+
+/// defines an enum of string values with an `Unknown` fallback
+///
+/// Newer gerrit versions add values to enums. Instead of failing the deserialization of the whole
+/// response, the value is kept as `Unknown`.
+macro_rules! string_enum {
+    ($(#[$meta:meta])* pub enum $name:ident { $($(#[$vmeta:meta])* $variant:ident,)* }) => {
+        $(#[$meta])*
+        #[allow(non_camel_case_types)]
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($(#[$vmeta])* $variant,)*
+            /// a value unknown to this version of libgerrit
+            Unknown(String),
+        }
+
+        impl $name {
+            /// the value as used by gerrit
+            pub fn as_str(&self) -> &str {
+                match *self {
+                    $($name::$variant => stringify!($variant),)*
+                    $name::Unknown(ref value) => value,
+                }
+            }
+        }
+
+        impl<'a> From<&'a str> for $name {
+            fn from(value: &'a str) -> $name {
+                match value {
+                    $(stringify!($variant) => $name::$variant,)*
+                    _ => $name::Unknown(value.into()),
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where S: Serializer {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<$name, D::Error>
+            where D: Deserializer<'de> {
+                String::deserialize(deserializer).map(|value| $name::from(value.as_str()))
+            }
+        }
+    }
+}
 
 /// The `AccountInfo` entity contains information about an account
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -35,7 +104,36 @@ pub struct AccountInfo {
     /// Whether the query would deliver more results if not limited. Only set on the last account
     /// that is returned.
     /// V02.13
-    pub _more_accounts: Option<String>,
+    pub _more_accounts: Option<bool>,
+    /// see `Extra`
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// The `AccountNameInput` entity contains information for setting a name for an account.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AccountNameInput {
+    /// The new full name of the account.
+    /// If not set or if set to an empty string, the account name is deleted.
+    /// V02.09
+    pub name: Option<String>,
+}
+
+/// The `EmailInfo` entity contains information about an email address of a user.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct EmailInfo {
+    /// The email address.
+    /// V02.09
+    pub email: String,
+    /// Whether this is the preferred email address of the user.
+    /// not set if false
+    /// V02.09
+    pub preferred: Option<bool>,
+    /// Set true if the user must confirm control of the email address by following a verification
+    /// link before Gerrit will permit use of this address.
+    /// not set if false
+    /// V02.09
+    pub pending_confirmation: Option<bool>,
 }
 
 /// The `ActionInfo` entity describes a REST API call the client can make to manipulate a resource.
@@ -54,21 +152,24 @@ pub struct ActionInfo {
     /// If true the action is permitted at this time and the caller is likely allowed to execute
     /// it. This may change if state is updated at the server or permissions are modified. Not
     /// present if false.
-    pub enabled: Option<String>,
+    pub enabled: Option<bool>,
+    /// see `Extra`
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
-/// `ChangeInfo` helper variant to present a status of a change
-#[allow(non_camel_case_types)]
-#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
-pub enum ChangeInfoChangeStatus {
-    /// new change
-    NEW,
-    /// change is merged
-    MERGED,
-    /// change is abandoned
-    ABANDONED,
-    /// its a draft change
-    DRAFT,
+string_enum! {
+    /// `ChangeInfo` helper variant to present a status of a change
+    pub enum ChangeInfoChangeStatus {
+        /// new change
+        NEW,
+        /// change is merged
+        MERGED,
+        /// change is abandoned
+        ABANDONED,
+        /// its a draft change
+        DRAFT,
+    }
 }
 
 /// The `LabelInfo` entity contains information about a label on a change, always corresponding to
@@ -83,7 +184,97 @@ pub enum ChangeInfoChangeStatus {
 pub struct LabelInfo {
     /// Whether the label is optional. Optional means the label may be set, but it’s neither
     /// necessary for submission nor does it block submission if set.
+    /// V02.09
     pub optional: Option<bool>,
+    /// One user who approved this label on the change (voted the maximum value) as an
+    /// `AccountInfo` entity.
+    /// V02.09
+    pub approved: Option<AccountInfo>,
+    /// One user who rejected this label on the change (voted the minimum value) as an
+    /// `AccountInfo` entity.
+    /// V02.09
+    pub rejected: Option<AccountInfo>,
+    /// One user who recommended this label on the change (voted positively, but not the maximum
+    /// value) as an `AccountInfo` entity.
+    /// V02.09
+    pub recommended: Option<AccountInfo>,
+    /// One user who disliked this label on the change (voted negatively, but not the minimum
+    /// value) as an `AccountInfo` entity.
+    /// V02.09
+    pub disliked: Option<AccountInfo>,
+    /// If true, the label blocks submit operation. If not set, the default is false.
+    /// V02.09
+    pub blocking: Option<bool>,
+    /// The voting value of the user who recommended/disliked this label on the change if it is
+    /// not "+1"/"-1".
+    /// V02.09
+    pub value: Option<i32>,
+    /// The default voting value for the label. This value may be outside the range specified in
+    /// `permitted_labels`.
+    /// V02.13
+    pub default_value: Option<i32>,
+    /// A map of all values that are allowed for this label. The map maps the values ("-2", "-1",
+    /// " 0", "+1", "+2") to the value descriptions.
+    /// Only set for `DETAILED_LABELS`.
+    /// V02.09
+    pub values: Option<HashMap<String, String>>,
+    /// List of all approvals for this label as a list of `ApprovalInfo` entities. Items in this
+    /// list may not represent actual votes cast by users; if a user votes on any label, a
+    /// corresponding `ApprovalInfo` will appear in this list for all labels.
+    /// Only set for `DETAILED_LABELS`.
+    /// V02.09
+    pub all: Option<Vec<ApprovalInfo>>,
+    /// see `Extra`
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+impl LabelInfo {
+    /// the lowest and the highest vote of `all`, `None` without votes
+    pub fn vote_range(&self) -> Option<(i32, i32)> {
+        let votes: Vec<i32> = self.all.iter().flat_map(|all| all.iter()).filter_map(|a| a.value).collect();
+        match (votes.iter().min(), votes.iter().max()) {
+            (Some(min), Some(max)) => Some((*min, *max)),
+            _ => None,
+        }
+    }
+}
+
+/// The `ApprovalInfo` entity contains information about an approval from a user for a label on a
+/// change. It has the fields of an `AccountInfo` and the vote of the account.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ApprovalInfo {
+    /// The numeric ID of the account
+    /// V02.09
+    pub _account_id: Option<u64>,
+    /// The full name of the user.
+    /// Only set if detailed account information is requested
+    /// V02.09
+    pub name: Option<String>,
+    /// The email address the user prefers to be contacted through.
+    /// Only set if detailed account information is requested
+    /// V02.09
+    pub email: Option<String>,
+    /// The username of the user.
+    /// Only set if detailed account information is requested
+    /// V02.09
+    pub username: Option<String>,
+    /// The vote that the user has given for the label. If present and zero, the user is permitted
+    /// to vote on the label. If absent, the user is not permitted to vote on that label.
+    /// V02.09
+    pub value: Option<i32>,
+    /// The time and date describing when the approval was made.
+    /// V02.09
+    pub date: Option<Timestamp>,
+    /// Value of the tag field from `ReviewInput` set while posting the review.
+    /// V02.13
+    pub tag: Option<String>,
+    /// If true, this vote was made after the change was submitted.
+    /// V02.14
+    pub post_submit: Option<bool>,
+    /// see `Extra`
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 /// The `ChangeMessageInfo` entity contains information about a message attached to a change.
@@ -98,7 +289,7 @@ pub struct ChangeMessageInfo {
     pub author: Option<AccountInfo>,
     /// The timestamp this message was posted.
     /// V02.09
-    pub date: String,
+    pub date: Timestamp,
     /// The text left by the user.
     /// V02.09
     pub message: String,
@@ -109,7 +300,10 @@ pub struct ChangeMessageInfo {
     pub tag: Option<String>,
     /// Which patchset (if any) generated this message.
     /// V02.09
-    pub _revision_number: Option<u16>,
+    pub _revision_number: Option<u32>,
+    /// see `Extra`
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 /// The `FetchInfo` entity contains information about how to fetch a patch set via a certain
@@ -125,6 +319,9 @@ pub struct FetchInfo {
     /// commands.
     /// Only set if download commands are requested.
     pub commands: Option<HashMap<String, String>>,
+    /// see `Extra`
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 impl FetchInfo {
@@ -141,11 +338,12 @@ impl FetchInfo {
     ///     url: "http://localhost/blah".into(),
     ///     reference: "refs/changes/85/225285/1".into(),
     ///     commands: None,
+    ///     extra: Default::default(),
     /// };
     /// assert_eq!("225285/1", fi.get_reference_string());
     /// ```
     pub fn get_reference_string(&self) -> &str {
-        &self.reference.trim_left_matches("refs/changes/")[3..]
+        &self.reference.trim_start_matches("refs/changes/")[3..]
     }
 }
 
@@ -157,9 +355,12 @@ pub struct GitPersonInfo {
     /// The email address of the author/committer.
     pub email: String,
     /// The timestamp of when this identity was constructed.
-    pub date: String,
+    pub date: Timestamp,
     /// The timezone offset from UTC of when this identity was constructed.
     pub tz: i32,
+    /// see `Extra`
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 /// `CommitInfoParents`, same as `CommitInfo` but commit is string
@@ -171,7 +372,10 @@ pub struct CommitInfoParents {
     pub committer: Option<GitPersonInfo>,
     pub subject: String,
     pub message: Option<String>,
-    pub web_links: Option<String>,
+    pub web_links: Option<Vec<WebLinkInfo>>,
+    /// see `Extra`
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 /// The `CommitInfo` entity contains information about a commit.
@@ -192,7 +396,10 @@ pub struct CommitInfo {
     /// The commit message.
     pub message: Option<String>,
     /// Links to the commit in external sites as a list of WebLinkInfo entities.
-    pub web_links: Option<String>,
+    pub web_links: Option<Vec<WebLinkInfo>>,
+    /// see `Extra`
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 /// The `FileInfo` entity contains information about a file in a patch set.
@@ -216,30 +423,33 @@ pub struct FileInfo {
     /// Number of deleted lines.
     /// Not set for binary files or if no lines were deleted.
     /// V02.09
-    pub lines_deleted: Option<String>,
+    pub lines_deleted: Option<u64>,
     /// Number of bytes by which the file size increased/decreased.
     /// V02.13
-    pub size_delta: Option<u64>,
+    pub size_delta: Option<i64>,
     /// File size in bytes.
     /// V02.13
     pub size: Option<u64>,
+    /// see `Extra`
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
-/// V02.13
-#[allow(non_camel_case_types)]
-#[allow(missing_docs)]
-#[derive(Deserialize, Serialize, Debug, Clone)]
-pub enum RevisionInfoChangeKind {
-    #[allow(missing_docs)]
-    REWORK,
-    #[allow(missing_docs)]
-    TRIVIAL_REBASE,
-    #[allow(missing_docs)]
-    MERGE_FIRST_PARENT_UPDATE,
-    #[allow(missing_docs)]
-    NO_CODE_CHANGE,
+string_enum! {
+    /// V02.13
     #[allow(missing_docs)]
-    NO_CHANGE,
+    pub enum RevisionInfoChangeKind {
+        #[allow(missing_docs)]
+        REWORK,
+        #[allow(missing_docs)]
+        TRIVIAL_REBASE,
+        #[allow(missing_docs)]
+        MERGE_FIRST_PARENT_UPDATE,
+        #[allow(missing_docs)]
+        NO_CODE_CHANGE,
+        #[allow(missing_docs)]
+        NO_CHANGE,
+    }
 }
 
 /// The `RevisionInfo` entity contains information about a patch set.
@@ -261,10 +471,11 @@ pub struct RevisionInfo {
     pub _number: u64,
     /// The timestamp of when the patch set was created.
     /// V02.13
-    pub created: Option<String>,
+    pub created: Option<Timestamp>,
     /// Information about how to fetch this patch set. The fetch information is provided as a map
     /// that maps the protocol name ("git", "http", "ssh") to FetchInfo entities.
     /// V02.09
+    #[serde(default)]
     pub fetch: HashMap<String, FetchInfo>,
     /// The uploader of the patch set as an AccountInfo entity.
     /// V02.13
@@ -299,6 +510,9 @@ pub struct RevisionInfo {
     /// object.
     /// V02.13
     pub push_certificate: Option<PushCertificateInfo>,
+    /// see `Extra`
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 /// The `PushCertificateInfo` entity contains information about a push certificate provided when
@@ -321,9 +535,9 @@ pub struct GpgKeyInfo {
     /// The 40-char (plus spaces) hex GPG key fingerprint.
     pub fingerprint: Option<String>,
     /// OpenPGP User IDs associated with the public key.
-    pub user_ids: String,
+    pub user_ids: Option<Vec<String>>,
     /// ASCII armored public key material.
-    pub key: String,
+    pub key: Option<String>,
     /// The result of server-side checks on the key; one of BAD, OK, or TRUSTED. BAD keys have
     /// serious problems and should not be used. If a key is OK, inspecting only that key found no
     /// problems, but the system does not fully trust the key’s origin. A `TRUSTED key is valid,
@@ -331,7 +545,7 @@ pub struct GpgKeyInfo {
     pub status: Option<String>,
     /// A list of human-readable problem strings found in the course of checking whether the key is
     /// valid and trusted.
-    pub problems: Option<String>,
+    pub problems: Option<Vec<String>>,
 }
 
 
@@ -346,18 +560,21 @@ pub struct ProblemInfo {
     pub status: Option<String>,
     /// If status is set, an additional plaintext message describing the outcome of the fix.
     pub outcome: Option<String>,
+    /// see `Extra`
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
-/// `ProjectInfo` helper variant to present a status of a project
-#[allow(non_camel_case_types)]
-#[derive(Deserialize, Serialize, Debug, Clone)]
-pub enum ProjectInfo_ProjectState {
-    /// The project is active
-    ACTIVE,
-    /// Project is read only, noch anges possible
-    READONLY,
-    /// project is hidden
-    HIDDEN,
+string_enum! {
+    /// `ProjectInfo` helper variant to present a status of a project
+    pub enum ProjectInfo_ProjectState {
+        /// The project is active
+        ACTIVE,
+        /// Project is read only, no changes possible
+        READ_ONLY,
+        /// project is hidden
+        HIDDEN,
+    }
 }
 
 /// The `WebLinkInfo` entity describes a link to an external site.
@@ -368,7 +585,10 @@ pub struct WebLinkInfo {
     /// The link URL.
     pub url: String,
     /// URL to the icon of the link.
-    pub image_url: String,
+    pub image_url: Option<String>,
+    /// see `Extra`
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 /// The `ProjectInfo` entity contains information about a project.
@@ -393,12 +613,155 @@ pub struct ProjectInfo {
     pub web_links: Option<Vec<WebLinkInfo>>,
 }
 
+/// The `ProjectInput` entity contains information for the creation of a new project.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct ProjectInput {
+    /// The name of the project (not encoded).
+    /// If set, must match the project name in the URL.
+    /// If name ends with .git the suffix will be automatically removed.
+    pub name: Option<String>,
+    /// The name of the parent project.
+    /// If not set, the All-Projects project will be the parent project.
+    pub parent: Option<String>,
+    /// The description of the project.
+    pub description: Option<String>,
+    /// Whether a permission-only project should be created.
+    pub permissions_only: Option<bool>,
+    /// Whether an empty initial commit should be created.
+    pub create_empty_commit: Option<bool>,
+    /// The submit type that should be set for the project (MERGE_IF_NECESSARY, REBASE_IF_NECESSARY,
+    /// FAST_FORWARD_ONLY, MERGE_ALWAYS, CHERRY_PICK).
+    /// If not set, MERGE_IF_NECESSARY is set as submit type.
+    pub submit_type: Option<String>,
+    /// A list of branches that should be initially created.
+    /// For the branch names the refs/heads/ prefix can be omitted.
+    pub branches: Option<Vec<String>>,
+    /// A list of groups that should be assigned as project owner.
+    /// Each group in the list must be specified as group-id.
+    /// If not set, the groups that are configured as default owners are set as project owners.
+    pub owners: Option<Vec<String>>,
+}
+
+/// The `BranchInfo` entity contains information about a branch.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct BranchInfo {
+    /// The ref of the branch.
+    #[serde(rename="ref")] // "ref" is a keyword
+    pub reference: String,
+    /// The revision to which the branch points.
+    pub revision: String,
+    /// Whether the calling user can delete this branch.
+    /// not set if false
+    pub can_delete: Option<bool>,
+    /// Links to the branch in external sites as a list of WebLinkInfo entries.
+    pub web_links: Option<Vec<WebLinkInfo>>,
+}
+
+/// The `BranchInput` entity contains information for the creation of a new branch.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct BranchInput {
+    /// The name of the branch. The prefix refs/heads/ can be omitted.
+    /// If set, must match the branch ID in the URL.
+    #[serde(rename="ref")] // "ref" is a keyword
+    pub reference: Option<String>,
+    /// The base revision of the new branch.
+    /// If not set, HEAD will be used as base revision.
+    pub revision: Option<String>,
+}
+
+/// The `TagInfo` entity contains information about a tag.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TagInfo {
+    /// The ref of the tag.
+    #[serde(rename="ref")] // "ref" is a keyword
+    pub reference: String,
+    /// For lightweight tags, the revision of the commit to which the tag points. For annotated
+    /// tags, the revision of the tag object.
+    pub revision: String,
+    /// The SHA1 of the tagged object. Only set for annotated tags.
+    pub object: Option<String>,
+    /// The tag message. For signed tags, includes the signature. Only set for annotated tags.
+    pub message: Option<String>,
+    /// The tagger as a GitPersonInfo entity. Only set for annotated tags.
+    pub tagger: Option<GitPersonInfo>,
+    /// Whether the calling user can delete this tag.
+    /// not set if false
+    /// V02.15
+    pub can_delete: Option<bool>,
+    /// Links to the tag in external sites as a list of WebLinkInfo entries.
+    pub web_links: Option<Vec<WebLinkInfo>>,
+}
+
+/// The `TagInput` entity contains information for creating a tag.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct TagInput {
+    /// The name of the tag. The leading refs/tags/ is optional.
+    /// If set, must match the tag ID in the URL.
+    #[serde(rename="ref")] // "ref" is a keyword
+    pub reference: Option<String>,
+    /// The revision to which the tag should point. If not specified, the project’s HEAD will be
+    /// used.
+    pub revision: Option<String>,
+    /// The tag message. When set, the tag will be created as an annotated tag.
+    pub message: Option<String>,
+}
+
+/// The `GroupOptionsInfo` entity contains options of the group.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct GroupOptionsInfo {
+    /// Whether the group is visible to all registered users.
+    /// not set if false
+    pub visible_to_all: Option<bool>,
+}
+
+/// The `GroupInfo` entity contains information about a group. This can be a Gerrit internal group,
+/// or an external group that is known to Gerrit.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct GroupInfo {
+    /// The URL encoded UUID of the group.
+    pub id: String,
+    /// The name of the group.
+    /// For external groups the name is missing if there is no group backend that can provide the
+    /// group name. Not set if returned in a map where the group name is used as map key.
+    pub name: Option<String>,
+    /// URL to information about the group. Typically a URL to a web page that permits users to
+    /// apply to join the group, or manage their membership.
+    pub url: Option<String>,
+    /// Options of the group.
+    pub options: Option<GroupOptionsInfo>,
+    /// The description of the group.
+    pub description: Option<String>,
+    /// The numeric ID of the group. Only set for Gerrit internal groups.
+    pub group_id: Option<u64>,
+    /// The name of the owner group. Only set for Gerrit internal groups.
+    pub owner: Option<String>,
+    /// The URL encoded UUID of the owner group. Only set for Gerrit internal groups.
+    pub owner_id: Option<String>,
+    /// Whether the query would deliver more results if not limited. Only set on the last group
+    /// that is returned.
+    pub _more_groups: Option<bool>,
+    /// A list of AccountInfo entities describing the direct members. Only set if members are
+    /// requested.
+    pub members: Option<Vec<AccountInfo>>,
+    /// A list of GroupInfo entities describing the direct subgroups. Only set if subgroups are
+    /// requested.
+    pub includes: Option<Vec<GroupInfo>>,
+}
+
+/// The `MembersInput` entity contains information about accounts that should be added as members
+/// to a group or that should be deleted from the group.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct MembersInput {
+    /// A list of account ids that identify the accounts that should be added or deleted.
+    pub members: Vec<String>,
+}
+
 /// The `ReviewerUpdateInfo` entity contains information about updates to change’s reviewers set.
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct ReviewerUpdateInfo {
     /// Timestamp of the update.
     /// V02.09
-    pub updated: String,
+    pub updated: Timestamp,
     /// The account which modified state of the reviewer in question as AccountInfo entity.
     /// V02.09
     pub updated_by: AccountInfo,
@@ -408,20 +771,23 @@ pub struct ReviewerUpdateInfo {
     /// The reviewer state, one of REVIEWER, CC or REMOVED.
     /// V02.09
     pub state: ReviewerState,
+    /// see `Extra`
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
-/// The reviewers as a map that maps a reviewer state to a list of `AccountInfo` entities. Possible
-/// reviewer states are REVIEWER, CC and REMOVED.
-/// Only set if detailed labels are requested
-#[allow(non_camel_case_types)]
-#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
-pub enum ReviewerState {
-    /// Users with at least one non-zero vote on the change.
-    REVIEWER,
-    /// Users that were added to the change, but have not voted.
-    CC,
-    /// Users that were previously reviewers on the change, but have been removed.
-    REMOVED,
+string_enum! {
+    /// The reviewers as a map that maps a reviewer state to a list of `AccountInfo` entities. Possible
+    /// reviewer states are REVIEWER, CC and REMOVED.
+    /// Only set if detailed labels are requested
+    pub enum ReviewerState {
+        /// Users with at least one non-zero vote on the change.
+        REVIEWER,
+        /// Users that were added to the change, but have not voted.
+        CC,
+        /// Users that were previously reviewers on the change, but have been removed.
+        REMOVED,
+    }
 }
 
 /// The `ChangeInfo` entity contains information about a change.
@@ -455,13 +821,13 @@ pub struct ChangeInfo {
     pub status: ChangeInfoChangeStatus,
     /// The timestamp of when the change was created.
     /// V02.09
-    pub created: String,
+    pub created: Timestamp,
     /// The timestamp of when the change was last updated.
     /// V02.09
-    pub updated: String,
+    pub updated: Timestamp,
     /// The timestamp of when the change was submitted.
     /// V02.13
-    pub submitted: Option<String>,
+    pub submitted: Option<Timestamp>,
     /// Whether the calling user has starred this change. not set if false
     /// V02.09
     pub starred: Option<bool>,
@@ -481,12 +847,14 @@ pub struct ChangeInfo {
     /// Not set for merged changes.
     /// V02.09
     pub mergeable: Option<bool>,
-    /// Number of inserted lines.
+    /// Number of inserted lines, 0 if not returned (e.g. with `SKIP_DIFFSTAT`).
     /// V02.09
-    pub insertions: u16,
-    /// Number of deleted lines.
+    #[serde(default)]
+    pub insertions: u64,
+    /// Number of deleted lines, 0 if not returned (e.g. with `SKIP_DIFFSTAT`).
     /// V02.09
-    pub deletions: u16,
+    #[serde(default)]
+    pub deletions: u64,
     /// The sortkey of the change.
     /// V02.09, not in V02.13
     pub _sortkey: Option<String>,
@@ -503,11 +871,11 @@ pub struct ChangeInfo {
     /// Actions the caller might be able to perform on this revision. The information is a map of
     /// view name to ActionInfo entities.
     /// V02.13
-    pub actions: Option<Vec<ActionInfo>>,
+    pub actions: Option<HashMap<String, ActionInfo>>,
     /// The labels of the change as a map that maps the label names to LabelInfo entries.
     /// Only set if labels or detailed labels are requested.
     /// V02.09
-    pub labels: Option<LabelInfo>,
+    pub labels: Option<HashMap<String, LabelInfo>>,
     /// A map of the permitted labels that maps a label name to the list of values that are allowed
     /// for that label.
     /// Only set if detailed labels are requested.
@@ -524,7 +892,7 @@ pub struct ChangeInfo {
     /// REMOVED: Users that were previously reviewers on the change, but have been removed.
     /// Only set if detailed labels are requested.
     /// V02.13
-    pub reviewers: Option<HashMap<ReviewerState, AccountInfo>>,
+    pub reviewers: Option<HashMap<ReviewerState, Vec<AccountInfo>>>,
     /// Updates to reviewers set for the change as ReviewerUpdateInfo entities. Only set if
     /// reviewer updates are requested and if NoteDb is enabled.
     /// V02.13
@@ -561,6 +929,9 @@ pub struct ChangeInfo {
     /// Not documented
     /// V02.13
     pub submittable: Option<bool>,
+    /// see `Extra`
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 /// The `ChangeInput` entity contains information about creating a new change.
@@ -626,7 +997,7 @@ pub struct ReviewerInfo {
     /// Whether the query would deliver more results if not limited. Only set on the last account
     /// that is returned.
     /// V02.13
-    pub _more_accounts: Option<String>,
+    pub _more_accounts: Option<bool>,
     /// gerritcodereview#reviewer
     /// V02.09
     kind: Option<String>,
@@ -679,20 +1050,24 @@ pub struct ReviewerInput {
     pub confirmed: Option<bool>,
 }
 
+string_enum! {
+    /// Notify handling, defines to whom email notifications should be sent after an action.
+    /// V02.13
+    pub enum NotifyHandling {
+        /// Noone
+        NONE,
+        /// only owner
+        OWNER,
+        /// owner and reviewer
+        OWNER_REVIEWERS,
+        /// to all
+        ALL,
+    }
+}
+
 /// Abandon notifications to ...
 /// V02.13
-#[allow(non_camel_case_types)]
-#[derive(Deserialize, Serialize, Debug)]
-pub enum AbandonInputNotify {
-    /// Noone
-    NONE,
-    /// only owner
-    OWNER,
-    /// owner and reviewer
-    OWNER_REVIEWERS,
-    /// to all
-    ALL,
-}
+pub type AbandonInputNotify = NotifyHandling;
 
 /// The `AbandonInput` entity contains information for abandoning a change
 #[derive(Deserialize, Serialize, Debug)]
@@ -705,7 +1080,7 @@ pub struct AbandonInput {
     /// Allowed values are NONE, OWNER, OWNER_REVIEWERS and ALL.
     /// If not set, the default is ALL.
     /// V02.13
-    pub notify: Option<AbandonInputNotify>,
+    pub notify: Option<NotifyHandling>,
 }
 
 /// The `RestoreInput` entity contains information for restoring a change.
@@ -715,6 +1090,71 @@ pub struct RestoreInput {
     pub message: Option<String>,
 }
 
+/// The `SubmitInput` entity contains information for submitting a change.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SubmitInput {
+    /// If set, submit the change on behalf of the given user. The value may take any format
+    /// accepted by the accounts REST API. Using this option requires Submit (On Behalf Of)
+    /// permission on the branch.
+    /// V02.13
+    pub on_behalf_of: Option<String>,
+    /// Notify handling that defines to whom email notifications should be sent after the change is
+    /// submitted.
+    /// Allowed values are NONE, OWNER, OWNER_REVIEWERS and ALL.
+    /// If not set, the default is ALL.
+    /// V02.13
+    pub notify: Option<NotifyHandling>,
+}
+
+/// The `RebaseInput` entity contains information for changing parent when rebasing.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RebaseInput {
+    /// The new parent revision. This can be a ref or a SHA1 to a concrete patchset.
+    /// Alternatively, a change number can be specified, in which case the current patch set is
+    /// inferred.
+    /// Empty string is used for rebasing directly on top of the target branch, which effectively
+    /// breaks dependency towards a parent change.
+    /// V02.09
+    pub base: Option<String>,
+}
+
+/// The `CherryPickInput` entity contains information for cherry-picking a change to a new branch.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CherryPickInput {
+    /// Commit message for the cherry-picked change
+    /// V02.09
+    pub message: Option<String>,
+    /// Destination branch
+    /// V02.09
+    pub destination: String,
+    /// Number of the parent relative to which the cherry-pick should be considered.
+    /// V02.13
+    pub parent: Option<u64>,
+    /// Notify handling that defines to whom email notifications should be sent after the
+    /// cherry-pick.
+    /// V02.15
+    pub notify: Option<NotifyHandling>,
+}
+
+/// The `RevertInput` entity contains information for reverting a change.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RevertInput {
+    /// Message to be added as review comment to the change when reverting the change.
+    /// V02.09
+    pub message: Option<String>,
+}
+
+/// The `MoveInput` entity contains information for moving a change to a new branch.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct MoveInput {
+    /// Destination branch
+    /// V02.13
+    pub destination_branch: String,
+    /// A message to be posted in this change’s comments
+    /// V02.13
+    pub message: Option<String>,
+}
+
 /// The `CommentRange` entity describes the range of an inline comment
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct CommentRange {
@@ -729,7 +1169,7 @@ pub struct CommentRange {
 }
 
 /// The `CommentInput` entity contains information for creating an inline comment
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct CommentInput {
     /// Must be gerritcodereview#comment if provided.
     /// V02.09
@@ -761,7 +1201,7 @@ pub struct CommentInput {
     /// The timestamp of this comment.
     /// Accepted but ignored.
     /// V02.09
-    pub updated: Option<String>,
+    pub updated: Option<Timestamp>,
     /// The comment message.
     /// If not set and an existing draft comment is updated, the existing draft comment is deleted.
     /// V02.09
@@ -771,10 +1211,61 @@ pub struct CommentInput {
     /// link#review-input[ReviewInput]
     /// V02.13
     pub tag: Option<String>,
+    /// Whether or not the comment must be addressed by the user.
+    /// V02.15
+    pub unresolved: Option<bool>,
 }
 
-/// The `ReviewInput` entity contains information for adding a review to a revision
+/// The `CommentInfo` entity contains information about an inline comment
 #[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct CommentInfo {
+    /// The patch set number for the comment; only set in contexts where
+    /// comments may be returned for multiple patch sets.
+    /// V02.09
+    pub patch_set: Option<u64>,
+    /// The URL encoded UUID of the comment.
+    /// V02.09
+    pub id: String,
+    /// The path of the file for which the inline comment was done.
+    /// Not set if returned in a map where the key is the file path.
+    /// V02.09
+    pub path: Option<String>,
+    /// The side on which the comment was added.
+    /// Allowed values are REVISION and PARENT.
+    /// If not set, the default is REVISION.
+    /// V02.09
+    pub side: Option<String>,
+    /// The number of the line for which the comment was done.
+    /// If range is set, this equals the end line of the range.
+    /// If neither line nor range is set, it's a file comment.
+    /// V02.09
+    pub line: Option<u64>,
+    /// The range of the comment as a CommentRange entity.
+    /// V02.09
+    pub range: Option<CommentRange>,
+    /// The URL encoded UUID of the comment to which this comment is a reply.
+    /// V02.09
+    pub in_reply_to: Option<String>,
+    /// The comment message.
+    /// V02.09
+    pub message: Option<String>,
+    /// The timestamp of when this comment was written.
+    /// V02.09
+    pub updated: Timestamp,
+    /// The author of the message as an AccountInfo entity.
+    /// Unset for draft comments, assumed to be the calling user.
+    /// V02.09
+    pub author: Option<AccountInfo>,
+    /// Value of the tag field from ReviewInput set while posting the review.
+    /// V02.13
+    pub tag: Option<String>,
+    /// Whether or not the comment must be addressed by the user.
+    /// V02.15
+    pub unresolved: Option<bool>,
+}
+
+/// The `ReviewInput` entity contains information for adding a review to a revision
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct ReviewInput {
     /// The message to be added as review comment.
     /// V02.09
@@ -786,7 +1277,7 @@ pub struct ReviewInput {
     /// The comments that should be added as a map that maps a file path to a list of CommentInput
     /// entities.
     /// V02.09
-    pub comments: Option<HashMap<String, CommentInput>>,
+    pub comments: Option<HashMap<String, Vec<CommentInput>>>,
     /// Whether all labels are required to be within the user’s permitted ranges based on access
     /// controls.
     /// If true, attempting to use a label not granted to the user will fail the entire modify
@@ -825,5 +1316,350 @@ pub struct ReviewInput {
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct ReviewInfo {
     /// The labels of the review as a map that maps the label names to the voting values.
+    #[serde(default)]
     pub labels: HashMap<String, i8>,
 }
+
+/// The `ServerInfo` entity contains information about the configuration of the Gerrit server
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ServerInfo {
+    /// Information about the authentication configuration as `AuthInfo` entity.
+    /// V02.10
+    pub auth: AuthInfo,
+    /// Information about the configuration from the change section as `ChangeConfigInfo` entity.
+    /// V02.10
+    pub change: ChangeConfigInfo,
+    /// Information about the configured download options as `DownloadInfo` entity.
+    /// V02.10
+    pub download: DownloadInfo,
+    /// Information about the configuration from the gerrit section as `GerritInfo` entity.
+    /// V02.10
+    pub gerrit: GerritInfo,
+    /// Information about plugins as `PluginConfigInfo` entity.
+    /// V02.12
+    pub plugin: Option<PluginConfigInfo>,
+}
+
+impl ServerInfo {
+    /// returns the download scheme `name` (like "http", "ssh" or "anonymous http")
+    ///
+    /// The download schemes are provided by the `download-commands` plugin, without this plugin
+    /// there are no schemes and the `fetch` map of a `RevisionInfo` is empty.
+    pub fn download_scheme(&self, name: &str) -> Option<&DownloadSchemeInfo> {
+        self.download.schemes.get(name)
+    }
+}
+
+/// The `AuthInfo` entity contains information about the authentication configuration of the
+/// Gerrit server.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AuthInfo {
+    /// The authentication type that is configured on the server. Can be OPENID, OPENID_SSO,
+    /// OAUTH, HTTP, HTTP_LDAP, CLIENT_SSL_CERT_LDAP, LDAP, LDAP_BIND, CUSTOM_EXTENSION or
+    /// DEVELOPMENT_BECOME_ANY_ACCOUNT.
+    /// V02.10
+    pub auth_type: String,
+    /// Whether contributor agreements are required.
+    /// V02.10
+    pub use_contributor_agreements: Option<bool>,
+    /// List of account fields that are editable.
+    /// V02.10
+    pub editable_account_fields: Option<Vec<String>>,
+    /// The login URL. Only set if authentication type is HTTP or HTTP_LDAP.
+    /// V02.10
+    pub login_url: Option<String>,
+    /// The login text. Only set if authentication type is HTTP or HTTP_LDAP.
+    /// V02.10
+    pub login_text: Option<String>,
+    /// The URL to switch accounts.
+    /// V02.10
+    pub switch_account_url: Option<String>,
+    /// The register URL. Only set if authentication type is LDAP, LDAP_BIND or CUSTOM_EXTENSION.
+    /// V02.10
+    pub register_url: Option<String>,
+    /// The URL to edit the full name. Only set if authentication type is LDAP, LDAP_BIND or
+    /// CUSTOM_EXTENSION.
+    /// V02.10
+    pub edit_full_name_url: Option<String>,
+    /// The URL to obtain an HTTP password. Only set if authentication type is LDAP, LDAP_BIND or
+    /// CUSTOM_EXTENSION.
+    /// V02.10
+    pub http_password_url: Option<String>,
+    /// Whether git over http uses basic authentication with the LDAP password.
+    /// V02.12
+    pub is_git_basic_auth: Option<bool>,
+}
+
+/// The `ChangeConfigInfo` entity contains information about Gerrit configuration from the change
+/// section.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ChangeConfigInfo {
+    /// Whether blame is allowed.
+    /// V02.12
+    pub allow_blame: Option<bool>,
+    /// Whether draft workflow is allowed.
+    /// V02.12
+    pub allow_drafts: Option<bool>,
+    /// Number of lines from which on a change is considered as large.
+    /// V02.10
+    pub large_change: Option<u64>,
+    /// The label of the reply button in the change screen.
+    /// V02.10
+    pub reply_label: Option<String>,
+    /// The tooltip of the reply button in the change screen.
+    /// V02.10
+    pub reply_tooltip: Option<String>,
+    /// How often in seconds the web interface should poll for updates to the currently open
+    /// change.
+    /// V02.10
+    pub update_delay: Option<u64>,
+    /// Whether submitting a change submits all changes of the same topic.
+    /// V02.12
+    pub submit_whole_topic: Option<bool>,
+}
+
+/// The `DownloadInfo` entity contains information about supported download options.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DownloadInfo {
+    /// The supported download schemes as a map which maps the scheme name to a
+    /// `DownloadSchemeInfo` entity. Empty without the `download-commands` plugin.
+    /// V02.10
+    #[serde(default)]
+    pub schemes: HashMap<String, DownloadSchemeInfo>,
+    /// List of supported archive formats.
+    /// V02.10
+    #[serde(default)]
+    pub archives: Vec<String>,
+}
+
+/// The `DownloadSchemeInfo` entity contains information about a supported download scheme and its
+/// commands.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DownloadSchemeInfo {
+    /// The URL of the download scheme, where "${project}" is used as placeholder for the project
+    /// name.
+    /// V02.10
+    pub url: String,
+    /// Whether this download scheme requires authentication.
+    /// V02.10
+    pub is_auth_required: Option<bool>,
+    /// Whether this download scheme supports authentication.
+    /// V02.10
+    pub is_auth_supported: Option<bool>,
+    /// Download commands as a map which maps the command name to the download command.
+    /// V02.10
+    pub commands: Option<HashMap<String, String>>,
+    /// Clone commands as a map which maps the command name to the clone command.
+    /// V02.12
+    pub clone_commands: Option<HashMap<String, String>>,
+}
+
+/// The `GerritInfo` entity contains information about Gerrit configuration from the gerrit
+/// section.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct GerritInfo {
+    /// Name of the root project.
+    /// V02.10
+    pub all_projects: String,
+    /// Name of the project in which meta data of all users is stored.
+    /// V02.10
+    pub all_users: String,
+    /// Whether documentation search is available.
+    /// V02.12
+    pub doc_search: Option<bool>,
+    /// Custom base URL where Gerrit server documentation is located.
+    /// V02.10
+    pub doc_url: Option<String>,
+    /// Whether to enable the web UI for editing GPG keys.
+    /// V02.12
+    pub edit_gpg_keys: Option<bool>,
+    /// URL to report bugs.
+    /// V02.10
+    pub report_bug_url: Option<String>,
+}
+
+/// The `PluginConfigInfo` entity contains information about Gerrit extensions by plugins.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PluginConfigInfo {
+    /// Whether an avatar provider is registered.
+    /// V02.12
+    pub has_avatars: Option<bool>,
+    /// List of paths to JavaScript files of plugins.
+    /// V02.12
+    pub js_resource_paths: Option<Vec<String>>,
+}
+
+/// Additional fields of `ChangeInfo` which are requested with the `o` parameter of
+/// `GET /changes/` and `GET /changes/{change-id}`
+#[allow(non_camel_case_types)]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ListChangesOption {
+    /// A summary of each label required for submit, and approvers that have granted (or rejected)
+    /// with that label.
+    LABELS,
+    /// Detailed label information, including numeric values of all existing approvals, recognized
+    /// label values, values permitted to be set by the current user, all reviewers by state, and
+    /// reviewers that may be removed by the current user.
+    DETAILED_LABELS,
+    /// Describe the current revision (patch set) of the change, including the commit SHA-1 and URLs
+    /// to fetch from.
+    CURRENT_REVISION,
+    /// Describe all revisions, not just current.
+    ALL_REVISIONS,
+    /// Include the commands field in the FetchInfo for revisions. Only valid when the
+    /// CURRENT_REVISION or ALL_REVISIONS option is selected.
+    DOWNLOAD_COMMANDS,
+    /// Parse and output all header fields from the commit object, including message. Only valid
+    /// when the CURRENT_REVISION or ALL_REVISIONS option is selected.
+    CURRENT_COMMIT,
+    /// Parse and output all header fields from the output revisions. If only CURRENT_REVISION was
+    /// requested then only the current revision’s commit data will be output.
+    ALL_COMMITS,
+    /// List files modified by the commit and magic files, including basic line counts
+    /// inserted/deleted per file. Only valid when the CURRENT_REVISION or ALL_REVISIONS option is
+    /// selected.
+    CURRENT_FILES,
+    /// List files modified by the commit and magic files, including basic line counts
+    /// inserted/deleted per file. If only the CURRENT_REVISION was requested then only that
+    /// commit’s modified files will be output.
+    ALL_FILES,
+    /// Include _account_id, email and username fields when referencing accounts.
+    DETAILED_ACCOUNTS,
+    /// Include updates to reviewers set as ReviewerUpdateInfo entities.
+    /// V02.13
+    REVIEWER_UPDATES,
+    /// Include messages associated with the change.
+    MESSAGES,
+    /// Include information on available actions for the change and its current revision. Ignored if
+    /// the caller is not authenticated.
+    CURRENT_ACTIONS,
+    /// Include information on available change actions for the change. Ignored if the caller is not
+    /// authenticated.
+    CHANGE_ACTIONS,
+    /// Include the reviewed field if all of the following are true: the change is open, the caller
+    /// is authenticated, and the caller has commented on the change more recently than the last
+    /// update from the change owner.
+    REVIEWED,
+    /// Don’t include the mergeable field in ChangeInfo.
+    /// V02.14
+    SKIP_MERGEABLE,
+    /// Don’t include the insertions and deletions fields in ChangeInfo.
+    /// V03.00
+    SKIP_DIFFSTAT,
+    /// Include the submittable field in ChangeInfo, which can be used to tell if the change is
+    /// reviewed and ready for submit.
+    SUBMITTABLE,
+    /// Include the web_links field in CommitInfo, therefore only valid in combination with
+    /// CURRENT_COMMIT or ALL_COMMITS.
+    WEB_LINKS,
+    /// Include potential problems with the change.
+    CHECK,
+    /// Include the full commit message with Gerrit-specific commit footers in the RevisionInfo.
+    COMMIT_FOOTERS,
+    /// Include push certificate information in the RevisionInfo. Ignored if signed push is not
+    /// enabled on the server.
+    PUSH_CERTIFICATES,
+    /// Include references to external tracking systems as TrackingIdInfo.
+    /// V02.14
+    TRACKING_IDS,
+}
+
+impl ListChangesOption {
+    /// all known options
+    pub fn all() -> &'static [ListChangesOption] {
+        static ALL: &'static [ListChangesOption] = &[
+        ListChangesOption::LABELS,
+        ListChangesOption::DETAILED_LABELS,
+        ListChangesOption::CURRENT_REVISION,
+        ListChangesOption::ALL_REVISIONS,
+        ListChangesOption::DOWNLOAD_COMMANDS,
+        ListChangesOption::CURRENT_COMMIT,
+        ListChangesOption::ALL_COMMITS,
+        ListChangesOption::CURRENT_FILES,
+        ListChangesOption::ALL_FILES,
+        ListChangesOption::DETAILED_ACCOUNTS,
+        ListChangesOption::REVIEWER_UPDATES,
+        ListChangesOption::MESSAGES,
+        ListChangesOption::CURRENT_ACTIONS,
+        ListChangesOption::CHANGE_ACTIONS,
+        ListChangesOption::REVIEWED,
+        ListChangesOption::SKIP_MERGEABLE,
+        ListChangesOption::SKIP_DIFFSTAT,
+        ListChangesOption::SUBMITTABLE,
+        ListChangesOption::WEB_LINKS,
+        ListChangesOption::CHECK,
+        ListChangesOption::COMMIT_FOOTERS,
+        ListChangesOption::PUSH_CERTIFICATES,
+        ListChangesOption::TRACKING_IDS,
+        ];
+        ALL
+    }
+
+    /// name of the option as used in the `o` parameter
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ListChangesOption::LABELS => "LABELS",
+            ListChangesOption::DETAILED_LABELS => "DETAILED_LABELS",
+            ListChangesOption::CURRENT_REVISION => "CURRENT_REVISION",
+            ListChangesOption::ALL_REVISIONS => "ALL_REVISIONS",
+            ListChangesOption::DOWNLOAD_COMMANDS => "DOWNLOAD_COMMANDS",
+            ListChangesOption::CURRENT_COMMIT => "CURRENT_COMMIT",
+            ListChangesOption::ALL_COMMITS => "ALL_COMMITS",
+            ListChangesOption::CURRENT_FILES => "CURRENT_FILES",
+            ListChangesOption::ALL_FILES => "ALL_FILES",
+            ListChangesOption::DETAILED_ACCOUNTS => "DETAILED_ACCOUNTS",
+            ListChangesOption::REVIEWER_UPDATES => "REVIEWER_UPDATES",
+            ListChangesOption::MESSAGES => "MESSAGES",
+            ListChangesOption::CURRENT_ACTIONS => "CURRENT_ACTIONS",
+            ListChangesOption::CHANGE_ACTIONS => "CHANGE_ACTIONS",
+            ListChangesOption::REVIEWED => "REVIEWED",
+            ListChangesOption::SKIP_MERGEABLE => "SKIP_MERGEABLE",
+            ListChangesOption::SKIP_DIFFSTAT => "SKIP_DIFFSTAT",
+            ListChangesOption::SUBMITTABLE => "SUBMITTABLE",
+            ListChangesOption::WEB_LINKS => "WEB_LINKS",
+            ListChangesOption::CHECK => "CHECK",
+            ListChangesOption::COMMIT_FOOTERS => "COMMIT_FOOTERS",
+            ListChangesOption::PUSH_CERTIFICATES => "PUSH_CERTIFICATES",
+            ListChangesOption::TRACKING_IDS => "TRACKING_IDS",
+        }
+    }
+}
+
+impl fmt::Display for ListChangesOption {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for ListChangesOption {
+    type Err = GerritError;
+
+    /// parses an option name, the case is ignored
+    fn from_str(s: &str) -> Result<ListChangesOption, GerritError> {
+        let upper = s.trim().to_uppercase();
+
+        ListChangesOption::all().iter()
+            .find(|o| o.as_str() == upper)
+            .cloned()
+            .ok_or_else(|| {
+                let valid: Vec<&str> = ListChangesOption::all().iter().map(|o| o.as_str()).collect();
+                GerritError::UnknownListChangesOption(s.into(), valid.join(", "))
+            })
+    }
+}
+
+
+#[test]
+fn test_string_enum_unknown() {
+    let status: ChangeInfoChangeStatus = serde_json::from_str("\"MERGED\"").unwrap();
+    assert_eq!(status, ChangeInfoChangeStatus::MERGED);
+
+    let kind: RevisionInfoChangeKind = serde_json::from_str("\"NEW_KIND_OF_CHANGE\"").unwrap();
+    assert_eq!(kind, RevisionInfoChangeKind::Unknown("NEW_KIND_OF_CHANGE".into()));
+    assert_eq!(serde_json::to_string(&kind).unwrap(), "\"NEW_KIND_OF_CHANGE\"");
+
+    let reviewers: HashMap<ReviewerState, Vec<AccountInfo>> =
+        serde_json::from_str(r#"{"CC": [], "ATTENTION": [{"_account_id": 1}]}"#).unwrap();
+    assert!(reviewers[&ReviewerState::CC].is_empty());
+    assert_eq!(reviewers[&ReviewerState::Unknown("ATTENTION".into())][0]._account_id, Some(1));
+}
@@ -15,6 +15,11 @@ quick_error! {
                 display("function '{}' not implemented in gerrit version '{}' (first occurances in '{}'", function, cur, ocu)
         }
 
+        UnparsableVersion(function: String, version: String) {
+            description("Version of the server not parsable")
+            display("function '{}' needs a known gerrit version, but '{}' is not parsable", function, version)
+        }
+
         ChangeInputProblem {
             description("Problem with ChangeInput")
             display("Problem with ChangeInput")
@@ -30,6 +35,36 @@ quick_error! {
             display("Reviewer not found")
         }
 
+        Conflict(text: String) {
+            description("Conflict with current state of the resource")
+            display("Conflict: {}", text.trim())
+        }
+
+        NotFound(text: String) {
+            description("Resource not found")
+            display("Not found: {}", text.trim())
+        }
+
+        Forbidden(text: String) {
+            description("Operation not permitted")
+            display("Forbidden: {}", text.trim())
+        }
+
+        Unauthorized(text: String) {
+            description("Authentication failed")
+            display("Unauthorized: {}", text.trim())
+        }
+
+        PreconditionFailed(text: String) {
+            description("Precondition failed")
+            display("Precondition failed: {}", text.trim())
+        }
+
+        ServerError(status: u32, text: String) {
+            description("Internal problem of the server")
+            display("Server error {}: {}", status, text.trim())
+        }
+
         GerritApi(status: u32, text: String) {
             description("HTTP problem")
             display("HTTP status: {}, text: {}", status, text.trim())
@@ -43,6 +78,81 @@ quick_error! {
         ChangeIDEmpty {
             description("ChangeID is empty")
         }
+
+        InvalidUrl(url: String, reason: String) {
+            description("Invalid url of the gerrit server")
+            display("'{}' is no valid url of a gerrit server: {}", url, reason)
+        }
+
+        InvalidChangeId(id: String) {
+            description("Invalid change id")
+            display("'{}' is no change number, Change-Id or 'project~branch~Change-Id' triplet", id)
+        }
+
+        InvalidRevisionId(id: String) {
+            description("Invalid revision id")
+            display("'{}' is no 'current', patch set number or commit", id)
+        }
+
+        InvalidNotify(notify: String) {
+            description("Invalid notify handling")
+            display("'{}' is no notify handling, use 'none', 'owner', 'owner_reviewers' or 'all'", notify)
+        }
+
+        AccountIDEmpty {
+            description("AccountID is empty")
+        }
+
+        EmailEmpty {
+            description("Email is empty")
+        }
+
+        QueryEmpty {
+            description("Query is empty")
+        }
+
+        ProjectNameEmpty {
+            description("Project name is empty")
+        }
+
+        BranchNameEmpty {
+            description("Branch name is empty")
+        }
+
+        TagNameEmpty {
+            description("Tag name is empty")
+        }
+
+        GroupIDEmpty {
+            description("GroupID is empty")
+        }
+
+        AnonymousReadOnly(request: String) {
+            description("Anonymous access is read only")
+            display("'{}' needs authentication, anonymous access is read only. Please provide credentials", request)
+        }
+
+        UnknownListChangesOption(option: String, valid: String) {
+            description("Unknown list changes option")
+            display("unknown option '{}', valid options are: {}", option, valid)
+        }
+    }
+}
+
+impl GerritError {
+    /// error for a failed http response with `status` and the message of the server in `text`
+    ///
+    /// Status codes without an own variant are returned as `GerritApi`.
+    pub fn from_status(status: u32, text: String) -> GerritError {
+        match status {
+            401 => GerritError::Unauthorized(text),
+            403 => GerritError::Forbidden(text),
+            404 => GerritError::NotFound(text),
+            409 => GerritError::Conflict(text),
+            412 => GerritError::PreconditionFailed(text),
+            500..=599 => GerritError::ServerError(status, text),
+            _ => GerritError::GerritApi(status, text),
+        }
     }
 }
 
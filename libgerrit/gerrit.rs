@@ -1,14 +1,167 @@
 
 //! Implements the gerrit structure
 
+use accounts;
+use call;
 use config;
 use changes;
+use credentials::{self, CredentialProvider};
+use error::GGRError;
+use error::GGRResult;
+use error::GerritError;
+use groups;
+use projects;
+use std::sync::Arc;
+use transport::{CurlTransport, ProxySettings, Timeouts, TlsSettings};
+use transport::Transport;
 use url;
 
+/// Builder of a `Gerrit` object
+///
+/// ```no_run
+/// use libgerrit::gerrit::GerritBuilder;
+/// use libgerrit::transport::Timeouts;
+/// use std::time::Duration;
+///
+/// let gerrit = GerritBuilder::new("https://gerrit.example.com/r")
+///     .user_agent("my-tool/1.0")
+///     .timeouts(Timeouts { connect: Some(Duration::from_secs(5)), total: None })
+///     .build()
+///     .unwrap();
+/// let changes = gerrit.changes().query_changes(Some(vec!("status:open")), None).unwrap();
+/// ```
+pub struct GerritBuilder {
+    url: String,
+    credentials: Option<Arc<dyn CredentialProvider>>,
+    transport: Option<Arc<dyn Transport>>,
+    timeouts: Timeouts,
+    tls: TlsSettings,
+    proxy: ProxySettings,
+    user_agent: Option<String>,
+    auth: call::AuthMode,
+    retry: call::RetryPolicy,
+}
+
+impl GerritBuilder {
+    /// a builder for the gerrit server at `url`, like `http://localhost:8080/gerrit`
+    pub fn new<S>(url: S) -> GerritBuilder
+    where S: Into<String> {
+        GerritBuilder {
+            url: url.into(),
+            credentials: None,
+            transport: None,
+            timeouts: Timeouts::default(),
+            tls: TlsSettings::default(),
+            proxy: ProxySettings::default(),
+            user_agent: None,
+            auth: call::AuthMode::default(),
+            retry: call::RetryPolicy::default(),
+        }
+    }
+
+    /// authenticate with credentials of `provider`, default is `credentials::default_provider`
+    pub fn credentials(mut self, provider: Arc<dyn CredentialProvider>) -> GerritBuilder {
+        self.credentials = Some(provider);
+        self
+    }
+
+    /// send all requests through `transport` instead of a `CurlTransport`
+    ///
+    /// Credentials, timeouts, certificates, proxy and user agent are options of the
+    /// `CurlTransport` and ignored for an own transport.
+    pub fn transport(mut self, transport: Arc<dyn Transport>) -> GerritBuilder {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// set the connect and request timeouts
+    pub fn timeouts(mut self, timeouts: Timeouts) -> GerritBuilder {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// set the certificate authorities and the client certificate
+    pub fn tls(mut self, tls: TlsSettings) -> GerritBuilder {
+        self.tls = tls;
+        self
+    }
+
+    /// set the proxy
+    pub fn proxy(mut self, proxy: ProxySettings) -> GerritBuilder {
+        self.proxy = proxy;
+        self
+    }
+
+    /// set the `User-Agent` header of all requests
+    pub fn user_agent<S: Into<String>>(mut self, user_agent: S) -> GerritBuilder {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// set the authentication mode, see `Gerrit::set_auth_mode`
+    pub fn auth_mode(mut self, auth: call::AuthMode) -> GerritBuilder {
+        self.auth = auth;
+        self
+    }
+
+    /// set the retries of temporary failed requests, see `Gerrit::set_retry_policy`
+    pub fn retry_policy(mut self, retry: call::RetryPolicy) -> GerritBuilder {
+        self.retry = retry;
+        self
+    }
+
+    /// validates the url and creates the `Gerrit` object
+    ///
+    /// The url needs to be an absolute `http` or `https` url with a host, otherwise a
+    /// `GerritError::InvalidUrl` is returned.
+    pub fn build(self) -> GGRResult<Gerrit> {
+        let invalid = |reason: &str| GGRError::GerritApiError(GerritError::InvalidUrl(self.url.clone(), reason.into()));
+
+        let url = url::Url::parse(&self.url).map_err(|x| invalid(&x.to_string()))?;
+        if url.scheme() != "http" && url.scheme() != "https" {
+            return Err(invalid("only http and https are supported"));
+        }
+        if url.host_str().map_or(true, |host| host.is_empty()) {
+            return Err(invalid("no host"));
+        }
+
+        let transport = match self.transport {
+            Some(transport) => transport,
+            None => {
+                let credentials = self.credentials.unwrap_or_else(|| Arc::new(credentials::default_provider()));
+                let mut transport = CurlTransport::with_credentials(credentials)
+                    .timeouts(self.timeouts)
+                    .tls(self.tls)
+                    .proxy(self.proxy);
+                if let Some(user_agent) = self.user_agent {
+                    transport = transport.user_agent(user_agent);
+                }
+                Arc::new(transport)
+            },
+        };
+
+        Ok(Gerrit {
+            url: url,
+            transport: transport,
+            auth: self.auth,
+            retry: self.retry,
+            server: Arc::new(config::ServerCache::new()),
+        })
+    }
+}
+
 /// `Gerrit` structure for management of several gerrit endpoints
+///
+/// All endpoint objects of a `Gerrit` object and its clones share one transport, with one
+/// session and its connections, and the cached server version and info. A `Gerrit` object can be
+/// used from several threads at once.
 #[derive(Clone)]
 pub struct Gerrit {
     url: url::Url,
+    transport: Arc<dyn Transport>,
+    auth: call::AuthMode,
+    retry: call::RetryPolicy,
+    server: Arc<config::ServerCache>,
 }
 
 impl Gerrit {
@@ -17,25 +170,108 @@ impl Gerrit {
     /// The url points to the http endpoint of an gerrit server like
     /// `http://localhost:8080/gerrit`. All other function append to this url there endpoint pathes
     /// and query parameters.
+    ///
+    /// # Panics
+    ///
+    /// With an invalid url, use `GerritBuilder` to handle this.
     pub fn new<S>(url: S) -> Gerrit
     where S: Into<String> {
-        Gerrit {
-            url: url::Url::parse(&url.into()).unwrap(),
-        }
+        GerritBuilder::new(url).build().expect("invalid gerrit url")
+    }
+
+    /// Creates a new `Gerrit` object which sends all requests through `transport`
+    ///
+    /// The transport is shared by all endpoint objects returned from this `Gerrit` object and its
+    /// clones.
+    ///
+    /// # Panics
+    ///
+    /// With an invalid url, use `GerritBuilder` to handle this.
+    pub fn with_transport<S>(url: S, transport: Arc<dyn Transport>) -> Gerrit
+    where S: Into<String> {
+        GerritBuilder::new(url).transport(transport).build().expect("invalid gerrit url")
+    }
+
+    /// Creates a new `Gerrit` object which authenticates with credentials from `provider`
+    ///
+    /// `Gerrit::new` uses `credentials::default_provider`.
+    ///
+    /// # Panics
+    ///
+    /// With an invalid url, use `GerritBuilder` to handle this.
+    pub fn with_credentials<S>(url: S, provider: Arc<dyn CredentialProvider>) -> Gerrit
+    where S: Into<String> {
+        GerritBuilder::new(url).credentials(provider).build().expect("invalid gerrit url")
+    }
+
+    /// Sets the authentication mode of all endpoint objects returned afterwards
+    ///
+    /// With the default `AuthMode::Auto` requests are anonymous if no credentials are found.
+    /// Anonymous requests use the endpoints without `/a` and are read only.
+    pub fn set_auth_mode(&mut self, auth: call::AuthMode) {
+        self.auth = auth;
+    }
+
+    /// Sets the retries of temporary failed requests of all endpoint objects returned afterwards
+    ///
+    /// The default is `RetryPolicy::default()`, `RetryPolicy::none()` disables retries.
+    pub fn set_retry_policy(&mut self, retry: call::RetryPolicy) {
+        self.retry = retry;
+    }
+
+    /// base url of the gerrit server
+    pub fn url(&self) -> &url::Url {
+        &self.url
+    }
+
+    /// true if the requests are sent authenticated, see `set_auth_mode`
+    pub fn is_authenticated(&self) -> bool {
+        self.call().is_authenticated()
+    }
+
+    /// a call object for a new endpoint, with the shared transport
+    fn call(&self) -> call::Call {
+        let mut call = call::Call::with_transport(&self.url, self.transport.clone());
+        call.set_auth_mode(self.auth);
+        call.set_retry_policy(self.retry);
+        call.set_server_cache(self.server.clone());
+        call
+    }
+
+    /// Returnes a Accounts endpoint
+    ///
+    /// This represent a accounts endpoint for lookup of accounts and manipulating of account
+    /// settings
+    pub fn accounts(&self) -> accounts::Accounts {
+        accounts::Accounts::with_call(self.call())
     }
 
     /// Returnes a Change endpoint
     ///
     /// This represent a change endpoint for add, remove or manipulating of changes and changesets
-    pub fn changes(&mut self) -> changes::Changes {
-        changes::Changes::new(&self.url)
+    pub fn changes(&self) -> changes::Changes {
+        changes::Changes::with_call(self.call())
     }
 
     /// Returnes a Config endpoint
     ///
-    /// manipulate the configuration of a gerrit instance
-    pub fn config(&mut self) -> config::Config {
-        config::Config::new(&self.url)
+    /// manipulate the configuration of a gerrit instance. The server version and server info are
+    /// fetched once and shared by all endpoints of this `Gerrit` object and its clones.
+    pub fn config(&self) -> config::Config {
+        config::Config::with_call(self.call())
+    }
+
+    /// Returnes a Groups endpoint
+    ///
+    /// list groups and manage there members
+    pub fn groups(&self) -> groups::Groups {
+        groups::Groups::with_call(self.call())
     }
-}
 
+    /// Returnes a Projects endpoint
+    ///
+    /// list, create and manipulate projects and there branches and tags
+    pub fn projects(&self) -> projects::Projects {
+        projects::Projects::with_call(self.call())
+    }
+}
@@ -0,0 +1,138 @@
+//! Implements the groups endpoint
+
+use call;
+use entities;
+use error::GGRError;
+use error::GGRResult;
+use error::GerritError;
+use std::collections::HashMap;
+use url;
+
+const ENDPOINT: &'static str = "/groups";
+
+/// Interface to retrieve Groups and manage their members on a gerrit server
+pub struct Groups {
+    call: call::Call,
+}
+
+impl Groups {
+    /// create a new `Groups` instance
+    pub fn new(url: &url::Url) -> Groups {
+        Groups {
+            call: call::Call::new(url),
+        }
+    }
+
+    /// create a new `Groups` instance which sends its requests through `call`
+    pub fn with_call(call: call::Call) -> Groups {
+        Groups {
+            call: call,
+        }
+    }
+
+    /// returns the path of a group, the group id is url encoded
+    fn group_path(groupid: &str) -> GGRResult<String> {
+        if groupid.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::GroupIDEmpty));
+        }
+
+        Ok(format!("{}/{}", ENDPOINT, call::encode_path_segment(groupid)))
+    }
+
+    /// api function 'GET /groups/'
+    ///
+    /// Returns a map of group names to `GroupInfo` entities.
+    pub fn list_groups(&self) -> GGRResult<HashMap<String, entities::GroupInfo>> {
+        let path = format!("{}/", ENDPOINT);
+
+        self.call.execute::<(),HashMap<String, entities::GroupInfo>>("list groups", &path, call::CallMethod::Get, None)
+    }
+
+    /// api function 'GET /groups/{group-id}'
+    ///
+    /// `groupid` can be the UUID, the legacy numeric id or the name of the group.
+    pub fn get_group(&self, groupid: &str) -> GGRResult<entities::GroupInfo> {
+        let path = Groups::group_path(groupid)?;
+
+        self.call.execute::<(),entities::GroupInfo>("get group", &path, call::CallMethod::Get, None)
+    }
+
+    /// api function 'GET /groups/{group-id}/members/'
+    ///
+    /// With `recursive` the members of all included groups are resolved too. Every account is
+    /// only once in the list.
+    pub fn list_members(&mut self, groupid: &str, recursive: bool) -> GGRResult<Vec<entities::AccountInfo>> {
+        let path = format!("{}/members/", Groups::group_path(groupid)?);
+
+        if recursive {
+            self.call.set_url_query(Some("recursive"));
+        } else {
+            self.call.set_url_query(None);
+        }
+
+        let out = self.call.execute::<(),Vec<entities::AccountInfo>>("list group members", &path, call::CallMethod::Get, None);
+        self.call.set_url_query(None);
+        out
+    }
+
+    /// api function 'GET /groups/{group-id}/groups/'
+    ///
+    /// Returns the direct included groups (subgroups) of a group.
+    pub fn list_included_groups(&self, groupid: &str) -> GGRResult<Vec<entities::GroupInfo>> {
+        let path = format!("{}/groups/", Groups::group_path(groupid)?);
+
+        self.call.execute::<(),Vec<entities::GroupInfo>>("list included groups", &path, call::CallMethod::Get, None)
+    }
+
+    /// api function 'PUT /groups/{group-id}/members/{account-id}'
+    pub fn add_member(&self, groupid: &str, accountid: &str) -> GGRResult<entities::AccountInfo> {
+        if accountid.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::AccountIDEmpty));
+        }
+
+        let path = format!("{}/members/{}", Groups::group_path(groupid)?, call::encode_path_segment(accountid));
+
+        self.call.execute::<(),entities::AccountInfo>("add group member", &path, call::CallMethod::Put, None)
+    }
+
+    /// api function 'POST /groups/{group-id}/members.add'
+    pub fn add_members(&self, groupid: &str, accountids: &[&str]) -> GGRResult<Vec<entities::AccountInfo>> {
+        if accountids.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::AccountIDEmpty));
+        }
+
+        let path = format!("{}/members.add", Groups::group_path(groupid)?);
+
+        let membersinput = entities::MembersInput {
+            members: accountids.iter().map(|s| s.to_string()).collect(),
+        };
+
+        self.call.execute::<&entities::MembersInput,Vec<entities::AccountInfo>>("add group members", &path, call::CallMethod::Post, Some(&&membersinput))
+    }
+
+    /// api function 'DELETE /groups/{group-id}/members/{account-id}'
+    pub fn remove_member(&self, groupid: &str, accountid: &str) -> GGRResult<()> {
+        if accountid.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::AccountIDEmpty));
+        }
+
+        let path = format!("{}/members/{}", Groups::group_path(groupid)?, call::encode_path_segment(accountid));
+
+        self.call.execute_nocontent::<()>("remove group member", &path, call::CallMethod::Delete, None)
+    }
+
+    /// api function 'POST /groups/{group-id}/members.delete'
+    pub fn remove_members(&self, groupid: &str, accountids: &[&str]) -> GGRResult<()> {
+        if accountids.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::AccountIDEmpty));
+        }
+
+        let path = format!("{}/members.delete", Groups::group_path(groupid)?);
+
+        let membersinput = entities::MembersInput {
+            members: accountids.iter().map(|s| s.to_string()).collect(),
+        };
+
+        self.call.execute_nocontent::<&entities::MembersInput>("remove group members", &path, call::CallMethod::Post, Some(&&membersinput))
+    }
+}
@@ -0,0 +1,221 @@
+//! Identifiers of changes and revisions
+//!
+//! Gerrit accepts several forms of `{change-id}` and `{revision-id}` in the path of a request.
+//! `ChangeId` and `RevisionId` parse them from user input and encode them for the path, e.g. the
+//! project `tools/ggr` of a triplet becomes `tools%2Fggr`.
+
+use call;
+use entities::ChangeInfo;
+use error::GerritError;
+use std::fmt;
+use std::str::FromStr;
+
+/// identifies a change
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ChangeId {
+    /// the legacy numeric id, e.g. `4711`
+    Number(u64),
+    /// the `Change-Id` footer of the commit, e.g. `I8473b95934b5732ac55d26311a706c9c2bde9940`.
+    /// It is ambiguous if the change is cherry picked to other branches.
+    ChangeId(String),
+    /// project, branch and `Change-Id`, e.g. `tools/ggr~master~I8473b95934b5732ac55d26311a706c9c2bde9940`
+    Triplet {
+        /// name of the project
+        project: String,
+        /// name of the branch, without `refs/heads/`
+        branch: String,
+        /// `Change-Id` footer
+        change_id: String,
+    },
+    /// project and numeric id, e.g. `tools/ggr~4711`
+    /// V02.16
+    ProjectNumber {
+        /// name of the project
+        project: String,
+        /// numeric id
+        number: u64,
+    },
+}
+
+impl ChangeId {
+    /// the id as percent encoded path segment
+    pub fn path_segment(&self) -> String {
+        match *self {
+            ChangeId::Number(number) => number.to_string(),
+            ChangeId::ChangeId(ref change_id) => call::encode_path_segment(change_id),
+            ChangeId::Triplet { ref project, ref branch, ref change_id } => {
+                format!("{}~{}~{}", call::encode_path_segment(project), call::encode_path_segment(branch),
+                        call::encode_path_segment(change_id))
+            },
+            ChangeId::ProjectNumber { ref project, number } => {
+                format!("{}~{}", call::encode_path_segment(project), number)
+            },
+        }
+    }
+}
+
+impl FromStr for ChangeId {
+    type Err = GerritError;
+
+    /// parses a numeric id, a `Change-Id` or a triplet. Already percent encoded parts, like the
+    /// `id` field of `ChangeInfo`, are decoded.
+    fn from_str(s: &str) -> Result<ChangeId, GerritError> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(GerritError::ChangeIDEmpty);
+        }
+
+        if let Ok(number) = s.parse() {
+            return Ok(ChangeId::Number(number));
+        }
+
+        let invalid = || GerritError::InvalidChangeId(s.into());
+        let decode = |part: &str| {
+            let decoded = call::decode_path_segment(part);
+            if decoded.is_empty() { Err(invalid()) } else { Ok(decoded) }
+        };
+
+        let parts: Vec<&str> = s.split('~').collect();
+        match parts.len() {
+            1 if is_change_id(s) => Ok(ChangeId::ChangeId(s.into())),
+            2 => {
+                Ok(ChangeId::ProjectNumber {
+                    project: decode(parts[0])?,
+                    number: parts[1].parse().map_err(|_| invalid())?,
+                })
+            },
+            3 if is_change_id(parts[2]) => {
+                Ok(ChangeId::Triplet {
+                    project: decode(parts[0])?,
+                    branch: decode(parts[1])?.trim_start_matches("refs/heads/").into(),
+                    change_id: parts[2].into(),
+                })
+            },
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for ChangeId {
+    /// the id in the form of user input, not encoded
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ChangeId::Number(number) => write!(f, "{}", number),
+            ChangeId::ChangeId(ref change_id) => write!(f, "{}", change_id),
+            ChangeId::Triplet { ref project, ref branch, ref change_id } => write!(f, "{}~{}~{}", project, branch, change_id),
+            ChangeId::ProjectNumber { ref project, number } => write!(f, "{}~{}", project, number),
+        }
+    }
+}
+
+impl From<u64> for ChangeId {
+    fn from(number: u64) -> ChangeId {
+        ChangeId::Number(number)
+    }
+}
+
+impl<'a> From<&'a ChangeInfo> for ChangeId {
+    /// the unambiguous triplet of a change
+    fn from(ci: &'a ChangeInfo) -> ChangeId {
+        ChangeId::Triplet {
+            project: ci.project.clone(),
+            branch: ci.branch.clone(),
+            change_id: ci.change_id.clone(),
+        }
+    }
+}
+
+/// identifies a revision (patch set) of a change
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RevisionId {
+    /// the current revision of the change
+    Current,
+    /// the number of a patch set, e.g. `3`
+    Patchset(u32),
+    /// the commit SHA-1 of the patch set, also abbreviated
+    Commit(String),
+}
+
+impl RevisionId {
+    /// the id as path segment
+    pub fn path_segment(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl FromStr for RevisionId {
+    type Err = GerritError;
+
+    /// parses `current`, a patch set number or a commit SHA-1 with at least 4 hex digits. Like
+    /// gerrit, a number is always a patch set number.
+    fn from_str(s: &str) -> Result<RevisionId, GerritError> {
+        let s = s.trim();
+        if s == "current" {
+            return Ok(RevisionId::Current);
+        }
+
+        if let Ok(patchset) = s.parse() {
+            return Ok(RevisionId::Patchset(patchset));
+        }
+
+        if s.len() >= 4 && s.len() <= 40 && s.chars().all(|c| c.is_digit(16)) {
+            return Ok(RevisionId::Commit(s.to_lowercase()));
+        }
+
+        Err(GerritError::InvalidRevisionId(s.into()))
+    }
+}
+
+impl fmt::Display for RevisionId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RevisionId::Current => write!(f, "current"),
+            RevisionId::Patchset(patchset) => write!(f, "{}", patchset),
+            RevisionId::Commit(ref commit) => write!(f, "{}", commit),
+        }
+    }
+}
+
+impl From<u32> for RevisionId {
+    fn from(patchset: u32) -> RevisionId {
+        RevisionId::Patchset(patchset)
+    }
+}
+
+/// a `Change-Id` footer is `I` with 40 hex digits
+fn is_change_id(s: &str) -> bool {
+    s.len() == 41 && s.starts_with('I') && s[1..].chars().all(|c| c.is_digit(16))
+}
+
+#[test]
+fn test_change_id() {
+    let change_id = "I8473b95934b5732ac55d26311a706c9c2bde9940";
+
+    assert_eq!("4711".parse::<ChangeId>().unwrap(), ChangeId::Number(4711));
+    assert_eq!(change_id.parse::<ChangeId>().unwrap(), ChangeId::ChangeId(change_id.into()));
+
+    let triplet: ChangeId = format!("tools/ggr~refs/heads/master~{}", change_id).parse().unwrap();
+    assert_eq!(triplet.path_segment(), format!("tools%2Fggr~master~{}", change_id));
+    assert_eq!(triplet.to_string(), format!("tools/ggr~master~{}", change_id));
+    // the encoded `id` of a `ChangeInfo`
+    assert_eq!(triplet.path_segment().parse::<ChangeId>().unwrap(), triplet);
+
+    let project_number: ChangeId = "tools%2Fggr~4711".parse().unwrap();
+    assert_eq!(project_number, ChangeId::ProjectNumber { project: "tools/ggr".into(), number: 4711 });
+    assert_eq!(project_number.path_segment(), "tools%2Fggr~4711");
+
+    assert!(match "".parse::<ChangeId>() { Err(GerritError::ChangeIDEmpty) => true, _ => false });
+    for invalid in &["I123", "tools~master~4711", "~master~I8473b95934b5732ac55d26311a706c9c2bde9940", "a~b~c~d"] {
+        assert!(match invalid.parse::<ChangeId>() { Err(GerritError::InvalidChangeId(_)) => true, _ => false }, "{}", invalid);
+    }
+}
+
+#[test]
+fn test_revision_id() {
+    assert_eq!("current".parse::<RevisionId>().unwrap(), RevisionId::Current);
+    assert_eq!("3".parse::<RevisionId>().unwrap(), RevisionId::Patchset(3));
+    assert_eq!("184EBE53".parse::<RevisionId>().unwrap(), RevisionId::Commit("184ebe53".into()));
+    assert_eq!(RevisionId::Commit("184ebe53".into()).path_segment(), "184ebe53");
+    assert!("latest".parse::<RevisionId>().is_err());
+    assert!("abc".parse::<RevisionId>().is_err());
+}
@@ -4,16 +4,27 @@
 #[macro_use] extern crate log;
 #[macro_use] extern crate quick_error;
 #[macro_use] extern crate serde_derive;
+extern crate chrono;
 extern crate curl;
 extern crate git2;
+extern crate netrc;
 extern crate semver;
 extern crate serde;
 extern crate serde_json;
 extern crate url;
 
+pub mod accounts;
 pub mod call;
+pub mod cassette;
 pub mod changes;
 pub mod config;
+pub mod credentials;
 pub mod entities;
 pub mod error;
 pub mod gerrit;
+pub mod groups;
+pub mod ids;
+pub mod projects;
+pub mod query;
+pub mod timestamp;
+pub mod transport;
@@ -0,0 +1,215 @@
+//! Implements the projects endpoint
+
+use call;
+use config;
+use entities;
+use error::GGRError;
+use error::GGRResult;
+use error::GerritError;
+use std::collections::HashMap;
+use url;
+
+const ENDPOINT: &'static str = "/projects";
+
+/// Interface to retrieve and manipulate Projects, their branches and tags on a gerrit server
+pub struct Projects {
+    call: call::Call,
+}
+
+impl Projects {
+    /// create a new `Projects` instance
+    pub fn new(url: &url::Url) -> Projects {
+        Projects {
+            call: call::Call::new(url),
+        }
+    }
+
+    /// create a new `Projects` instance which sends its requests through `call`
+    pub fn with_call(call: call::Call) -> Projects {
+        Projects {
+            call: call,
+        }
+    }
+
+    /// `Config` endpoint on the same server and transport, used for version checks
+    fn config(&self) -> config::Config {
+        let mut call = self.call.clone();
+        call.set_url_query(None);
+        config::Config::with_call(call)
+    }
+
+    /// returns the path of a project, the project name is url encoded
+    fn project_path(projectname: &str) -> GGRResult<String> {
+        if projectname.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::ProjectNameEmpty));
+        }
+
+        Ok(format!("{}/{}", ENDPOINT, call::encode_path_segment(projectname)))
+    }
+
+    /// api function 'GET /projects/'
+    ///
+    /// Returns a map of project names to `ProjectInfo` entities. With `prefix` only projects
+    /// starting with this prefix are listed and with `description` the description of every
+    /// project is returned.
+    pub fn list_projects(&mut self, prefix: Option<&str>, description: bool) -> GGRResult<HashMap<String, entities::ProjectInfo>> {
+        let mut querystring = String::new();
+        if let Some(prefix) = prefix {
+            querystring.push_str(&format!("p={}", call::encode_query_value(prefix)));
+        }
+        if description {
+            if !querystring.is_empty() {
+                querystring.push('&');
+            }
+            querystring.push('d');
+        }
+
+        if querystring.is_empty() {
+            self.call.set_url_query(None);
+        } else {
+            self.call.set_url_query(Some(&querystring));
+        }
+
+        let path = format!("{}/", ENDPOINT);
+
+        let out = self.call.execute::<(),HashMap<String, entities::ProjectInfo>>("list projects", &path, call::CallMethod::Get, None);
+        self.call.set_url_query(None);
+        out
+    }
+
+    /// api function 'GET /projects/?query={query}'
+    ///
+    /// V02.12
+    pub fn query_projects(&mut self, query: &str) -> GGRResult<Vec<entities::ProjectInfo>> {
+        if query.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::ProjectNameEmpty));
+        }
+
+        self.config().require_version("GET /projects/?query={query}", "2.12.0")?;
+
+        self.call.set_url_query(Some(&format!("query={}", call::encode_query_value(query))));
+
+        let path = format!("{}/", ENDPOINT);
+
+        let out = self.call.execute::<(),Vec<entities::ProjectInfo>>("query projects", &path, call::CallMethod::Get, None);
+        self.call.set_url_query(None);
+        out
+    }
+
+    /// api function 'GET /projects/{project-name}'
+    pub fn get_project(&self, projectname: &str) -> GGRResult<entities::ProjectInfo> {
+        let path = Projects::project_path(projectname)?;
+
+        self.call.execute::<(),entities::ProjectInfo>("get project", &path, call::CallMethod::Get, None)
+    }
+
+    /// api function 'PUT /projects/{project-name}'
+    pub fn create_project(&self, projectname: &str, pi: &entities::ProjectInput) -> GGRResult<entities::ProjectInfo> {
+        let path = Projects::project_path(projectname)?;
+
+        self.call.execute::<&entities::ProjectInput,entities::ProjectInfo>("create project", &path, call::CallMethod::Put, Some(&pi))
+    }
+
+    /// api function 'GET /projects/{project-name}/description'
+    pub fn get_description(&self, projectname: &str) -> GGRResult<String> {
+        let path = format!("{}/description", Projects::project_path(projectname)?);
+
+        self.call.execute::<(),String>("get project description", &path, call::CallMethod::Get, None)
+    }
+
+    /// api function 'GET /projects/{project-name}/parent'
+    pub fn get_parent(&self, projectname: &str) -> GGRResult<String> {
+        let path = format!("{}/parent", Projects::project_path(projectname)?);
+
+        self.call.execute::<(),String>("get project parent", &path, call::CallMethod::Get, None)
+    }
+
+    /// convenience function to get the state of a project
+    ///
+    /// The state is taken from `GET /projects/{project-name}`.
+    pub fn get_state(&self, projectname: &str) -> GGRResult<Option<entities::ProjectInfo_ProjectState>> {
+        self.get_project(projectname).map(|pi| pi.state)
+    }
+
+    /// api function 'GET /projects/{project-name}/branches/'
+    pub fn list_branches(&self, projectname: &str) -> GGRResult<Vec<entities::BranchInfo>> {
+        let path = format!("{}/branches/", Projects::project_path(projectname)?);
+
+        self.call.execute::<(),Vec<entities::BranchInfo>>("list branches", &path, call::CallMethod::Get, None)
+    }
+
+    /// api function 'PUT /projects/{project-name}/branches/{branch-id}'
+    ///
+    /// Without `revision` the branch is created at `HEAD` of the project.
+    pub fn create_branch(&self, projectname: &str, branchname: &str, revision: Option<&str>) -> GGRResult<entities::BranchInfo> {
+        if branchname.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::BranchNameEmpty));
+        }
+
+        let path = format!("{}/branches/{}", Projects::project_path(projectname)?, call::encode_path_segment(branchname));
+
+        let branchinput = entities::BranchInput {
+            reference: None,
+            revision: revision.map(|s| s.to_string()),
+        };
+
+        self.call.execute::<&entities::BranchInput,entities::BranchInfo>("create branch", &path, call::CallMethod::Put, Some(&&branchinput))
+    }
+
+    /// api function 'DELETE /projects/{project-name}/branches/{branch-id}'
+    pub fn delete_branch(&self, projectname: &str, branchname: &str) -> GGRResult<()> {
+        if branchname.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::BranchNameEmpty));
+        }
+
+        let path = format!("{}/branches/{}", Projects::project_path(projectname)?, call::encode_path_segment(branchname));
+
+        self.call.execute_nocontent::<()>("delete branch", &path, call::CallMethod::Delete, None)
+    }
+
+    /// api function 'GET /projects/{project-name}/tags/'
+    pub fn list_tags(&self, projectname: &str) -> GGRResult<Vec<entities::TagInfo>> {
+        let path = format!("{}/tags/", Projects::project_path(projectname)?);
+
+        self.call.execute::<(),Vec<entities::TagInfo>>("list tags", &path, call::CallMethod::Get, None)
+    }
+
+    /// api function 'PUT /projects/{project-name}/tags/{tag-id}'
+    ///
+    /// With a `message` an annotated tag is created. Without `revision` the tag points to `HEAD`
+    /// of the project.
+    ///
+    /// V02.13
+    pub fn create_tag(&self, projectname: &str, tagname: &str, revision: Option<&str>, message: Option<&str>) -> GGRResult<entities::TagInfo> {
+        if tagname.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::TagNameEmpty));
+        }
+
+        let path = format!("{}/tags/{}", Projects::project_path(projectname)?, call::encode_path_segment(tagname));
+
+        self.config().require_version("PUT /projects/{project-name}/tags/{tag-id}", "2.13.0")?;
+
+        let taginput = entities::TagInput {
+            reference: None,
+            revision: revision.map(|s| s.to_string()),
+            message: message.map(|s| s.to_string()),
+        };
+
+        self.call.execute::<&entities::TagInput,entities::TagInfo>("create tag", &path, call::CallMethod::Put, Some(&&taginput))
+    }
+
+    /// api function 'DELETE /projects/{project-name}/tags/{tag-id}'
+    ///
+    /// V02.14
+    pub fn delete_tag(&self, projectname: &str, tagname: &str) -> GGRResult<()> {
+        if tagname.is_empty() {
+            return Err(GGRError::GerritApiError(GerritError::TagNameEmpty));
+        }
+
+        let path = format!("{}/tags/{}", Projects::project_path(projectname)?, call::encode_path_segment(tagname));
+
+        self.config().require_version("DELETE /projects/{project-name}/tags/{tag-id}", "2.14.0")?;
+
+        self.call.execute_nocontent::<()>("delete tag", &path, call::CallMethod::Delete, None)
+    }
+}
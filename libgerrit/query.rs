@@ -0,0 +1,219 @@
+//! Typed builder for change queries
+//!
+//! A `Query` renders to the query syntax of the gerrit web frontend. Values are quoted when
+//! needed, the url encoding is done by the caller (`Changes::query_changes`).
+//!
+//! ```
+//! use libgerrit::query::Query;
+//!
+//! let q = Query::topic("my topic")
+//!     .and(Query::status("open"))
+//!     .and(Query::owner("self").or(Query::project("tools/ggr")));
+//!
+//! assert_eq!(q.to_string(), "topic:\"my topic\" status:open (owner:self OR project:tools/ggr)");
+//! ```
+
+use std::fmt;
+use timestamp::Timestamp;
+
+/// format of timestamps in queries, gerrit takes them as UTC
+const TIME_FORMAT: &'static str = "%Y-%m-%d %H:%M:%S%.3f";
+
+/// A change query
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    /// a search operator `name:value`, e.g. `topic:foo`
+    Operator(String, String),
+    /// query text which is used unmodified
+    Raw(String),
+    /// all queries have to match
+    And(Vec<Query>),
+    /// one of the queries has to match
+    Or(Vec<Query>),
+    /// the query must not match
+    Not(Box<Query>),
+    /// a query in parenthesis
+    Group(Box<Query>),
+}
+
+impl Query {
+    /// a search operator `name:value`
+    pub fn operator(name: &str, value: &str) -> Query {
+        Query::Operator(name.into(), value.into())
+    }
+
+    /// `topic:{topic}`
+    pub fn topic(topic: &str) -> Query {
+        Query::operator("topic", topic)
+    }
+
+    /// `status:{status}`, status is one of `open`, `pending`, `reviewed`, `closed`, `merged`,
+    /// `abandoned` or `draft`
+    pub fn status(status: &str) -> Query {
+        Query::operator("status", status)
+    }
+
+    /// `owner:{owner}`, owner is a name, email address, username or `self`
+    pub fn owner(owner: &str) -> Query {
+        Query::operator("owner", owner)
+    }
+
+    /// `project:{project}`
+    pub fn project(project: &str) -> Query {
+        Query::operator("project", project)
+    }
+
+    /// `label:{label}={value}`, e.g. `Query::label("Code-Review", "+2")`
+    pub fn label(label: &str, value: &str) -> Query {
+        Query::operator("label", &format!("{}={}", label, value))
+    }
+
+    /// `after:{time}`, changes updated at or after `time`
+    pub fn after(time: &Timestamp) -> Query {
+        Query::operator("after", &time.datetime().format(TIME_FORMAT).to_string())
+    }
+
+    /// `before:{time}`, changes updated at or before `time`
+    pub fn before(time: &Timestamp) -> Query {
+        Query::operator("before", &time.datetime().format(TIME_FORMAT).to_string())
+    }
+
+    /// combines this and `other` query, both have to match
+    pub fn and<Q: Into<Query>>(self, other: Q) -> Query {
+        match self {
+            Query::And(mut queries) => {
+                queries.push(other.into());
+                Query::And(queries)
+            },
+            query => Query::And(vec!(query, other.into())),
+        }
+    }
+
+    /// combines this and `other` query, one of them has to match
+    pub fn or<Q: Into<Query>>(self, other: Q) -> Query {
+        match self {
+            Query::Or(mut queries) => {
+                queries.push(other.into());
+                Query::Or(queries)
+            },
+            query => Query::Or(vec!(query, other.into())),
+        }
+    }
+
+    /// negates this query
+    pub fn not(self) -> Query {
+        Query::Not(Box::new(self))
+    }
+
+    /// puts this query in parenthesis
+    pub fn group(self) -> Query {
+        Query::Group(Box::new(self))
+    }
+
+    /// true for queries which need parenthesis when they are part of another query
+    fn is_compound(&self) -> bool {
+        match *self {
+            Query::And(ref queries) | Query::Or(ref queries) => queries.len() > 1,
+            Query::Raw(ref raw) => raw.trim().contains(char::is_whitespace),
+            _ => false,
+        }
+    }
+}
+
+/// quotes a value of an operator if it isn't a single word for the gerrit query parser
+///
+/// The characters which end a word are the `NON_WORD` characters of the query grammar of gerrit.
+fn quote_value(value: &str) -> String {
+    let single_word = !value.is_empty()
+        && !value.starts_with('-')
+        && !value.contains(|c: char| c.is_whitespace() || c.is_control() || "!\"$%&'():;?[]{}".contains(c));
+
+    if single_word {
+        value.into()
+    } else if !value.contains('"') {
+        format!("\"{}\"", value)
+    } else {
+        format!("{{{}}}", value)
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Query::Operator(ref name, ref value) => write!(f, "{}:{}", name, quote_value(value)),
+            Query::Raw(ref raw) => write!(f, "{}", raw),
+            Query::And(ref queries) => {
+                let parts: Vec<String> = queries.iter().map(|q| {
+                    match *q {
+                        Query::Or(_) | Query::Raw(_) if q.is_compound() => format!("({})", q),
+                        _ => q.to_string(),
+                    }
+                }).collect();
+                write!(f, "{}", parts.join(" "))
+            },
+            Query::Or(ref queries) => {
+                let parts: Vec<String> = queries.iter().map(|q| {
+                    match *q {
+                        Query::Raw(_) if q.is_compound() => format!("({})", q),
+                        _ => q.to_string(),
+                    }
+                }).collect();
+                write!(f, "{}", parts.join(" OR "))
+            },
+            Query::Not(ref query) => {
+                if query.is_compound() {
+                    write!(f, "NOT ({})", query)
+                } else {
+                    write!(f, "NOT {}", query)
+                }
+            },
+            Query::Group(ref query) => write!(f, "({})", query),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Query {
+    fn from(raw: &'a str) -> Query {
+        Query::Raw(raw.into())
+    }
+}
+
+impl<'a> From<&'a String> for Query {
+    fn from(raw: &'a String) -> Query {
+        Query::Raw(raw.clone())
+    }
+}
+
+impl From<String> for Query {
+    fn from(raw: String) -> Query {
+        Query::Raw(raw)
+    }
+}
+
+#[test]
+fn test_query_to_string() {
+    assert_eq!(Query::topic("a b+c&d#e").to_string(), "topic:\"a b+c&d#e\"");
+    assert_eq!(Query::topic("feature/x+y").to_string(), "topic:feature/x+y");
+    assert_eq!(Query::topic("a&b").to_string(), "topic:\"a&b\"");
+    assert_eq!(Query::topic("it's").to_string(), "topic:\"it's\"");
+    assert_eq!(Query::topic("v1.0#2,3*").to_string(), "topic:v1.0#2,3*");
+    for value in &["a!b", "a$b", "50%", "a;b", "why?", "[x]"] {
+        assert_eq!(Query::topic(value).to_string(), format!("topic:\"{}\"", value));
+    }
+    assert_eq!(Query::topic("-x").to_string(), "topic:\"-x\"");
+    assert_eq!(Query::topic("say \"hi\"").to_string(), "topic:{say \"hi\"}");
+    assert_eq!(Query::label("Code-Review", "+2").to_string(), "label:Code-Review=+2");
+    let time: Timestamp = "2017-03-01 12:30:05.126".parse().unwrap();
+    assert_eq!(Query::after(&time).to_string(), "after:\"2017-03-01 12:30:05.126\"");
+    assert_eq!(Query::before(&time).to_string(), "before:\"2017-03-01 12:30:05.126\"");
+
+    let q = Query::status("open").or(Query::status("merged"))
+        .and(Query::project("p"))
+        .and(Query::owner("self").not());
+    assert_eq!(q.to_string(), "(status:open OR status:merged) project:p NOT owner:self");
+
+    let q = Query::topic("t").and("is:watched").and("a OR b").not();
+    assert_eq!(q.to_string(), "NOT (topic:t is:watched (a OR b))");
+
+    assert_eq!(Query::topic("t").group().or(Query::from("x")).to_string(), "(topic:t) OR x");
+}
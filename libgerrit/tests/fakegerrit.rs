@@ -0,0 +1,411 @@
+//! runs the REST api against the in-process fake gerrit server with curl
+
+extern crate fakegerrit;
+extern crate libgerrit;
+
+use fakegerrit::{AuthScheme, FakeGerrit};
+use libgerrit::call::{AuthMode, RetryPolicy};
+use libgerrit::credentials::{BearerTokenProvider, ChainProvider, GitCredentialProvider, NetrcProvider,
+                             UserPasswordProvider};
+use libgerrit::entities::{self, ChangeInfoChangeStatus, ListChangesOption};
+use libgerrit::error::{GGRError, GerritError};
+use libgerrit::gerrit::{Gerrit, GerritBuilder};
+use libgerrit::ids::{ChangeId, RevisionId};
+use libgerrit::query::Query;
+use libgerrit::transport::{CurlTransport, ProxySettings, Timeouts};
+use std::collections::HashMap;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// starts a seeded server, the gerrit object reads the credentials from a `.netrc` of this server
+fn server(scheme: AuthScheme) -> (FakeGerrit, Gerrit) {
+    let seed = include_str!("fixtures/fakegerrit.json");
+    let server = FakeGerrit::start(seed, scheme).expect("fake gerrit not startable");
+
+    let netrc = server.git_root().join(".netrc");
+    fs::write(&netrc, "machine 127.0.0.1 login admin password secret\n").unwrap();
+    let gerrit = Gerrit::with_credentials(server.url(), Arc::new(NetrcProvider::new(netrc)));
+    (server, gerrit)
+}
+
+fn check_api(scheme: AuthScheme) {
+    let (server, gerrit) = server(scheme);
+
+    assert_eq!(gerrit.config().get_version().unwrap(), "2.13.5");
+
+    let query = vec!(Query::topic("feature x+y"), Query::status("open"));
+    let changes: Vec<_> = gerrit.changes()
+        .query_changes_iter(Some(query), Some(vec!(ListChangesOption::CURRENT_REVISION)))
+        .page_size(1)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let numbers: Vec<u64> = changes.iter().map(|ci| ci._number).collect();
+    assert_eq!(numbers, vec!(21, 22));
+    assert!(server.requests().iter().any(|r| r.contains("&S=1")));
+
+    let id = ChangeId::from(&changes[0]);
+    let abandoned = gerrit.changes().abandon_change(&id, Some("not needed"), None).unwrap();
+    assert_eq!(abandoned.status, ChangeInfoChangeStatus::ABANDONED);
+    match gerrit.changes().abandon_change(&id, None, None) {
+        Err(GGRError::GerritApiError(GerritError::Conflict(_))) => {},
+        x => panic!("expected a conflict, got {:?}", x.map(|ci| ci.id)),
+    }
+    let restored = gerrit.changes().restore_change(&id, None).unwrap();
+    assert_eq!(restored.status, ChangeInfoChangeStatus::NEW);
+
+    let added = gerrit.changes().add_reviewer(&id, "jane@example.com").unwrap();
+    assert_eq!(added.reviewers.unwrap()[0]._account_id, Some(1000001));
+
+    let mut labels = HashMap::new();
+    labels.insert("Code-Review".to_string(), 1);
+    let review = entities::ReviewInput {
+        message: Some("looks good".into()),
+        labels: Some(labels),
+        ..Default::default()
+    };
+    gerrit.changes().set_review(&id, &RevisionId::Current, &review).unwrap();
+
+    let reviewers = gerrit.changes().get_reviewers(&id).unwrap();
+    let names: Vec<_> = reviewers.iter().map(|r| r.name.clone().unwrap_or_default()).collect();
+    assert_eq!(names, vec!("Jane Reviewer", "Admin"));
+    assert_eq!(reviewers[1].approvals["Code-Review"], "+1");
+
+    let detailed = gerrit.changes().get_change(&id, Some(vec!(ListChangesOption::DETAILED_LABELS))).unwrap();
+    let codereview = &detailed.labels.unwrap()["Code-Review"];
+    assert_eq!(codereview.recommended.as_ref().and_then(|a| a._account_id), Some(1000000));
+    assert!(codereview.approved.is_none());
+    assert_eq!(codereview.vote_range(), Some((1, 1)));
+    let all = codereview.all.as_ref().unwrap();
+    assert_eq!((all[0].name.as_ref().map(|n| &n[..]), all[0].value), (Some("Admin"), Some(1)));
+
+    // 204 without a body
+    gerrit.changes().delete_reviewer(&id, "1000001").unwrap();
+    match gerrit.changes().delete_reviewer(&id, "1000001") {
+        Err(GGRError::GerritApiError(GerritError::NotFound(_))) => {},
+        x => panic!("expected not found, got {:?}", x),
+    }
+}
+
+#[test]
+fn test_fakegerrit_digest() {
+    check_api(AuthScheme::Digest);
+}
+
+#[test]
+fn test_fakegerrit_basic() {
+    check_api(AuthScheme::Basic);
+}
+
+#[test]
+fn test_fakegerrit_credentials() {
+    let (server, _) = server(AuthScheme::Basic);
+
+    let gerrit = Gerrit::with_credentials(server.url(), Arc::new(UserPasswordProvider::new("admin", "secret")));
+    assert_eq!(gerrit.changes().get_change(&ChangeId::Number(21), None).unwrap()._number, 21);
+
+    let gerrit = Gerrit::with_credentials(server.url(), Arc::new(BearerTokenProvider::new("token-of-admin")));
+    assert_eq!(gerrit.changes().get_change(&ChangeId::Number(21), None).unwrap()._number, 21);
+
+    let gerrit = Gerrit::with_credentials(server.url(), Arc::new(UserPasswordProvider::new("admin", "wrong")));
+    match gerrit.changes().get_change(&ChangeId::Number(21), None) {
+        Err(GGRError::GerritApiError(GerritError::Unauthorized(_))) => {},
+        x => panic!("expected unauthorized, got {:?}", x.map(|ci| ci.id)),
+    }
+
+    let gerrit = Gerrit::with_credentials(server.url(), Arc::new(BearerTokenProvider::new("wrong")));
+    match gerrit.changes().get_change(&ChangeId::Number(21), None) {
+        Err(GGRError::GerritApiError(GerritError::Unauthorized(_))) => {},
+        x => panic!("expected unauthorized, got {:?}", x.map(|ci| ci.id)),
+    }
+}
+
+#[test]
+fn test_fakegerrit_git_credential_helper() {
+    let (server, _) = server(AuthScheme::Digest);
+
+    let store = server.git_root().join("git-credentials");
+    let helper = format!("store --file={}", store.display());
+    let provider = || GitCredentialProvider::new().with_git_config(&[("credential.helper", &helper)]);
+    let entry = |password: &str| server.url().replace("http://", &format!("http://admin:{}@", password));
+
+    // a stale password is rejected and removed from the helper
+    fs::write(&store, format!("{}\n", entry("wrong"))).unwrap();
+    let gerrit = Gerrit::with_credentials(server.url(), Arc::new(provider()));
+    assert!(gerrit.changes().get_change(&ChangeId::Number(21), None).is_err());
+    assert!(!fs::read_to_string(&store).unwrap().contains("wrong"));
+
+    fs::write(&store, format!("{}\n", entry("secret"))).unwrap();
+    let gerrit = Gerrit::with_credentials(server.url(), Arc::new(provider()));
+    assert_eq!(gerrit.changes().get_change(&ChangeId::Number(21), None).unwrap()._number, 21);
+    assert!(fs::read_to_string(&store).unwrap().contains("secret"));
+}
+
+#[test]
+fn test_fakegerrit_anonymous() {
+    let (server, mut authenticated) = server(AuthScheme::Digest);
+
+    // no credentials at all
+    let gerrit = Gerrit::with_credentials(server.url(), Arc::new(ChainProvider::new()));
+    let changes = gerrit.changes().query_changes(Some(vec!(Query::topic("other"))), None).unwrap();
+    assert_eq!(changes[0]._number, 23);
+    assert!(server.requests().iter().any(|r| r.starts_with("GET /changes/?")));
+
+    match gerrit.changes().abandon_change(&ChangeId::from(&changes[0]), None, None) {
+        Err(GGRError::GerritApiError(GerritError::AnonymousReadOnly(_))) => {},
+        x => panic!("expected a read only error, got {:?}", x.map(|ci| ci.id)),
+    }
+    assert!(!server.requests().iter().any(|r| r.contains("abandon")));
+
+    // credentials available, but anonymous forced
+    authenticated.set_auth_mode(AuthMode::Anonymous);
+    assert_eq!(authenticated.changes().get_change(&ChangeId::Number(23), None).unwrap()._number, 23);
+    assert!(authenticated.changes().abandon_change(&ChangeId::Number(23), None, None).is_err());
+    assert!(!server.requests().iter().any(|r| r.starts_with("GET /a/")));
+}
+
+#[test]
+fn test_fakegerrit_anonymous_headers() {
+    let (server, _) = server(AuthScheme::Basic);
+    server.set_session_cookie("GerritAccount=abc");
+
+    let mut gerrit = Gerrit::with_credentials(server.url(), Arc::new(BearerTokenProvider::new("token-of-admin")));
+    assert_eq!(gerrit.changes().get_change(&ChangeId::Number(21), None).unwrap()._number, 21);
+    assert!(server.request_headers("authorization").iter().all(|a| a.is_some()));
+    let sent = server.requests().len();
+
+    // neither the token nor the session cookie goes out with anonymous requests
+    gerrit.set_auth_mode(AuthMode::Anonymous);
+    assert_eq!(gerrit.changes().get_change(&ChangeId::Number(21), None).unwrap()._number, 21);
+    assert_eq!(gerrit.changes().get_change(&ChangeId::Number(22), None).unwrap()._number, 22);
+    assert_eq!(server.requests().len(), sent + 2);
+    assert_eq!(server.request_headers("authorization")[sent..].to_vec(), vec!(None, None));
+    assert_eq!(server.request_headers("cookie")[sent..].to_vec(), vec!(None, None));
+}
+
+#[test]
+fn test_fakegerrit_login_on_demand() {
+    let (server, _) = server(AuthScheme::Digest);
+
+    // a prompt of git which answers with the credentials of admin and counts the questions
+    let prompts = server.git_root().join("prompts");
+    let askpass = server.git_root().join("askpass.sh");
+    fs::write(&askpass, format!("#!/bin/sh\necho \"$1\" >> {}\ncase \"$1\" in Username*) echo admin ;; *) echo secret ;; esac\n",
+                                prompts.display())).unwrap();
+    fs::set_permissions(&askpass, fs::Permissions::from_mode(0o755)).unwrap();
+    let askpass = askpass.display().to_string();
+    let interactive = || GitCredentialProvider::new()
+        .interactive(true)
+        .with_git_config(&[("credential.helper", ""), ("core.askPass", &askpass)]);
+    let asked = || fs::read_to_string(&prompts).map(|x| x.lines().count()).unwrap_or(0);
+
+    // no credentials stored, read calls are anonymous without a prompt
+    let gerrit = Gerrit::with_credentials(server.url(), Arc::new(ChainProvider::new().with(interactive())));
+    assert!(!gerrit.is_authenticated());
+    assert_eq!(gerrit.changes().get_change(&ChangeId::Number(21), None).unwrap()._number, 21);
+    assert_eq!(asked(), 0);
+
+    // the server requires an authentication, git asks once for username and password
+    assert_eq!(gerrit.accounts().get_self().unwrap().username, Some("admin".into()));
+    assert_eq!(asked(), 2);
+    assert!(gerrit.is_authenticated());
+    assert_eq!(gerrit.changes().get_change(&ChangeId::Number(21), None).unwrap()._number, 21);
+    assert_eq!(asked(), 2);
+    assert!(server.requests().iter().any(|r| r.starts_with("GET /accounts/self")));
+    assert!(server.requests().iter().any(|r| r.starts_with("GET /a/changes/21")));
+
+    // a mutating call needs credentials
+    let gerrit = Gerrit::with_credentials(server.url(), Arc::new(ChainProvider::new().with(interactive())));
+    assert_eq!(gerrit.changes().abandon_change(&ChangeId::Number(23), None, None).unwrap().status, ChangeInfoChangeStatus::ABANDONED);
+    assert_eq!(asked(), 4);
+}
+
+#[test]
+fn test_fakegerrit_query_multi() {
+    let (server, gerrit) = server(AuthScheme::Digest);
+
+    let queries = vec!(vec!(Query::topic("feature x+y"), Query::status("open")), vec!(Query::topic("other")));
+    let results = gerrit.changes().query_changes_multi(queries, None).unwrap();
+    let numbers: Vec<Vec<u64>> = results.iter().map(|cis| cis.iter().map(|ci| ci._number).collect()).collect();
+    assert_eq!(numbers, vec!(vec!(21, 22), vec!(23)));
+
+    // an empty query is not sent
+    let sent = server.requests().len();
+    let queries = vec!(vec!(Query::topic("other")), vec!());
+    match gerrit.changes().query_changes_multi(queries, None) {
+        Err(GGRError::GerritApiError(GerritError::QueryEmpty)) => {},
+        x => panic!("expected an empty query, got {:?}", x.map(|r| r.len())),
+    }
+    assert_eq!(server.requests().len(), sent);
+}
+
+#[test]
+fn test_fakegerrit_server_info() {
+    let (server, gerrit) = server(AuthScheme::Digest);
+    server.model().version = "2.12.4".into();
+
+    let info = gerrit.config().get_server_info().unwrap();
+    assert_eq!(info.auth.auth_type, "HTTP");
+    assert!(info.download_scheme("http").is_none());
+    assert_eq!(gerrit.config().get_version().unwrap(), "2.12.4");
+    let received = server.requests().len();
+
+    // version and info are fetched once for all endpoints of a gerrit object and its clones
+    let clone = gerrit.clone();
+    assert_eq!(clone.config().get_server_info().unwrap().gerrit.all_projects, "All-Projects");
+    match clone.changes().move_change(&ChangeId::Number(21), "stable", None) {
+        Err(GGRError::GerritApiError(GerritError::UnsupportedVersion(..))) => {},
+        x => panic!("expected an unsupported version, got {:?}", x.map(|ci| ci.id)),
+    }
+    assert_eq!(server.requests().len(), received);
+}
+
+#[test]
+fn test_fakegerrit_change_ids() {
+    let (server, gerrit) = server(AuthScheme::Digest);
+    let triplet = "ops%2Ftools~master~I0000000000000000000000000000000000000031";
+    {
+        let mut model = server.model();
+        let mut change = model.change("21").unwrap().clone();
+        change["id"] = triplet.into();
+        change["project"] = "ops/tools".into();
+        change["change_id"] = "I0000000000000000000000000000000000000031".into();
+        change["_number"] = 31.into();
+        model.changes.push(change);
+    }
+
+    // a project with a slash, as typed by a user and as returned in `ChangeInfo.id`
+    let id: ChangeId = "ops/tools~master~I0000000000000000000000000000000000000031".parse().unwrap();
+    let ci = gerrit.changes().get_change(&id, None).unwrap();
+    assert_eq!(ci._number, 31);
+    assert_eq!(ChangeId::from(&ci), id);
+    assert_eq!(ci.id.parse::<ChangeId>().unwrap(), id);
+    assert!(server.requests().iter().any(|r| r.starts_with(&format!("GET /a/changes/{}", triplet))));
+
+    let abandoned = gerrit.changes().abandon_change(&id, None, None).unwrap();
+    assert_eq!(abandoned.status, ChangeInfoChangeStatus::ABANDONED);
+    assert_eq!(gerrit.changes().get_change(&ChangeId::Number(31), None).unwrap().status, ChangeInfoChangeStatus::ABANDONED);
+}
+
+#[test]
+fn test_fakegerrit_retries() {
+    let (server, mut gerrit) = server(AuthScheme::Basic);
+    gerrit.set_retry_policy(RetryPolicy {
+        retries: 2,
+        backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_secs(1),
+    });
+
+    server.fail_next(2, 503, None);
+    assert_eq!(gerrit.changes().get_change(&ChangeId::Number(21), None).unwrap()._number, 21);
+
+    server.fail_next(3, 502, None);
+    match gerrit.changes().get_change(&ChangeId::Number(21), None) {
+        Err(GGRError::GerritApiError(GerritError::ServerError(502, _))) => {},
+        x => panic!("expected a server error, got {:?}", x.map(|ci| ci.id)),
+    }
+
+    // only GET is repeated on server errors
+    let before = server.requests().len();
+    server.fail_next(1, 500, None);
+    assert!(gerrit.changes().abandon_change(&ChangeId::Number(22), None, None).is_err());
+    assert_eq!(server.requests().len(), before + 1);
+
+    // but all requests on 429, if the server doesn't ask to wait too long
+    server.fail_next(1, 429, Some(0));
+    assert!(gerrit.changes().abandon_change(&ChangeId::Number(22), None, None).is_ok());
+    server.fail_next(1, 429, Some(60));
+    match gerrit.changes().restore_change(&ChangeId::Number(22), None) {
+        Err(GGRError::GerritApiError(GerritError::GerritApi(429, _))) => {},
+        x => panic!("expected too many requests, got {:?}", x.map(|ci| ci.id)),
+    }
+}
+
+#[test]
+fn test_timeouts() {
+    // a server which accepts connections, but never answers
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let timeouts = Timeouts {
+        connect: Some(Duration::from_secs(1)),
+        total: Some(Duration::from_millis(200)),
+    };
+    let transport = CurlTransport::with_credentials(Arc::new(ChainProvider::new())).timeouts(timeouts);
+    let mut gerrit = Gerrit::with_transport(url, Arc::new(transport));
+    gerrit.set_retry_policy(RetryPolicy {
+        retries: 1,
+        backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_secs(1),
+    });
+
+    let start = Instant::now();
+    assert!(gerrit.changes().get_change(&ChangeId::Number(21), None).is_err());
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn test_fakegerrit_proxy() {
+    let (server, _) = server(AuthScheme::Basic);
+
+    let proxy = |no_proxy: Vec<String>| {
+        let proxy = ProxySettings {
+            url: Some(server.url().to_string()),
+            no_proxy: no_proxy,
+        };
+        let transport = CurlTransport::with_credentials(Arc::new(BearerTokenProvider::new("token-of-admin"))).proxy(proxy);
+        let mut gerrit = Gerrit::with_transport("http://gerrit.invalid", Arc::new(transport));
+        gerrit.set_retry_policy(RetryPolicy::none());
+        gerrit
+    };
+
+    // the host is only reachable through the proxy
+    assert_eq!(proxy(vec!()).changes().get_change(&ChangeId::Number(21), None).unwrap()._number, 21);
+    assert!(proxy(vec!("invalid".into())).changes().get_change(&ChangeId::Number(21), None).is_err());
+}
+
+#[test]
+fn test_gerrit_builder_invalid_url() {
+    for url in &["not a url", "ftp://gerrit.example.com", "file:///tmp/gerrit"] {
+        match GerritBuilder::new(*url).build() {
+            Err(GGRError::GerritApiError(GerritError::InvalidUrl(ref u, _))) => assert_eq!(u, url),
+            Err(x) => panic!("wrong error for {}: {}", url, x),
+            Ok(_) => panic!("{} accepted", url),
+        }
+    }
+}
+
+#[test]
+fn test_fakegerrit_shared_session() {
+    let (server, _) = server(AuthScheme::Basic);
+    server.set_session_cookie("GerritAccount=abc");
+
+    let gerrit = GerritBuilder::new(server.url())
+        .credentials(Arc::new(UserPasswordProvider::new("admin", "secret")))
+        .user_agent("ggr-test/1.0")
+        .build()
+        .unwrap();
+
+    assert_eq!(gerrit.changes().get_change(&ChangeId::Number(21), None).unwrap()._number, 21);
+
+    // all threads share one gerrit and its session
+    let gerrit = &gerrit;
+    thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(move || {
+                assert_eq!(gerrit.changes().get_change(&ChangeId::Number(22), None).unwrap()._number, 22);
+            });
+        }
+    });
+
+    let cookies = server.request_headers("cookie");
+    // curl may send each request twice, first without auth to get the challenge
+    assert!(cookies.len() >= 5);
+    assert_eq!(cookies[0], None);
+    for cookie in &cookies[1..] {
+        assert!(cookie.as_ref().map_or(false, |c| c.contains("GerritAccount=abc")), "{:?}", cookie);
+    }
+    assert!(server.request_headers("user-agent").iter().all(|ua| ua.as_ref().map(|s| s.as_str()) == Some("ggr-test/1.0")));
+}
@@ -0,0 +1,212 @@
+//! replays recorded interactions of `tests/fixtures` without a gerrit server
+
+extern crate fakegerrit;
+extern crate libgerrit;
+extern crate serde_json;
+
+use fakegerrit::{AuthScheme, FakeGerrit};
+use libgerrit::cassette::{self, Cassette, Mode, ReplayTransport};
+use libgerrit::call::AuthMode;
+use libgerrit::credentials::UserPasswordProvider;
+use libgerrit::entities::{self, ChangeInfoChangeStatus, ListChangesOption, ReviewerState, RevisionInfoChangeKind};
+use libgerrit::error::GGRResult;
+use libgerrit::gerrit::Gerrit;
+use libgerrit::ids::ChangeId;
+use libgerrit::query::Query;
+use libgerrit::transport::CurlTransport;
+use std::env;
+use std::fs;
+use std::sync::Arc;
+
+fn fixture(cassette: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), cassette)
+}
+
+fn gerrit(cassette: &str) -> Gerrit {
+    let transport = ReplayTransport::load(fixture(cassette)).expect("cassette not loadable");
+    Gerrit::with_transport("http://localhost:8080", Arc::new(transport))
+}
+
+/// the requests of `test_replay_topic`: the open changes of a topic and the reviewers of the first
+fn query_topic(gerrit: &Gerrit) -> (Vec<entities::ChangeInfo>, Vec<entities::ReviewerInfo>) {
+    let query = vec!(Query::topic("feature x+y"), Query::status("open"));
+    let changes = gerrit.changes()
+        .query_changes(Some(query), Some(vec!(ListChangesOption::CURRENT_REVISION)))
+        .unwrap();
+    let reviewers = gerrit.changes().get_reviewers(&ChangeId::from(&changes[0])).unwrap();
+    (changes, reviewers)
+}
+
+/// records `fixtures/topic.json` from the fake gerrit server, run it with `cargo test -- --ignored`
+#[test]
+#[ignore]
+fn record_topic() {
+    let server = FakeGerrit::start(include_str!("fixtures/fakegerrit.json"), AuthScheme::Digest).unwrap();
+    let jane = r#"[{"_account_id": 1000001, "name": "Jane Reviewer", "email": "jane@example.com",
+                    "username": "jane", "approvals": {"Code-Review": "+2"}}]"#;
+    server.model().reviewers.insert("tools~master~I0000000000000000000000000000000000000021".into(),
+                                    serde_json::from_str(jane).unwrap());
+
+    let curl = CurlTransport::with_credentials(Arc::new(UserPasswordProvider::new("admin", "secret")));
+    let transport = cassette::wrap(Mode::Record, fixture("topic.json"), Arc::new(curl)).unwrap();
+    query_topic(&Gerrit::with_transport(server.url(), transport));
+}
+
+#[test]
+fn test_replay_topic() {
+    let gerrit = gerrit("topic.json");
+    let (changes, reviewers) = query_topic(&gerrit);
+
+    let numbers: Vec<u64> = changes.iter().map(|ci| ci._number).collect();
+    assert_eq!(numbers, vec!(21, 22));
+    assert_eq!(changes[0].topic, Some("feature x+y".into()));
+
+    assert_eq!(reviewers[0].name, Some("Jane Reviewer".into()));
+    assert_eq!(reviewers[0].approvals["Code-Review"], "+2");
+
+    // every interaction is served only once
+    assert!(gerrit.changes().get_reviewers(&ChangeId::from(&changes[0])).is_err());
+}
+
+/// the request of the version cassettes: the open changes with all details
+fn query_open(gerrit: &Gerrit) -> GGRResult<Vec<entities::ChangeInfo>> {
+    let options = vec!(ListChangesOption::DETAILED_LABELS, ListChangesOption::DETAILED_ACCOUNTS,
+                       ListChangesOption::CURRENT_REVISION, ListChangesOption::CURRENT_COMMIT,
+                       ListChangesOption::MESSAGES);
+    gerrit.changes().query_changes(Some(vec!(Query::status("open"))), Some(options))
+}
+
+/// replays the open changes of a version cassette and returns them with the raw json response
+fn replay_open(cassette: &str, auth: AuthMode) -> (Vec<entities::ChangeInfo>, serde_json::Value) {
+    let recorded = Cassette::load(cassette).unwrap();
+    let raw = serde_json::from_str(recorded.interactions[0].response.body.trim_start_matches(")]}'")).unwrap();
+
+    let transport = ReplayTransport::new(recorded);
+    let mut gerrit = Gerrit::with_transport("http://localhost:8080", Arc::new(transport));
+    gerrit.set_auth_mode(auth);
+
+    (query_open(&gerrit).unwrap(), raw)
+}
+
+/// queries the open changes of a synthetic cassette
+///
+/// The `synthetic-*.json` cassettes are written by hand after the REST documentation of each
+/// gerrit version, they are not captured from real servers. The captured responses are in
+/// `fixtures/corpus`, see `record_corpus`.
+fn synthetic(cassette: &str) -> (Vec<entities::ChangeInfo>, serde_json::Value) {
+    replay_open(&fixture(cassette), AuthMode::Auto)
+}
+
+/// records `fixtures/corpus/gerrit-<version>.json` from the real server `$GERRIT_CORPUS_URL`
+///
+/// The open changes are queried anonymously, run it once for a server of each version with
+/// `GERRIT_CORPUS_URL=https://... cargo test --test replay record_corpus -- --ignored`.
+#[test]
+#[ignore]
+fn record_corpus() {
+    let url = env::var("GERRIT_CORPUS_URL").expect("GERRIT_CORPUS_URL is not set");
+    let mut gerrit = Gerrit::new(url.clone());
+    gerrit.set_auth_mode(AuthMode::Anonymous);
+    let version = gerrit.config().get_version().unwrap();
+
+    fs::create_dir_all(fixture("corpus")).unwrap();
+    let cassette = fixture(&format!("corpus/gerrit-{}.json", version));
+    let transport = cassette::wrap(Mode::Record, &cassette, Arc::new(CurlTransport::new())).unwrap();
+    let mut gerrit = Gerrit::with_transport(url, transport);
+    gerrit.set_auth_mode(AuthMode::Anonymous);
+    assert!(!query_open(&gerrit).unwrap().is_empty());
+}
+
+/// every captured response of `fixtures/corpus` is deserialized without losing a value
+#[test]
+fn test_corpus() {
+    let corpus = match fs::read_dir(fixture("corpus")) {
+        Ok(corpus) => corpus,
+        Err(_) => return,
+    };
+
+    for cassette in corpus {
+        let cassette = cassette.unwrap().path().display().to_string();
+        let (changes, raw) = replay_open(&cassette, AuthMode::Anonymous);
+        assert!(!changes.is_empty(), "{}", cassette);
+        assert_lossless(&raw, &serde_json::to_value(&changes).unwrap(), &cassette);
+    }
+}
+
+/// every value of `raw` is also part of `serialized`, `null` values are ignored
+fn assert_lossless(raw: &serde_json::Value, serialized: &serde_json::Value, path: &str) {
+    match (raw, serialized) {
+        (&serde_json::Value::Object(ref raw), &serde_json::Value::Object(ref serialized)) => {
+            for (key, value) in raw {
+                let serialized = serialized.get(key).unwrap_or_else(|| panic!("{}/{} is lost", path, key));
+                assert_lossless(value, serialized, &format!("{}/{}", path, key));
+            }
+        },
+        (&serde_json::Value::Array(ref raw), &serde_json::Value::Array(ref serialized)) => {
+            assert_eq!(raw.len(), serialized.len(), "{}", path);
+            for (i, (raw, serialized)) in raw.iter().zip(serialized.iter()).enumerate() {
+                assert_lossless(raw, serialized, &format!("{}/{}", path, i));
+            }
+        },
+        _ => assert_eq!(raw, serialized, "{}", path),
+    }
+}
+
+#[test]
+fn test_synthetic_2_9() {
+    let (changes, raw) = synthetic("synthetic-2.9.json");
+    assert_lossless(&raw, &serde_json::to_value(&changes).unwrap(), "");
+
+    let change = &changes[0];
+    assert_eq!(change.status, ChangeInfoChangeStatus::NEW);
+    assert_eq!(change._sortkey, Some("002cbc25000004e5".into()));
+    assert_eq!(change.created.to_string(), "2014-05-05 07:15:44.639000000");
+    // 2.9 has no diffstat
+    assert_eq!((change.insertions, change.deletions), (0, 0));
+
+    let labels = change.labels.as_ref().unwrap();
+    assert_eq!(labels["Code-Review"].vote_range(), Some((0, 1)));
+    assert_eq!(labels["Code-Review"].recommended.as_ref().unwrap().name, Some("Jane Roe".into()));
+
+    let revision = &change.revisions.as_ref().unwrap()["184ebe53805e102605d11f6b143486d15c23a09c"];
+    assert!(revision.kind.is_none());
+    assert_eq!(revision.fetch["git"].get_reference_string(), "1253/1");
+}
+
+#[test]
+fn test_synthetic_2_13() {
+    let (changes, raw) = synthetic("synthetic-2.13.json");
+    assert_lossless(&raw, &serde_json::to_value(&changes).unwrap(), "");
+
+    let change = &changes[0];
+    assert_eq!((change.insertions, change.deletions), (34, 101970));
+    assert_eq!(change.reviewers.as_ref().unwrap()[&ReviewerState::REVIEWER].len(), 2);
+    assert_eq!(change.labels.as_ref().unwrap()["Code-Review"].vote_range(), Some((-1, 0)));
+
+    let revision = &change.revisions.as_ref().unwrap()["6ec8ec3a0a50a6d1a4e7f2a1b3ba5c2d7a4b2c61"];
+    assert_eq!(revision.kind, Some(RevisionInfoChangeKind::TRIVIAL_REBASE));
+    assert_eq!(revision.uploader.as_ref().unwrap()._account_id, Some(1000096));
+    assert!(change.extra.is_empty());
+}
+
+#[test]
+fn test_synthetic_3() {
+    let (changes, raw) = synthetic("synthetic-3.x.json");
+    assert_lossless(&raw, &serde_json::to_value(&changes).unwrap(), "");
+
+    let change = &changes[0];
+    assert_eq!(change.id, "myProject~4712");
+    assert_eq!(change.extra["total_comment_count"], 2);
+    assert!(change.extra.contains_key("submit_requirements"));
+    assert_eq!(change.owner.extra["display_name"], "John");
+    assert_eq!(change.reviewers.as_ref().unwrap()[&ReviewerState::CC][0].username, Some("watcher".into()));
+
+    let all = change.labels.as_ref().unwrap()["Code-Review"].all.as_ref().unwrap();
+    assert_eq!(all[1].tag, Some("autogenerated:bot".into()));
+    assert_eq!(all[1].extra["permitted_voting_range"]["max"], 2);
+
+    let revision = &change.revisions.as_ref().unwrap()["3f5c1e0b2d7a4c6e8f9a1b3d5c7e9f0a2b4c6d8e"];
+    assert_eq!(revision.kind, Some(RevisionInfoChangeKind::REWORK));
+    assert!(revision.fetch.is_empty());
+    assert_eq!(revision.extra["real_uploader"]["_account_id"], 1000096);
+}
@@ -0,0 +1,99 @@
+//! Timestamps of gerrit entities
+//!
+//! Gerrit returns all timestamps in UTC with the format `yyyy-mm-dd hh:mm:ss.fffffffff`, e.g.
+//! `2013-02-01 09:59:32.126000000`. `Timestamp` (de)serializes this format.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use error::GGRError;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// format of gerrit timestamps
+const FORMAT: &'static str = "%Y-%m-%d %H:%M:%S%.9f";
+
+/// A point in time in UTC, as used by gerrit entities
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(pub DateTime<Utc>);
+
+impl Timestamp {
+    /// the timestamp as chrono `DateTime`
+    pub fn datetime(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+impl From<DateTime<Utc>> for Timestamp {
+    fn from(datetime: DateTime<Utc>) -> Timestamp {
+        Timestamp(datetime)
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0.format(FORMAT))
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = GGRError;
+
+    /// parses the gerrit format, also without fractions of seconds or only a date like
+    /// `2017-03-01`, which is midnight UTC
+    fn from_str(s: &str) -> Result<Timestamp, GGRError> {
+        let s = s.trim();
+
+        let naive = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f")
+            .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M"))
+            .ok()
+            .or_else(|| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok().and_then(|d| d.and_hms_opt(0, 0, 0)));
+
+        match naive {
+            Some(naive) => Ok(Timestamp(Utc.from_utc_datetime(&naive))),
+            None => Err(GGRError::General(format!("'{}' is not a timestamp like 'yyyy-mm-dd hh:mm:ss'", s))),
+        }
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D>(deserializer: D) -> Result<Timestamp, D::Error>
+    where D: Deserializer<'de> {
+        struct TimestampVisitor;
+
+        impl<'de> Visitor<'de> for TimestampVisitor {
+            type Value = Timestamp;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a timestamp like 'yyyy-mm-dd hh:mm:ss.fffffffff'")
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Timestamp, E>
+            where E: de::Error {
+                value.parse().map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))
+            }
+        }
+
+        deserializer.deserialize_str(TimestampVisitor)
+    }
+}
+
+#[test]
+fn test_timestamp() {
+    use serde_json;
+
+    let ts: Timestamp = serde_json::from_str("\"2013-02-01 09:59:32.126000000\"").unwrap();
+    assert_eq!(ts, "2013-02-01 09:59:32.126".parse().unwrap());
+    assert_eq!(serde_json::to_string(&ts).unwrap(), "\"2013-02-01 09:59:32.126000000\"");
+
+    assert_eq!("2013-02-01".parse::<Timestamp>().unwrap().to_string(), "2013-02-01 00:00:00.000000000");
+    assert!("2013-02-01 09:59".parse::<Timestamp>().unwrap() < ts);
+    assert!(serde_json::from_str::<Timestamp>("\"yesterday\"").is_err());
+}
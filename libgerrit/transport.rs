@@ -0,0 +1,534 @@
+//! Pluggable http transport of `call::Call`
+//!
+//! A `Transport` sends one `Request` and returns the raw `CallResponse`. The default transport is
+//! `CurlTransport`. Applications with their own http stack or tests without a gerrit server can
+//! provide their own implementation with `Call::with_transport` or `Gerrit::with_transport`.
+
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::io::{Read, Write};
+use std::os::raw::{c_char, c_int};
+use std::path::PathBuf;
+use std::ptr;
+use std::str;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use curl;
+use git2;
+use url;
+
+use call::CallMethod;
+use call::CallResponse;
+use credentials::{self, Credential, CredentialProvider};
+use error::GGRError;
+use error::GerritError;
+use error::GGRResult;
+
+/// A http request handed to a `Transport`
+#[derive(Clone, Debug)]
+pub struct Request {
+    /// http method of the request
+    pub method: CallMethod,
+    /// complete url with path and query
+    pub url: url::Url,
+    /// additional request headers as `(name, value)` pairs
+    pub headers: Vec<(String, String)>,
+    /// body of the request, json encoded for `Post` and `Put`
+    pub body: Option<Vec<u8>>,
+    /// false for anonymous requests, they are sent without credentials and session cookies
+    pub authenticated: bool,
+}
+
+/// Sends requests to a gerrit server
+///
+/// Implementations are responsible for the authentication of `Request::authenticated` requests,
+/// see `credentials`. The response body is returned as received, the XSSI prefix is stripped by
+/// `call::Call`.
+pub trait Transport: Send + Sync {
+    /// sends `request` and returns the response of the server
+    fn send(&self, request: &Request) -> GGRResult<CallResponse>;
+
+    /// true if requests to `url` are sent with credentials
+    ///
+    /// `call::AuthMode::Auto` uses anonymous requests if this is false. Implementations must not
+    /// ask the user here, see `login`.
+    fn authenticates(&self, _url: &url::Url) -> bool {
+        true
+    }
+
+    /// asks for credentials for `url`, e.g. on the terminal, because the server requires an
+    /// authentication. Returns true if requests to `url` are sent with credentials afterwards.
+    fn login(&self, url: &url::Url) -> bool {
+        self.authenticates(url)
+    }
+}
+
+/// Timeouts of a `CurlTransport`, `None` waits forever
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timeouts {
+    /// maximum time to connect to the server
+    pub connect: Option<Duration>,
+    /// maximum time of a request, including the connect
+    pub total: Option<Duration>,
+}
+
+impl Default for Timeouts {
+    /// 10 seconds to connect and 60 seconds for a request
+    fn default() -> Timeouts {
+        Timeouts {
+            connect: Some(Duration::from_secs(10)),
+            total: Some(Duration::from_secs(60)),
+        }
+    }
+}
+
+/// TLS settings of a `CurlTransport` and of git fetches
+///
+/// Without values the system defaults are used.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TlsSettings {
+    /// file with the certificates of the certificate authorities (PEM)
+    pub ca_bundle: Option<PathBuf>,
+    /// client certificate for servers which require one (PEM)
+    pub client_cert: Option<PathBuf>,
+    /// private key of the client certificate (PEM)
+    pub client_key: Option<PathBuf>,
+}
+
+/// `GIT_OPT_SET_SSL_CERT_LOCATIONS` of `git_libgit2_opts`
+const GIT_OPT_SET_SSL_CERT_LOCATIONS: c_int = 12;
+
+extern "C" {
+    // not exported by libgit2-sys, the symbol is part of the libgit2 linked through git2
+    fn git_libgit2_opts(option: c_int, ...) -> c_int;
+}
+
+impl TlsSettings {
+    /// applies the certificate authorities to git fetches with git2
+    ///
+    /// The file is set for the TLS context of libgit2, not through the environment of the
+    /// process. libgit2 has no client certificates, see `check_git2_fetch`.
+    pub fn init_git2(&self) -> GGRResult<()> {
+        let ca_bundle = match self.ca_bundle {
+            Some(ref ca_bundle) => ca_bundle,
+            None => return Ok(()),
+        };
+        let file = ca_bundle.to_str()
+            .and_then(|file| CString::new(file).ok())
+            .ok_or_else(|| GGRError::General(format!("ca bundle path {} is not usable for git fetches", ca_bundle.display())))?;
+
+        // initializes libgit2 and its TLS context, like every git2 call
+        let _ = git2::Config::new();
+        let ret = unsafe { git_libgit2_opts(GIT_OPT_SET_SSL_CERT_LOCATIONS, file.as_ptr(), ptr::null::<c_char>()) };
+        if ret < 0 {
+            return Err(GGRError::General(format!("ca bundle {} is not usable for git fetches: {}",
+                                                 ca_bundle.display(), git2::Error::last_error(ret).map(|e| e.to_string()).unwrap_or_default())));
+        }
+        Ok(())
+    }
+
+    /// returns an error if a git fetch of `url` can't use these settings
+    ///
+    /// libgit2 can't send a client certificate, a https fetch with a configured client certificate
+    /// is refused instead of sent without it.
+    pub fn check_git2_fetch(&self, url: &str) -> GGRResult<()> {
+        if self.client_cert.is_some() && url.starts_with("https://") {
+            return Err(GGRError::General(format!("git fetches can't use the client certificate, {} is not fetched", url)));
+        }
+        Ok(())
+    }
+}
+
+/// Proxy settings of a `CurlTransport` and of git fetches
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProxySettings {
+    /// url of the proxy like `http://proxy.example.com:3128`, credentials can be part of the url.
+    /// Without an url curl uses the `http_proxy` environment variables.
+    pub url: Option<String>,
+    /// hosts which are reached without proxy. A host matches itself and all its subdomains, `*`
+    /// matches all hosts.
+    pub no_proxy: Vec<String>,
+}
+
+impl ProxySettings {
+    /// returns the proxy url for requests to `url`, `None` for a direct connection
+    pub fn proxy_for(&self, url: &str) -> Option<&str> {
+        let proxy = match self.url {
+            Some(ref proxy) => proxy,
+            None => return None,
+        };
+
+        let host = url::Url::parse(url).ok()
+            .and_then(|url| url.host_str().map(|host| host.to_lowercase()))
+            .unwrap_or_default();
+
+        let excluded = self.no_proxy.iter().any(|entry| {
+            let entry = entry.trim().trim_start_matches('.').to_lowercase();
+            entry == "*" || host == entry || host.ends_with(&format!(".{}", entry))
+        });
+
+        if excluded { None } else { Some(proxy) }
+    }
+
+    /// proxy options for a git2 fetch from `url`
+    pub fn git2_proxy_options(&self, url: &str) -> git2::ProxyOptions<'static> {
+        let mut options = git2::ProxyOptions::new();
+        if let Some(proxy) = self.proxy_for(url) {
+            options.url(proxy);
+        }
+        options
+    }
+}
+
+/// Default transport based on curl
+///
+/// Credentials are taken from a `CredentialProvider`, by default from
+/// `credentials::default_provider`. For a username and password digest authentication is tried
+/// first and basic authentication if the server answers with `401`. Cookies and bearer tokens are
+/// sent as headers. Accepted credentials are approved and credentials which end with `401` are
+/// rejected at the provider. The credential of a server is looked up once, without asking the user,
+/// and cached until the server rejects it. Only `login` lets the provider prompt. Requests which exceed the `Timeouts` fail with a `GGRError::Curl`.
+/// Certificates and proxy are configured with `TlsSettings` and `ProxySettings`.
+///
+/// The transport is one session and can be used from several threads at once: every request
+/// takes an idle curl handle of a pool, so open connections are reused, and cookies set by the
+/// server are shared by all handles.
+pub struct CurlTransport {
+    handles: Mutex<Vec<curl::easy::Easy>>,
+    cookies: Mutex<Vec<String>>,
+    credentials: Arc<dyn CredentialProvider>,
+    known: Mutex<HashMap<String, Option<Credential>>>,
+    timeouts: Timeouts,
+    tls: TlsSettings,
+    proxy: ProxySettings,
+    user_agent: Option<String>,
+}
+
+impl CurlTransport {
+    /// create a new curl transport with the default credential provider
+    pub fn new() -> CurlTransport {
+        CurlTransport::with_credentials(Arc::new(credentials::default_provider()))
+    }
+
+    /// create a new curl transport which authenticates with credentials of `provider`
+    pub fn with_credentials(provider: Arc<dyn CredentialProvider>) -> CurlTransport {
+        CurlTransport {
+            handles: Mutex::new(Vec::new()),
+            cookies: Mutex::new(Vec::new()),
+            credentials: provider,
+            known: Mutex::new(HashMap::new()),
+            timeouts: Timeouts::default(),
+            tls: TlsSettings::default(),
+            proxy: ProxySettings::default(),
+            user_agent: None,
+        }
+    }
+
+    /// set the connect and request timeouts
+    pub fn timeouts(mut self, timeouts: Timeouts) -> CurlTransport {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// set the certificate authorities and the client certificate
+    pub fn tls(mut self, tls: TlsSettings) -> CurlTransport {
+        self.tls = tls;
+        self
+    }
+
+    /// set the proxy
+    pub fn proxy(mut self, proxy: ProxySettings) -> CurlTransport {
+        self.proxy = proxy;
+        self
+    }
+
+    /// set the `User-Agent` header, default is the one of libcurl
+    pub fn user_agent<S: Into<String>>(mut self, user_agent: S) -> CurlTransport {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// the credential for `url`, cached per server. With `prompt` the provider may ask the user if
+    /// no credential is stored.
+    fn credential(&self, url: &url::Url, prompt: bool) -> GGRResult<Option<Credential>> {
+        let key = url.origin().ascii_serialization();
+        if let Some(credential) = self.known.lock().unwrap_or_else(|x| x.into_inner()).get(&key) {
+            if credential.is_some() || !prompt {
+                return Ok(credential.clone());
+            }
+        }
+
+        let credential = if prompt {
+            self.credentials.credential(url)?
+        } else {
+            self.credentials.stored_credential(url)?
+        };
+        self.known.lock().unwrap_or_else(|x| x.into_inner()).insert(key, credential.clone());
+        Ok(credential)
+    }
+
+    /// rejects `credential` at the provider and removes it from the cache
+    fn reject(&self, url: &url::Url, credential: &Credential) {
+        self.known.lock().unwrap_or_else(|x| x.into_inner()).remove(&url.origin().ascii_serialization());
+        self.credentials.reject(url, credential);
+    }
+
+    /// one request with the authentication method `auth` and the `credential`
+    ///
+    /// The handle is taken from the pool of idle handles and given back afterwards, also if the
+    /// request fails.
+    fn send_with_auth(&self, request: &Request, auth: &curl::easy::Auth, credential: Option<&Credential>) -> GGRResult<CallResponse> {
+        let mut handle = self.handles.lock().unwrap_or_else(|x| x.into_inner()).pop().unwrap_or_else(curl::easy::Easy::new);
+        let response = self.send_with_handle(&mut handle, request, auth, credential);
+        self.store_cookies(&mut handle);
+        self.handles.lock().unwrap_or_else(|x| x.into_inner()).push(handle);
+        response
+    }
+
+    /// adds the cookies of `handle` to the cookies of the session
+    fn store_cookies(&self, handle: &mut curl::easy::Easy) {
+        let list = match handle.cookies() {
+            Ok(list) => list,
+            Err(x) => {
+                warn!("cookies not readable: {}", x);
+                return;
+            },
+        };
+
+        let mut cookies = self.cookies.lock().unwrap_or_else(|x| x.into_inner());
+        for cookie in list.iter().filter_map(|c| str::from_utf8(c).ok()) {
+            cookies.retain(|c| cookie_key(c) != cookie_key(cookie));
+            cookies.push(cookie.to_string());
+        }
+    }
+
+    /// one request through `handle`
+    fn send_with_handle(&self, handle: &mut curl::easy::Easy, request: &Request, auth: &curl::easy::Auth, credential: Option<&Credential>) -> GGRResult<CallResponse> {
+        debug!("request {} {}", request.method, request.url);
+
+        // options of the last request, e.g. username and password, are not reused. The cookies of
+        // the session are loaded into the emptied cookie engine of the handle, not for anonymous
+        // requests.
+        handle.reset();
+        handle.cookie_file("")?;
+        handle.cookie_list("ALL")?;
+        if request.authenticated {
+            for cookie in self.cookies.lock().unwrap_or_else(|x| x.into_inner()).iter() {
+                handle.cookie_list(cookie)?;
+            }
+        }
+        handle.http_auth(auth)?;
+        if let Some(ref user_agent) = self.user_agent {
+            handle.useragent(user_agent)?;
+        }
+        if let Some(connect) = self.timeouts.connect {
+            handle.connect_timeout(connect)?;
+        }
+        if let Some(total) = self.timeouts.total {
+            handle.timeout(total)?;
+        }
+        if let Some(ref ca_bundle) = self.tls.ca_bundle {
+            handle.cainfo(ca_bundle)?;
+        }
+        if let Some(ref client_cert) = self.tls.client_cert {
+            handle.ssl_cert(client_cert)?;
+        }
+        if let Some(ref client_key) = self.tls.client_key {
+            handle.ssl_key(client_key)?;
+        }
+        if self.proxy.url.is_some() {
+            // an empty proxy is a direct connection, also with `http_proxy` in the environment
+            handle.proxy(self.proxy.proxy_for(request.url.as_str()).unwrap_or(""))?;
+        }
+        if !self.proxy.no_proxy.is_empty() {
+            handle.noproxy(&self.proxy.no_proxy.join(","))?;
+        }
+
+        let mut headers = curl::easy::List::new();
+        headers.append("Accept: application/json")?;
+
+        match credential {
+            Some(&Credential::UserPassword { ref username, ref password }) => {
+                handle.username(username)?;
+                handle.password(password)?;
+            },
+            Some(&Credential::Cookie(ref cookie)) => handle.cookie(cookie)?,
+            Some(&Credential::BearerToken(ref token)) => headers.append(&format!("Authorization: Bearer {}", token))?,
+            None => {},
+        }
+
+        match request.method {
+            CallMethod::Get => handle.get(true)?,
+            CallMethod::Post => handle.custom_request("POST")?,
+            CallMethod::Put => handle.custom_request("PUT")?,
+            CallMethod::Delete => handle.custom_request("DELETE")?,
+        }
+
+        handle.url(request.url.as_str())?;
+
+        for &(ref key, ref value) in &request.headers {
+            headers.append(&format!("{}: {}", key, value))?;
+        }
+        if request.body.is_some() {
+            headers.append("Content-Type: application/json")?;
+        }
+        handle.http_headers(headers)?;
+
+        let mut out = Vec::new();
+        let (status, headers) = send_req(handle, &mut out, request.body.clone())?;
+        debug!("response: {}", status);
+
+        Ok(CallResponse::new(status, headers, out))
+    }
+}
+
+impl Default for CurlTransport {
+    fn default() -> CurlTransport {
+        CurlTransport::new()
+    }
+}
+
+impl Transport for CurlTransport {
+    fn authenticates(&self, url: &url::Url) -> bool {
+        match self.credential(url, false) {
+            Ok(credential) => credential.is_some(),
+            Err(x) => {
+                warn!("problem with credentials for {}: {}", url, x);
+                false
+            },
+        }
+    }
+
+    fn login(&self, url: &url::Url) -> bool {
+        match self.credential(url, true) {
+            Ok(credential) => credential.is_some(),
+            Err(x) => {
+                warn!("problem with credentials for {}: {}", url, x);
+                false
+            },
+        }
+    }
+
+    fn send(&self, request: &Request) -> GGRResult<CallResponse> {
+        if !request.authenticated {
+            return self.send_with_auth(request, &curl::easy::Auth::new(), None);
+        }
+
+        let credential = match self.credential(&request.url, false)? {
+            Some(credential) => credential,
+            None => return self.send_with_auth(request, &curl::easy::Auth::new(), None),
+        };
+
+        let response = match credential {
+            Credential::UserPassword { .. } => {
+                let mut response = None;
+                for auth in vec!(
+                    curl::easy::Auth::new().digest(true),
+                    curl::easy::Auth::new().basic(true),
+                ) {
+                    let r = self.send_with_auth(request, auth, Some(&credential))?;
+
+                    if r.status() == 401 /* Unauthorized */ {
+                        debug!("status 401 ... try other http method if available");
+                        continue;
+                    }
+                    response = Some(r);
+                    break;
+                }
+
+                match response {
+                    Some(response) => response,
+                    None => {
+                        self.reject(&request.url, &credential);
+                        return Err(GGRError::GerritApiError(GerritError::Unauthorized("No Authentication algorithm found for your gerrit server. 'basic' and 'digest' tested".into())));
+                    },
+                }
+            },
+            _ => self.send_with_auth(request, &curl::easy::Auth::new(), Some(&credential))?,
+        };
+
+        match response.status() {
+            401 => self.reject(&request.url, &credential),
+            200..=399 => self.credentials.approve(&request.url, &credential),
+            _ => {},
+        }
+
+        Ok(response)
+    }
+}
+
+/// domain, path and name of a cookie in the netscape format, which identify a cookie
+fn cookie_key(line: &str) -> (Option<&str>, Option<&str>, Option<&str>) {
+    let fields: Vec<&str> = line.split('\t').collect();
+    (fields.get(0).cloned(), fields.get(2).cloned(), fields.get(5).cloned())
+}
+
+/// interface function for `handle_req`, set some fields if body has content
+fn send_req<W: Write>(handle: &mut curl::easy::Easy,
+                      out: &mut W,
+                      body: Option<Vec<u8>>)
+                      -> GGRResult<(u32, Vec<String>)> {
+    match body {
+        Some(body) => {
+            let mut body = &body[..];
+            handle.upload(true)?;
+            handle.in_filesize(body.len() as u64)?;
+            handle_req(handle, out, &mut |buf| body.read(buf).unwrap_or(0))
+        }
+        None => {
+            handle.upload(false)?;
+            handle_req(handle, out, &mut |_| 0)
+        },
+    }
+}
+
+/// do the curl request
+fn handle_req<W: Write>(handle: &mut curl::easy::Easy,
+                        out: &mut W,
+                        read: &mut dyn FnMut(&mut [u8]) -> usize)
+                        -> GGRResult<(u32, Vec<String>)> {
+    let mut headers = Vec::new();
+    {
+        let mut handle = handle.transfer();
+        handle.read_function(|buf| Ok(read(buf)))?;
+        handle.write_function(|data| {
+                Ok(match out.write_all(data) {
+                    Ok(_) => data.len(),
+                    Err(_) => 0,
+                })
+            })?;
+        handle.header_function(|data| {
+                headers.push(String::from_utf8_lossy(data).into_owned());
+                true
+            })?;
+        handle.perform()?;
+    }
+
+    Ok((handle.response_code()?, headers))
+}
+
+#[test]
+fn test_proxy_for() {
+    let proxy = ProxySettings {
+        url: Some("http://proxy:3128".into()),
+        no_proxy: vec!("localhost".into(), ".intern.example.com".into()),
+    };
+
+    assert_eq!(proxy.proxy_for("https://review.example.com/a/changes/"), Some("http://proxy:3128"));
+    assert_eq!(proxy.proxy_for("https://gerrit.intern.example.com/tools"), None);
+    assert_eq!(proxy.proxy_for("https://intern.example.com/tools"), None);
+    assert_eq!(proxy.proxy_for("http://LOCALHOST:8080/"), None);
+    assert_eq!(proxy.proxy_for("http://otherlocalhost/"), Some("http://proxy:3128"));
+    assert_eq!(ProxySettings::default().proxy_for("https://review.example.com/"), None);
+}
+
+#[test]
+fn test_check_git2_fetch() {
+    let mut tls = TlsSettings::default();
+    assert!(tls.check_git2_fetch("https://gerrit.example.com/tools").is_ok());
+
+    tls.client_cert = Some("client.pem".into());
+    assert!(tls.check_git2_fetch("https://gerrit.example.com/tools").is_err());
+    assert!(tls.check_git2_fetch("http://gerrit.example.com/tools").is_ok());
+}
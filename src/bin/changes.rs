@@ -4,8 +4,10 @@
 use clap::{self, App, SubCommand, Arg};
 use libgerrit::error::GGRError;
 use libgerrit::error::GGRResult;
-use libgerrit::gerrit::Gerrit;
-use libgerrit::entities;
+use libgerrit::entities::{self, ListChangesOption};
+use libgerrit::ids::{ChangeId, RevisionId};
+use libgerrit::query::Query;
+use libgerrit::timestamp::Timestamp;
 use config;
 use gron::ToGron;
 use serde_json;
@@ -28,17 +30,26 @@ pub fn menu<'a, 'b>() -> App<'a, 'b> {
                      .short("f")
                 )
                 .arg(Arg::with_name("ofields")
-                     .help("return optional fields information")
+                     .help("return optional fields information, e.g. CURRENT_REVISION or MESSAGES")
                      .short("o")
                      .takes_value(true)
                      .multiple(true)
+                     .validator(|o| o.parse::<ListChangesOption>().map(|_| ()).map_err(|e| e.to_string()))
                 )
                 .arg(Arg::with_name("userquery")
                      .help("user query for changes")
-                     .required(true)
+                     .required_unless("query")
                      .multiple(true)
                      .takes_value(true)
                 )
+                .arg(Arg::with_name("query")
+                     .help("an additional query, repeat it to run several queries in one request")
+                     .long("query")
+                     .short("q")
+                     .takes_value(true)
+                     .multiple(true)
+                     .number_of_values(1)
+                )
                 .arg(Arg::with_name("fieldslist")
                      .help("get all fields useable for --fields options")
                      .short("l")
@@ -56,15 +67,111 @@ pub fn menu<'a, 'b>() -> App<'a, 'b> {
                      .short("u")
                      .conflicts_with("raw")
                 )
+                .arg(Arg::with_name("limit")
+                     .help("return at most this number of changes")
+                     .long("limit")
+                     .takes_value(true)
+                )
+                .arg(Arg::with_name("start")
+                     .help("skip this number of changes of the result")
+                     .long("start")
+                     .takes_value(true)
+                )
+                .arg(Arg::with_name("since")
+                     .help("only changes updated at or after this UTC time, e.g. '2017-03-01' or \
+                            '2017-03-01 12:00:00'. Added to the query as 'after:'.")
+                     .long("since")
+                     .takes_value(true)
+                     .validator(|t| t.parse::<Timestamp>().map(|_| ()).map_err(|e| e.to_string()))
+                )
+                .arg(Arg::with_name("until")
+                     .help("only changes updated at or before this UTC time. Added to the query as \
+                            'before:'.")
+                     .long("until")
+                     .takes_value(true)
+                     .validator(|t| t.parse::<Timestamp>().map(|_| ()).map_err(|e| e.to_string()))
+                )
+                .arg(Arg::with_name("sort")
+                     .help("sort changes by this timestamp, oldest first")
+                     .long("sort")
+                     .takes_value(true)
+                     .possible_values(&["created", "updated", "submitted"])
+                )
         )
         .subcommand(SubCommand::with_name("fetch")
                     .about("get one change and his ancestors")
                     .arg(Arg::with_name("changeid")
                          .help("the changeid which needs fetched")
                          .takes_value(true)
+                         .validator(|c| c.parse::<ChangeId>().map(|_| ()).map_err(|e| e.to_string()))
+                         .required(true)
+                         .index(1)
+                    )
+        )
+        .subcommand(SubCommand::with_name("comment")
+                    .about("add an inline comment to a file of a change")
+                    .arg(Arg::with_name("changeid")
+                         .help("the change to comment on")
+                         .takes_value(true)
+                         .validator(|c| c.parse::<ChangeId>().map(|_| ()).map_err(|e| e.to_string()))
+                         .required(true)
+                         .index(1)
+                    )
+                    .arg(Arg::with_name("file")
+                         .help("path of the commented file")
+                         .takes_value(true)
+                         .required(true)
+                         .index(2)
+                    )
+                    .arg(Arg::with_name("line")
+                         .help("line number of the comment, without a file comment is added")
+                         .takes_value(true)
+                         .long("line")
+                         .short("l")
+                    )
+                    .arg(Arg::with_name("message")
+                         .help("the comment text")
+                         .takes_value(true)
+                         .required(true)
+                         .long("message")
+                         .short("m")
+                    )
+                    .arg(Arg::with_name("revision")
+                         .help("revision to comment on")
+                         .takes_value(true)
+                         .validator(|r| r.parse::<RevisionId>().map(|_| ()).map_err(|e| e.to_string()))
+                         .default_value("current")
+                         .long("revision")
+                         .short("r")
+                    )
+                    .arg(Arg::with_name("draft")
+                         .help("save the comment as draft instead of publishing it")
+                         .long("draft")
+                         .short("d")
+                    )
+        )
+        .subcommand(SubCommand::with_name("comments")
+                    .about("list inline comments of a change")
+                    .arg(Arg::with_name("changeid")
+                         .help("the change to list comments for")
+                         .takes_value(true)
+                         .validator(|c| c.parse::<ChangeId>().map(|_| ()).map_err(|e| e.to_string()))
                          .required(true)
                          .index(1)
                     )
+                    .arg(Arg::with_name("drafts")
+                         .help("list own draft comments of a revision instead of published comments")
+                         .long("drafts")
+                         .short("d")
+                    )
+                    .arg(Arg::with_name("revision")
+                         .help("revision to list drafts for")
+                         .takes_value(true)
+                         .validator(|r| r.parse::<RevisionId>().map(|_| ()).map_err(|e| e.to_string()))
+                         .default_value("current")
+                         .long("revision")
+                         .short("r")
+                    )
         )
 }
 
@@ -73,10 +180,15 @@ pub fn menu<'a, 'b>() -> App<'a, 'b> {
 /// Currently implemented sub commands:
 ///
 /// * query
+/// * fetch
+/// * comment
+/// * comments
 pub fn manage(x: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
     match x.subcommand() {
         ("query", Some(y)) => { query(y, config) },
         ("fetch", Some(y)) => { fetch(y, config) },
+        ("comment", Some(y)) => { comment(y, config) },
+        ("comments", Some(y)) => { comments(y, config) },
         _ => {
             println!("{}", x.usage());
             Ok(())
@@ -86,13 +198,23 @@ pub fn manage(x: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
 
 /// create, call and prints queries to a gerrit server
 fn query(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
-    let mut gerrit = Gerrit::new(config.get_base_url());
+    let gerrit = config.gerrit(config.credentials())?;
     let mut changes = gerrit.changes();
 
-    let userquery = y.values_of_lossy("userquery");
-    if userquery.is_none() {
+    // every `--query` is one query, the terms of it are separated by whitespace
+    let mut queries: Vec<Vec<Query>> = Vec::new();
+    if let Some(userquery) = y.values_of_lossy("userquery") {
+        queries.push(userquery.into_iter().map(Query::from).collect());
+    }
+    for query in y.values_of_lossy("query").unwrap_or_default() {
+        queries.push(query.split_whitespace().map(Query::from).collect());
+    }
+    if queries.is_empty() || queries.iter().any(|q| q.is_empty()) {
         return Err(GGRError::General("No or bad userquery".into()));
     };
+    let names: Vec<String> = queries.iter()
+        .map(|q| q.iter().map(|term| term.to_string()).collect::<Vec<_>>().join(" "))
+        .collect();
 
     let regsel = match y.values_of_lossy("regexp-selector") {
         Some(x) => x,
@@ -102,45 +224,171 @@ fn query(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
     let raw = y.is_present("raw");
     let human = y.is_present("human");
 
-    let label_part = y.values_of_lossy("ofields");
+    let label_part = match y.values_of("ofields") {
+        Some(ofields) => Some(ofields.map(|o| o.parse::<ListChangesOption>()).collect::<Result<Vec<_>, _>>()?),
+        None => None,
+    };
+
+    // the time bounds are part of every query, so `--limit` and `--start` count only matching changes
+    if let Some(since) = y.value_of("since") {
+        let since = Query::after(&since.parse()?);
+        for query in &mut queries {
+            query.push(since.clone());
+        }
+    }
+    if let Some(until) = y.value_of("until") {
+        let until = Query::before(&until.parse()?);
+        for query in &mut queries {
+            query.push(until.clone());
+        }
+    }
+    let sort = y.value_of("sort");
+
+    if queries.len() > 1 {
+        if y.is_present("limit") || y.is_present("start") {
+            return Err(GGRError::General("--limit and --start are only supported for a single query".into()));
+        }
 
-    match changes.query_changes(userquery, label_part) {
-        Ok(cis) => {
-            let changeinfos = ChangeInfos::new(cis);
+        let results: Vec<Vec<entities::ChangeInfo>> = changes.query_changes_multi(queries, label_part)?
+            .into_iter()
+            .map(|cis| sort_changes(cis, sort))
+            .collect();
 
-            if raw {
-                println!("{}", changeinfos.raw());
-                return Ok(());
-            }
+        if raw || human {
+            let json = serde_json::Value::Array(results.into_iter().map(|cis| ChangeInfos::new(cis).to_json()).collect());
+            let out = if raw {
+                serde_json::to_string(&json)
+            } else {
+                serde_json::to_string_pretty(&json)
+            };
+            println!("{}", out.unwrap_or_else(|_| "problem with decoding".into()));
+            return Ok(());
+        }
 
-            if human {
-                println!("{}", changeinfos.human());
-                return Ok(());
-            }
+        for (name, cis) in names.iter().zip(results.into_iter()) {
+            println!("query '{}':", name);
+            print_changeinfos(&ChangeInfos::new(cis), &regsel, fieldslist, false, false);
+        }
+
+        return Ok(());
+    }
+
+    let mut changequery = changes.query_changes_iter(queries.pop(), label_part);
+    if let Some(limit) = y.value_of("limit") {
+        changequery = changequery.limit(limit.parse().map_err(|_| GGRError::General(format!("limit '{}' is not a number", limit)))?);
+    }
+    if let Some(start) = y.value_of("start") {
+        changequery = changequery.start(start.parse().map_err(|_| GGRError::General(format!("start '{}' is not a number", start)))?);
+    }
+
+    if raw || human || sort.is_some() {
+        let cis = sort_changes(changequery.collect::<GGRResult<Vec<entities::ChangeInfo>>>()?, sort);
+        print_changeinfos(&ChangeInfos::new(cis), &regsel, fieldslist, raw, human);
+        return Ok(());
+    }
+
+    // print every change as soon as its page is fetched, without collecting all pages first
+    let mut count = 0;
+    let mut hm: HashMap<String, usize> = HashMap::new();
+    for (i, ci) in changequery.enumerate() {
+        let json = serde_json::to_value(&ci?).expect("Problem with to_value");
+        if fieldslist {
+            count += 1;
+            count_fields(&json, &mut hm);
+        } else {
+            print!("{}", gron_reg(&json, &format!("[{}]", i), &regsel));
+        }
+    }
+    if fieldslist {
+        print_fieldslist(count, &hm);
+    }
+
+    Ok(())
+}
+
+/// sorts changes by one of their timestamps
+///
+/// Changes without the sort timestamp (e.g. not yet submitted changes) are sorted first.
+fn sort_changes(mut cis: Vec<entities::ChangeInfo>, sort: Option<&str>) -> Vec<entities::ChangeInfo> {
+    match sort {
+        Some("created") => cis.sort_by_key(|ci| ci.created),
+        Some("updated") => cis.sort_by_key(|ci| ci.updated),
+        Some("submitted") => cis.sort_by_key(|ci| ci.submitted),
+        _ => {},
+    }
+
+    cis
+}
+
+/// prints the result of one query in the selected output format
+fn print_changeinfos(changeinfos: &ChangeInfos, regsel: &[String], fieldslist: bool, raw: bool, human: bool) {
+    if raw {
+        println!("{}", changeinfos.raw());
+        return;
+    }
 
-            if fieldslist {
-                let (count, hm) = changeinfos.fieldslist();
-                let mut printout = String::new();
+    if human {
+        println!("{}", changeinfos.human());
+        return;
+    }
+
+    if fieldslist {
+        let (count, hm) = changeinfos.fieldslist();
+        print_fieldslist(count, &hm);
+    } else {
+        println!("{}", changeinfos.as_string_reg(regsel).trim());
+    }
+}
 
-                let mut vec_hm: Vec<(&String, &usize)> = hm.iter().collect();
-                vec_hm.sort();
+/// prints the count of changes and how often each field occurs
+fn print_fieldslist(count: usize, hm: &HashMap<String, usize>) {
+    let mut printout = String::new();
+
+    let mut vec_hm: Vec<(&String, &usize)> = hm.iter().collect();
+    vec_hm.sort();
+
+    for entry in vec_hm {
+        printout.push_str(&format!("{}({})", entry.0, entry.1));
+        printout.push(' ');
+    }
+    println!("{} -> {}", count, printout);
+}
 
-                for entry in vec_hm {
-                    printout.push_str(&format!("{}({})", entry.0, entry.1));
-                    printout.push(' ');
+/// counts the fields of one json object into `hm`
+fn count_fields(entry: &serde_json::Value, hm: &mut HashMap<String, usize>) {
+    if let serde_json::value::Value::Object(ref x) = *entry {
+        for key in x.keys() {
+            let counter = hm.entry(key.to_owned()).or_insert(0);
+            *counter += 1;
+        }
+    }
+}
+
+/// gron lines of `json` below `prefix` whose key matches one of the selectors
+fn gron_reg(json: &serde_json::Value, prefix: &str, selectors: &[String]) -> String {
+    let mut grondata: Vec<u8> = Vec::new();
+    let _ = json.to_gron(&mut grondata, prefix);
+    let mut out = String::from("");
+
+    for line in String::from_utf8(grondata).unwrap_or_default().lines() {
+        let mut keyval = line.splitn(2, '=');
+        let key = keyval.next().unwrap_or("").trim();
+        let val = keyval.next().unwrap_or("").trim();
+
+        // remove empty lines
+        if key.is_empty() { continue };
+        if val == "null" { continue };
+
+        for selector in selectors {
+            if let Ok(re) = regex::Regex::new(selector) {
+                if re.is_match(key) {
+                    out.push_str(&format!("{} {}\n", key, val));
                 }
-                println!("{} -> {}", count, printout);
-            } else {
-                println!("{}", changeinfos.as_string_reg(&regsel).trim());
             }
-        },
-        Err(x) => {
-            return Err(x);
         }
     }
 
-
-    Ok(())
+    out
 }
 
 #[derive(Default, Debug)]
@@ -189,30 +437,7 @@ impl ChangeInfos {
     }
 
     pub fn as_string_reg(&self, selectors: &[String]) -> String {
-        let json = self.to_json();
-        let mut grondata: Vec<u8> = Vec::new();
-        let _ = json.to_gron(&mut grondata, "");
-        let mut out = String::from("");
-
-        for line in String::from_utf8(grondata).unwrap_or_default().lines() {
-            let mut keyval = line.splitn(2, '=');
-            let key = keyval.next().unwrap_or("").trim();
-            let val = keyval.next().unwrap_or("").trim();
-
-            // remove empty lines
-            if key.is_empty() { continue };
-            if val == "null" { continue };
-
-            for selector in selectors {
-                if let Ok(re) = regex::Regex::new(selector) {
-                    if re.is_match(key) {
-                        out.push_str(&format!("{} {}\n", key, val));
-                    }
-                }
-            }
-        }
-
-        out
+        gron_reg(&self.to_json(), "", selectors)
     }
 
     /// prints all selectable fields os a search string
@@ -226,15 +451,7 @@ impl ChangeInfos {
             if let Some(array) = self.to_json().as_array() {
                 entries = array.len();
                 for entry in array {
-                    match *entry {
-                        serde_json::value::Value::Object(ref x) => {
-                            for key in x.keys() {
-                                let counter = out_hmap.entry(key.to_owned()).or_insert(0);
-                                *counter += 1;
-                            }
-                        }
-                        _ => continue,
-                    }
+                    count_fields(entry, &mut out_hmap);
                 }
             } else {
                 println!("no array");
@@ -259,12 +476,15 @@ impl ChangeInfos {
 fn fetch(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
     let changeid = y.value_of_lossy("changeid").expect("no changeid provided, see help");
 
-    let mut gerrit = Gerrit::new(config.get_base_url());
+    let credentials = config.credentials();
+    let gerrit = config.gerrit(credentials.clone())?;
     let mut changes = gerrit.changes();
 
-    match changes.get_change(&*changeid, Some(vec!("CURRENT_REVISION", "DOWNLOAD_COMMANDS", "CURRENT_COMMIT"))) {
+    match changes.get_change(&changeid.parse()?, Some(vec!(ListChangesOption::CURRENT_REVISION, ListChangesOption::DOWNLOAD_COMMANDS, ListChangesOption::CURRENT_COMMIT))) {
         Ok(change) => {
-            topic::fetch_changeinfos(&[change], true, &changeid, None)
+            let mut changeinfos = [change];
+            topic::complete_fetchinfos(&gerrit, &mut changeinfos);
+            topic::fetch_changeinfos(&changeinfos, true, &changeid, None, &*credentials, &config.proxy(), &config.tls())
         },
         Err(x) => {
             println!("Error on retrival of {}: {}", changeid, x);
@@ -272,3 +492,74 @@ fn fetch(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
         }
     }
 }
+
+/// add one inline comment, published or as draft
+fn comment(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
+    let changeid: ChangeId = y.value_of("changeid").expect("no changeid provided, see help").parse()?;
+    let file = y.value_of("file").expect("no file provided, see help");
+    let message = y.value_of("message").expect("no message provided, see help");
+    let revision: RevisionId = y.value_of("revision").unwrap_or("current").parse()?;
+
+    let line = match y.value_of("line") {
+        Some(line) => Some(line.parse::<u64>().map_err(|_| GGRError::General(format!("line '{}' is not a number", line)))?),
+        None => None,
+    };
+
+    let commentinput = entities::CommentInput {
+        path: Some(file.into()),
+        line: line,
+        message: Some(message.into()),
+        ..Default::default()
+    };
+
+    let gerrit = config.gerrit(config.credentials())?;
+    let changes = gerrit.changes();
+
+    if y.is_present("draft") {
+        let draft = changes.create_draft(&changeid, &revision, &commentinput)?;
+        println!("draft {} saved on {}:{}", draft.id, file, draft.line.unwrap_or(0));
+    } else {
+        let mut comments = HashMap::new();
+        comments.insert(file.to_string(), vec!(commentinput));
+
+        let review = entities::ReviewInput {
+            comments: Some(comments),
+            ..Default::default()
+        };
+
+        changes.set_review(&changeid, &revision, &review)?;
+        println!("comment published on {}:{}", file, line.unwrap_or(0));
+    }
+
+    Ok(())
+}
+
+/// list published inline comments of a change or drafts of one revision
+fn comments(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
+    let changeid: ChangeId = y.value_of("changeid").expect("no changeid provided, see help").parse()?;
+    let revision: RevisionId = y.value_of("revision").unwrap_or("current").parse()?;
+
+    let gerrit = config.gerrit(config.credentials())?;
+    let changes = gerrit.changes();
+
+    let comments = if y.is_present("drafts") {
+        changes.list_drafts(&changeid, &revision)?
+    } else {
+        changes.list_comments(&changeid)?
+    };
+
+    let mut files: Vec<&String> = comments.keys().collect();
+    files.sort();
+
+    for file in files {
+        println!("{}:", file);
+        for comment in &comments[file] {
+            let author = comment.author.as_ref()
+                .and_then(|a| a.name.clone())
+                .unwrap_or_else(|| "draft".into());
+            println!("  {:>5} {}: {}", comment.line.unwrap_or(0), author, comment.message.as_ref().map(|m| m.trim()).unwrap_or(""));
+        }
+    }
+
+    Ok(())
+}
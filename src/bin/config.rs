@@ -2,13 +2,20 @@
 //! configuration related
 
 use clap::{self, SubCommand, App};
+use libgerrit::call::{AuthMode, RetryPolicy};
+use libgerrit::cassette;
+use libgerrit::credentials::{self, BearerTokenProvider, CredentialProvider, GitCredentialProvider, UserPasswordProvider};
 use libgerrit::error::GGRError;
 use libgerrit::error::GGRResult;
+use libgerrit::gerrit::{Gerrit, GerritBuilder};
+use libgerrit::transport::{CurlTransport, ProxySettings, Timeouts, TlsSettings, Transport};
 use std::env;
 use std::fmt;
 use std::fs;
 use std::io;
 use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 use toml_config;
 
 pub fn menu<'a, 'b>() -> App<'a, 'b> {
@@ -48,14 +55,31 @@ fn list(_: &clap::ArgMatches) -> GGRResult<()> {
 pub struct Config {
     /// gerrit server endpoint (eg. https://geritserver.com:8080/gr)
     api: String,
-    /// username to login
-    ///
-    /// **deprecated** since 0.1.9
+    /// username to login, used with `password` before all other credential sources
     username: Option<String>,
-    /// password for login
-    ///
-    /// **deprecated** since 0.1.9
+    /// http password for login
     password: Option<String>,
+    /// bearer token, used before all other credential sources
+    token: Option<String>,
+    /// `true` for read only access without credentials, `false` to always authenticate. Without
+    /// a value ggr is anonymous if no credentials are found.
+    anonymous: Option<bool>,
+    /// number of retries of temporary failed requests, default 3
+    retries: Option<u32>,
+    /// seconds to connect to the server, default 10
+    connect_timeout: Option<u64>,
+    /// seconds for one request, default 60
+    timeout: Option<u64>,
+    /// file with the certificates of the certificate authorities, for a server with a private CA
+    ca_bundle: Option<String>,
+    /// client certificate, used with `client_key` (REST calls only, https git fetches are refused)
+    client_cert: Option<String>,
+    /// private key of `client_cert`
+    client_key: Option<String>,
+    /// url of a http proxy for REST calls and git fetches
+    proxy: Option<String>,
+    /// comma separated list of hosts which are reached without proxy
+    no_proxy: Option<String>,
     /// claims the repository as the topmost repository
     root: bool,
     /// do not execute anything if dryrun is true
@@ -68,6 +92,16 @@ impl Default for Config {
             api: "".to_owned(),
             username: None,
             password: None,
+            token: None,
+            anonymous: None,
+            retries: None,
+            connect_timeout: None,
+            timeout: None,
+            ca_bundle: None,
+            client_cert: None,
+            client_key: None,
+            proxy: None,
+            no_proxy: None,
             root: true,
             dryrun: false,
         }
@@ -79,7 +113,33 @@ impl fmt::Display for Config {
         try!(writeln!(f, "* url ......... : {api}",
                api = self.api.clone(),
         ));
-        try!(writeln!(f, "  user/pass ... : from .netrc file"));
+        let credentials = match (&self.token, &self.username, &self.password) {
+            (&Some(_), _, _) => "token from config file".to_owned(),
+            (_, &Some(ref username), &Some(_)) => format!("user '{}' from config file", username),
+            _ => format!("${}, ${}/${}, .netrc, git credential helper or .gitcookies file",
+                         credentials::ENV_TOKEN, credentials::ENV_USERNAME, credentials::ENV_HTTP_PASSWORD),
+        };
+        try!(writeln!(f, "  credentials . : {}", credentials));
+        try!(writeln!(f, "  anonymous ... : {}", match self.anonymous {
+            Some(true) => "yes",
+            Some(false) => "no",
+            None => "if no credentials are found",
+        }));
+        let retry = self.retry_policy();
+        let timeouts = self.timeouts();
+        try!(writeln!(f, "  retries ..... : {}", retry.retries));
+        try!(writeln!(f, "  timeouts .... : connect {:?}, request {:?}", timeouts.connect, timeouts.total));
+        let tls = self.tls();
+        if let Some(ref ca_bundle) = tls.ca_bundle {
+            try!(writeln!(f, "  ca bundle ... : {}", ca_bundle.display()));
+        }
+        if let Some(ref client_cert) = tls.client_cert {
+            try!(writeln!(f, "  client cert . : {}", client_cert.display()));
+        }
+        let proxy = self.proxy();
+        if let Some(ref url) = proxy.url {
+            try!(writeln!(f, "  proxy ....... : {} (not for: {})", url, proxy.no_proxy.join(", ")));
+        }
         write!(f, "  root ........ : {root}", root = self.root)
     }
 }
@@ -87,13 +147,7 @@ impl fmt::Display for Config {
 impl Config {
     /// Creates new Config from ConfigFile
     pub fn from_configfile(cf: ConfigFile) -> Config {
-        let config: Config = toml_config::ConfigFactory::load(cf.file.path().as_path());
-
-        if config.username.is_some() || config.password.is_some() {
-            info!("ignoring username and password from configfile using .netrc file now");
-        }
-
-        config
+        toml_config::ConfigFactory::load(cf.file.path().as_path())
     }
 
     /// Config is only functional if `api` is set.
@@ -105,6 +159,91 @@ impl Config {
         &self.api
     }
 
+    /// credentials of the config file followed by `credentials::default_provider`
+    ///
+    /// If nothing is stored git prompts for username and password, but only when a request needs
+    /// an authentication. A configured git credential helper stores them after a successful login.
+    pub fn credentials(&self) -> Arc<dyn CredentialProvider> {
+        let mut chain = credentials::ChainProvider::new()
+            .with_opt(self.token.clone().map(BearerTokenProvider::new));
+        if let (&Some(ref username), &Some(ref password)) = (&self.username, &self.password) {
+            chain = chain.with(UserPasswordProvider::new(username.clone(), password.clone()));
+        }
+
+        Arc::new(chain.with(credentials::default_provider())
+                      .with(GitCredentialProvider::new().interactive(true)))
+    }
+
+    /// retries of `.ggr.conf`, defaults of `RetryPolicy` otherwise
+    pub fn retry_policy(&self) -> RetryPolicy {
+        let mut retry = RetryPolicy::default();
+        if let Some(retries) = self.retries {
+            retry.retries = retries;
+        }
+        retry
+    }
+
+    /// timeouts of `.ggr.conf`, defaults of `Timeouts` otherwise. A value of `0` waits forever.
+    pub fn timeouts(&self) -> Timeouts {
+        let seconds = |value: Option<u64>, default: Option<Duration>| match value {
+            Some(0) => None,
+            Some(x) => Some(Duration::from_secs(x)),
+            None => default,
+        };
+
+        let default = Timeouts::default();
+        Timeouts {
+            connect: seconds(self.connect_timeout, default.connect),
+            total: seconds(self.timeout, default.total),
+        }
+    }
+
+    /// certificate authorities and client certificate of `.ggr.conf`
+    pub fn tls(&self) -> TlsSettings {
+        TlsSettings {
+            ca_bundle: self.ca_bundle.as_ref().map(|x| x.into()),
+            client_cert: self.client_cert.as_ref().map(|x| x.into()),
+            client_key: self.client_key.as_ref().map(|x| x.into()),
+        }
+    }
+
+    /// proxy of `.ggr.conf`
+    pub fn proxy(&self) -> ProxySettings {
+        ProxySettings {
+            url: self.proxy.clone(),
+            no_proxy: self.no_proxy.as_ref()
+                .map(|x| x.split(',').map(|host| host.trim().to_string()).filter(|host| !host.is_empty()).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// a `Gerrit` object for the configured server with `credentials`, the anonymous mode,
+    /// retries, timeouts, certificates and proxy. An invalid url returns an error.
+    ///
+    /// With `GGR_CASSETTE=record:<path>` all requests are recorded to the cassette file `path`,
+    /// with `GGR_CASSETTE=replay:<path>` they are answered from it without a server.
+    pub fn gerrit(&self, credentials: Arc<dyn CredentialProvider>) -> GGRResult<Gerrit> {
+        let mut transport: Arc<dyn Transport> = Arc::new(CurlTransport::with_credentials(credentials)
+            .user_agent(format!("gerrit-rust/{}", env!("CARGO_PKG_VERSION")))
+            .timeouts(self.timeouts())
+            .tls(self.tls())
+            .proxy(self.proxy()));
+        if let Ok(switch) = env::var(cassette::ENV_CASSETTE) {
+            let (mode, path) = cassette::parse_switch(&switch)?;
+            transport = cassette::wrap(mode, path, transport)?;
+        }
+
+        GerritBuilder::new(self.get_base_url())
+            .transport(transport)
+            .retry_policy(self.retry_policy())
+            .auth_mode(match self.anonymous {
+                Some(true) => AuthMode::Anonymous,
+                Some(false) => AuthMode::Authenticated,
+                None => AuthMode::Auto,
+            })
+            .build()
+    }
+
     pub fn is_root(&self) -> &bool {
         &self.root
     }
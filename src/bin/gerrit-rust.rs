@@ -14,7 +14,6 @@ extern crate rustc_serialize;
 extern crate serde_json;
 extern crate toml_config;
 extern crate url;
-extern crate netrc;
 
 pub mod changes;
 pub mod config;
@@ -84,6 +83,11 @@ fn main() {
     }
 
     config.set_dry_run(matches.is_present("dry-run"));
+    // before the first use of git2
+    if let Err(x) = config.tls().init_git2() {
+        println!("Error: {}", x);
+        exit(-1);
+    }
 
     let out = match matches.subcommand() {
         ("topic", Some(x)) => { topic::manage(x, &config) },
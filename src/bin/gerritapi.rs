@@ -1,9 +1,9 @@
 
 use clap::{self, SubCommand, App, Arg};
 use libgerrit::error::GGRResult;
-use libgerrit::gerrit::Gerrit;
 use config;
 use libgerrit::entities;
+use libgerrit::ids::{ChangeId, RevisionId};
 
 pub fn menu<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name("gerritapi")
@@ -49,6 +49,7 @@ pub fn menu<'a, 'b>() -> App<'a, 'b> {
                             .arg(Arg::with_name("changeid")
                                  .required(true)
                                  .takes_value(true)
+                                 .validator(|c| c.parse::<ChangeId>().map(|_| ()).map_err(|e| e.to_string()))
                                  .help("receive reviewer list from this {change-id}")
                                  .index(1)
                             )
@@ -58,6 +59,7 @@ pub fn menu<'a, 'b>() -> App<'a, 'b> {
                             .arg(Arg::with_name("changeid")
                                  .required(true)
                                  .takes_value(true)
+                                 .validator(|c| c.parse::<ChangeId>().map(|_| ()).map_err(|e| e.to_string()))
                                  .help("The change id which should abandoned")
                                  .index(1)
                             )
@@ -72,10 +74,258 @@ pub fn menu<'a, 'b>() -> App<'a, 'b> {
                                  .short("n")
                                  .help("Notification hint (only v2.13). defaullt is 'none'")
                                  .takes_value(true)
-                                 .possible_values(&["all", "none", "owner", "owner_reviewer"])
+                                 .possible_values(&["all", "none", "owner", "owner_reviewers", "owner_reviewer"])
                                  .default_value("none")
                             )
                 )
+                .subcommand(SubCommand::with_name("submitchange")
+                            .about("Submit a change")
+                            .arg(Arg::with_name("changeid")
+                                 .required(true)
+                                 .takes_value(true)
+                                 .validator(|c| c.parse::<ChangeId>().map(|_| ()).map_err(|e| e.to_string()))
+                                 .help("The change id which should submitted")
+                                 .index(1)
+                            )
+                            .arg(Arg::with_name("notify")
+                                 .long("notify")
+                                 .short("n")
+                                 .help("Notification hint (only v2.13)")
+                                 .takes_value(true)
+                                 .possible_values(&["all", "none", "owner", "owner_reviewers", "owner_reviewer"])
+                            )
+                )
+                .subcommand(SubCommand::with_name("rebasechange")
+                            .about("Rebase a change")
+                            .arg(Arg::with_name("changeid")
+                                 .required(true)
+                                 .takes_value(true)
+                                 .validator(|c| c.parse::<ChangeId>().map(|_| ()).map_err(|e| e.to_string()))
+                                 .help("The change id which should rebased")
+                                 .index(1)
+                            )
+                            .arg(Arg::with_name("base")
+                                 .long("base")
+                                 .short("b")
+                                 .help("new parent revision, ref or change number. Empty string rebases on target branch")
+                                 .takes_value(true)
+                            )
+                )
+                .subcommand(SubCommand::with_name("cherrypick")
+                            .about("Cherry-pick a revision to a other branch")
+                            .arg(Arg::with_name("changeid")
+                                 .required(true)
+                                 .takes_value(true)
+                                 .validator(|c| c.parse::<ChangeId>().map(|_| ()).map_err(|e| e.to_string()))
+                                 .help("The change id which should cherry-picked")
+                                 .index(1)
+                            )
+                            .arg(Arg::with_name("destination")
+                                 .required(true)
+                                 .takes_value(true)
+                                 .help("destination branch")
+                                 .index(2)
+                            )
+                            .arg(Arg::with_name("revision")
+                                 .long("revision")
+                                 .short("r")
+                                 .help("revision to cherry-pick, default is 'current'")
+                                 .takes_value(true)
+                                 .validator(|r| r.parse::<RevisionId>().map(|_| ()).map_err(|e| e.to_string()))
+                                 .default_value("current")
+                            )
+                            .arg(Arg::with_name("message")
+                                 .long("message")
+                                 .short("m")
+                                 .help("commit message of the cherry-picked change")
+                                 .takes_value(true)
+                            )
+                )
+                .subcommand(SubCommand::with_name("revertchange")
+                            .about("Revert a merged change")
+                            .arg(Arg::with_name("changeid")
+                                 .required(true)
+                                 .takes_value(true)
+                                 .validator(|c| c.parse::<ChangeId>().map(|_| ()).map_err(|e| e.to_string()))
+                                 .help("The change id which should reverted")
+                                 .index(1)
+                            )
+                            .arg(Arg::with_name("message")
+                                 .long("message")
+                                 .short("m")
+                                 .help("Revert message")
+                                 .takes_value(true)
+                            )
+                )
+                .subcommand(SubCommand::with_name("movechange")
+                            .about("Move a change to a other branch")
+                            .arg(Arg::with_name("changeid")
+                                 .required(true)
+                                 .takes_value(true)
+                                 .validator(|c| c.parse::<ChangeId>().map(|_| ()).map_err(|e| e.to_string()))
+                                 .help("The change id which should moved")
+                                 .index(1)
+                            )
+                            .arg(Arg::with_name("destination")
+                                 .required(true)
+                                 .takes_value(true)
+                                 .help("destination branch")
+                                 .index(2)
+                            )
+                            .arg(Arg::with_name("message")
+                                 .long("message")
+                                 .short("m")
+                                 .help("Move message")
+                                 .takes_value(true)
+                            )
+                )
+    )
+    .subcommand(SubCommand::with_name("accounts")
+                .about("Accounts endpoint")
+                .subcommand(SubCommand::with_name("self")
+                            .about("Account of the calling user (checks credentials)")
+                )
+                .subcommand(SubCommand::with_name("get")
+                            .about("Get a account")
+                            .arg(Arg::with_name("accountid")
+                                 .required(true)
+                                 .takes_value(true)
+                                 .help("numeric account id, username or email address")
+                                 .index(1)
+                            )
+                )
+                .subcommand(SubCommand::with_name("query")
+                            .about("query accounts")
+                            .arg(Arg::with_name("query")
+                                 .required(true)
+                                 .takes_value(true)
+                                 .help("Query string")
+                                 .index(1)
+                            )
+                            .arg(Arg::with_name("details")
+                                 .long("details")
+                                 .short("d")
+                                 .help("receive detailed account information")
+                            )
+                )
+    )
+    .subcommand(SubCommand::with_name("projects")
+                .about("Projects endpoint")
+                .subcommand(SubCommand::with_name("list")
+                            .about("List projects")
+                            .arg(Arg::with_name("prefix")
+                                 .long("prefix")
+                                 .short("p")
+                                 .takes_value(true)
+                                 .help("only projects starting with this prefix")
+                            )
+                )
+                .subcommand(SubCommand::with_name("branches")
+                            .about("List branches of a project")
+                            .arg(Arg::with_name("project")
+                                 .required(true)
+                                 .takes_value(true)
+                                 .help("The name of the project")
+                                 .index(1)
+                            )
+                )
+                .subcommand(SubCommand::with_name("createbranch")
+                            .about("Create a branch on a project")
+                            .arg(Arg::with_name("project")
+                                 .required(true)
+                                 .takes_value(true)
+                                 .help("The name of the project")
+                                 .index(1)
+                            )
+                            .arg(Arg::with_name("branch")
+                                 .required(true)
+                                 .takes_value(true)
+                                 .help("The name of the new branch")
+                                 .index(2)
+                            )
+                            .arg(Arg::with_name("revision")
+                                 .long("revision")
+                                 .short("r")
+                                 .takes_value(true)
+                                 .help("base revision of the new branch, default is HEAD")
+                            )
+                )
+                .subcommand(SubCommand::with_name("deletebranch")
+                            .about("Delete a branch of a project")
+                            .arg(Arg::with_name("project")
+                                 .required(true)
+                                 .takes_value(true)
+                                 .help("The name of the project")
+                                 .index(1)
+                            )
+                            .arg(Arg::with_name("branch")
+                                 .required(true)
+                                 .takes_value(true)
+                                 .help("The name of the branch")
+                                 .index(2)
+                            )
+                )
+                .subcommand(SubCommand::with_name("tags")
+                            .about("List tags of a project")
+                            .arg(Arg::with_name("project")
+                                 .required(true)
+                                 .takes_value(true)
+                                 .help("The name of the project")
+                                 .index(1)
+                            )
+                )
+    )
+    .subcommand(SubCommand::with_name("groups")
+                .about("Groups endpoint")
+                .subcommand(SubCommand::with_name("list")
+                            .about("List groups")
+                )
+                .subcommand(SubCommand::with_name("members")
+                            .about("List members of a group")
+                            .arg(Arg::with_name("group")
+                                 .required(true)
+                                 .takes_value(true)
+                                 .help("UUID, numeric id or name of the group")
+                                 .index(1)
+                            )
+                            .arg(Arg::with_name("recursive")
+                                 .long("recursive")
+                                 .short("R")
+                                 .help("include members of all included groups")
+                            )
+                )
+                .subcommand(SubCommand::with_name("addmember")
+                            .about("Add accounts to a group")
+                            .arg(Arg::with_name("group")
+                                 .required(true)
+                                 .takes_value(true)
+                                 .help("UUID, numeric id or name of the group")
+                                 .index(1)
+                            )
+                            .arg(Arg::with_name("accounts")
+                                 .required(true)
+                                 .takes_value(true)
+                                 .multiple(true)
+                                 .help("accounts to add")
+                                 .index(2)
+                            )
+                )
+                .subcommand(SubCommand::with_name("removemember")
+                            .about("Remove accounts from a group")
+                            .arg(Arg::with_name("group")
+                                 .required(true)
+                                 .takes_value(true)
+                                 .help("UUID, numeric id or name of the group")
+                                 .index(1)
+                            )
+                            .arg(Arg::with_name("accounts")
+                                 .required(true)
+                                 .takes_value(true)
+                                 .multiple(true)
+                                 .help("accounts to remove")
+                                 .index(2)
+                            )
+                )
     )
     .subcommand(SubCommand::with_name("config")
                 .about("Config endpoint")
@@ -83,12 +333,19 @@ pub fn menu<'a, 'b>() -> App<'a, 'b> {
                      .short("V")
                      .help("gerrit server version")
                 )
+                .arg(Arg::with_name("info")
+                     .short("I")
+                     .help("gerrit server information")
+                )
     )
 }
 
 pub fn manage(x: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
     match x.subcommand() {
         ("changes", Some(y)) => { changes(y, config) },
+        ("accounts", Some(y)) => { accounts(y, config) },
+        ("projects", Some(y)) => { projects(y, config) },
+        ("groups", Some(y)) => { groups(y, config) },
         ("config", Some(y)) => { configs(y, config) },
         _ => {
             println!("{}", x.usage());
@@ -98,7 +355,7 @@ pub fn manage(x: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
 }
 
 fn configs(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
-    let mut gerrit = Gerrit::new(config.get_base_url());
+    let gerrit = config.gerrit(config.credentials())?;
 
     if y.is_present("version") {
         match gerrit.config().get_version() {
@@ -107,11 +364,200 @@ fn configs(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
         }
     }
 
+    if y.is_present("info") {
+        match gerrit.config().get_server_info() {
+            Ok(info) => println!("* {:?}", info),
+            Err(x) => println!("Error: {:?}", x),
+        }
+    }
+
+    Ok(())
+}
+
+fn accounts(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
+    let gerrit = config.gerrit(config.credentials())?;
+
+    match y.subcommand() {
+        ("self", Some(_)) => {
+            match gerrit.accounts().get_self() {
+                Ok(ai) => println!("* {:?}", ai),
+                Err(x) => println!("Error: {:?}", x),
+            }
+        },
+
+        ("get", Some(opt)) => {
+            let accountid = opt.value_of("accountid").unwrap();
+
+            match gerrit.accounts().get_account(accountid) {
+                Ok(ai) => println!("* {:?}", ai),
+                Err(x) => println!("Error: {:?}", x),
+            }
+        },
+
+        ("query", Some(opt)) => {
+            let query = opt.value_of("query").unwrap();
+            let options = if opt.is_present("details") { Some(vec!("DETAILS")) } else { None };
+
+            match gerrit.accounts().query_accounts(query, options) {
+                Ok(ais) => {
+                    for ai in ais {
+                        println!("* {:?}", ai);
+                    }
+                },
+                Err(x) => println!("Error: {:?}", x),
+            }
+        },
+
+        e => {
+            println!("unknown subcommand {}", e.0);
+            println!("{}", y.usage());
+        }
+    }
+
+    Ok(())
+}
+
+fn projects(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
+    let gerrit = config.gerrit(config.credentials())?;
+
+    match y.subcommand() {
+        ("list", Some(opt)) => {
+            let prefix = opt.value_of("prefix");
+
+            match gerrit.projects().list_projects(prefix, true) {
+                Ok(pis) => {
+                    let mut names: Vec<&String> = pis.keys().collect();
+                    names.sort();
+                    for name in names {
+                        println!("* {} {}", name, pis[name].description.clone().unwrap_or_default());
+                    }
+                },
+                Err(x) => println!("Error: {:?}", x),
+            }
+        },
+
+        ("branches", Some(opt)) => {
+            let project = opt.value_of("project").unwrap();
+
+            match gerrit.projects().list_branches(project) {
+                Ok(bis) => {
+                    for bi in bis {
+                        println!("* {} {}", bi.revision, bi.reference);
+                    }
+                },
+                Err(x) => println!("Error: {:?}", x),
+            }
+        },
+
+        ("createbranch", Some(opt)) => {
+            let project = opt.value_of("project").unwrap();
+            let branch = opt.value_of("branch").unwrap();
+            let revision = opt.value_of("revision");
+
+            match gerrit.projects().create_branch(project, branch, revision) {
+                Ok(bi) => println!("* {:?}", bi),
+                Err(x) => println!("Error: {:?}", x),
+            }
+        },
+
+        ("deletebranch", Some(opt)) => {
+            let project = opt.value_of("project").unwrap();
+            let branch = opt.value_of("branch").unwrap();
+
+            match gerrit.projects().delete_branch(project, branch) {
+                Ok(_) => println!("* branch {} deleted", branch),
+                Err(x) => println!("Error: {:?}", x),
+            }
+        },
+
+        ("tags", Some(opt)) => {
+            let project = opt.value_of("project").unwrap();
+
+            match gerrit.projects().list_tags(project) {
+                Ok(tis) => {
+                    for ti in tis {
+                        println!("* {} {}", ti.revision, ti.reference);
+                    }
+                },
+                Err(x) => println!("Error: {:?}", x),
+            }
+        },
+
+        e => {
+            println!("unknown subcommand {}", e.0);
+            println!("{}", y.usage());
+        }
+    }
+
+    Ok(())
+}
+
+fn groups(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
+    let gerrit = config.gerrit(config.credentials())?;
+
+    match y.subcommand() {
+        ("list", Some(_)) => {
+            match gerrit.groups().list_groups() {
+                Ok(gis) => {
+                    let mut names: Vec<&String> = gis.keys().collect();
+                    names.sort();
+                    for name in names {
+                        println!("* {} ({})", name, gis[name].id);
+                    }
+                },
+                Err(x) => println!("Error: {:?}", x),
+            }
+        },
+
+        ("members", Some(opt)) => {
+            let group = opt.value_of("group").unwrap();
+            let recursive = opt.is_present("recursive");
+
+            match gerrit.groups().list_members(group, recursive) {
+                Ok(ais) => {
+                    for ai in ais {
+                        println!("* {:?}", ai);
+                    }
+                },
+                Err(x) => println!("Error: {:?}", x),
+            }
+        },
+
+        ("addmember", Some(opt)) => {
+            let group = opt.value_of("group").unwrap();
+            let accounts: Vec<&str> = opt.values_of("accounts").unwrap().collect();
+
+            match gerrit.groups().add_members(group, &accounts) {
+                Ok(ais) => {
+                    for ai in ais {
+                        println!("* added {:?}", ai);
+                    }
+                },
+                Err(x) => println!("Error: {:?}", x),
+            }
+        },
+
+        ("removemember", Some(opt)) => {
+            let group = opt.value_of("group").unwrap();
+            let accounts: Vec<&str> = opt.values_of("accounts").unwrap().collect();
+
+            match gerrit.groups().remove_members(group, &accounts) {
+                Ok(_) => println!("* removed {:?}", accounts),
+                Err(x) => println!("Error: {:?}", x),
+            }
+        },
+
+        e => {
+            println!("unknown subcommand {}", e.0);
+            println!("{}", y.usage());
+        }
+    }
+
     Ok(())
 }
 
 fn changes(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
-    let mut gerrit = Gerrit::new(config.get_base_url());
+    let gerrit = config.gerrit(config.credentials())?;
 
     match y.subcommand() {
         ("create", Some(opt)) => {
@@ -157,9 +603,9 @@ fn changes(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
         },
 
         ("listreviewers", Some(opt)) => {
-            let changeid = opt.value_of("changeid").unwrap();
+            let changeid: ChangeId = opt.value_of("changeid").unwrap().parse()?;
 
-            match gerrit.changes().get_reviewers(changeid) {
+            match gerrit.changes().get_reviewers(&changeid) {
                 Ok(reviewers) => {
                     for reviewer in reviewers {
                         println!("* {:?}", reviewer);
@@ -172,11 +618,11 @@ fn changes(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
         },
 
         ("abandonchange", Some(opt)) => {
-            let changeid = opt.value_of("changeid").unwrap();
+            let changeid: ChangeId = opt.value_of("changeid").unwrap().parse()?;
             let message = opt.value_of("message");
             let notify = opt.value_of("notify");
 
-            match gerrit.changes().abandon_change(changeid, message, notify) {
+            match gerrit.changes().abandon_change(&changeid, message, notify) {
                 Ok(ci) => {
                     println!("* {:?}", ci);
                 },
@@ -184,6 +630,59 @@ fn changes(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
             };
         },
 
+        ("submitchange", Some(opt)) => {
+            let changeid: ChangeId = opt.value_of("changeid").unwrap().parse()?;
+            let notify = opt.value_of("notify");
+
+            match gerrit.changes().submit_change(&changeid, None, notify) {
+                Ok(ci) => println!("* {:?}", ci),
+                Err(x) => println!("Error: {:?}", x),
+            };
+        },
+
+        ("rebasechange", Some(opt)) => {
+            let changeid: ChangeId = opt.value_of("changeid").unwrap().parse()?;
+            let base = opt.value_of("base");
+
+            match gerrit.changes().rebase_change(&changeid, base) {
+                Ok(ci) => println!("* {:?}", ci),
+                Err(x) => println!("Error: {:?}", x),
+            };
+        },
+
+        ("cherrypick", Some(opt)) => {
+            let changeid: ChangeId = opt.value_of("changeid").unwrap().parse()?;
+            let destination = opt.value_of("destination").unwrap();
+            let revision: RevisionId = opt.value_of("revision").unwrap().parse()?;
+            let message = opt.value_of("message");
+
+            match gerrit.changes().cherrypick_revision(&changeid, &revision, destination, message) {
+                Ok(ci) => println!("* {:?}", ci),
+                Err(x) => println!("Error: {:?}", x),
+            };
+        },
+
+        ("revertchange", Some(opt)) => {
+            let changeid: ChangeId = opt.value_of("changeid").unwrap().parse()?;
+            let message = opt.value_of("message");
+
+            match gerrit.changes().revert_change(&changeid, message) {
+                Ok(ci) => println!("* {:?}", ci),
+                Err(x) => println!("Error: {:?}", x),
+            };
+        },
+
+        ("movechange", Some(opt)) => {
+            let changeid: ChangeId = opt.value_of("changeid").unwrap().parse()?;
+            let destination = opt.value_of("destination").unwrap();
+            let message = opt.value_of("message");
+
+            match gerrit.changes().move_change(&changeid, destination, message) {
+                Ok(ci) => println!("* {:?}", ci),
+                Err(x) => println!("Error: {:?}", x),
+            };
+        },
+
         e => {
             println!("unknown subcommand {}", e.0);
             println!("{}", y.usage());
@@ -2,23 +2,20 @@
 use clap::{self, SubCommand, App, Arg};
 use git2;
 use std::collections::HashMap;
-use std::env;
-use std::fs::File;
-use std::io::BufReader;
 use std::io::{self, Write};
-use std::iter;
 use std::ops::Deref;
 use std::path::Path;
-use std::path::PathBuf;
 use std::process::Command;
 use std::thread;
+use libgerrit::credentials::{self, CredentialProvider};
 use libgerrit::error::GGRError;
 use libgerrit::error::GGRResult;
 use libgerrit::error::GerritError;
 use libgerrit::gerrit::Gerrit;
-use libgerrit::entities;
-use netrc;
-use url;
+use libgerrit::entities::{self, ListChangesOption};
+use libgerrit::ids::{ChangeId, RevisionId};
+use libgerrit::query::Query;
+use libgerrit::transport::{ProxySettings, TlsSettings};
 use config;
 
 pub fn menu<'a, 'b>() -> App<'a, 'b> {
@@ -139,6 +136,29 @@ pub fn menu<'a, 'b>() -> App<'a, 'b> {
                      .takes_value(true)
                 )
     )
+    .subcommand(SubCommand::with_name("submit")
+                .about("Submit a topic")
+                .arg(Arg::with_name("topicname")
+                     .help("topic name to submit")
+                     .required(true)
+                     .takes_value(true)
+                     .index(1)
+                )
+                .arg(Arg::with_name("notify")
+                     .help("Notification hint (only v2.13)")
+                     .long("notify")
+                     .short("n")
+                     .takes_value(true)
+                     .possible_values(&["all", "none", "owner", "owner_reviewers", "owner_reviewer"])
+                )
+                .arg(Arg::with_name("force")
+                     .help("submit changes where the server does not report submittable or mergeable")
+                     .long("force")
+                )
+                .after_help("* all changes of the topic needs to be submittable and mergeable, otherwise nothing is submitted\n\
+                             * changes with unknown submittable or mergeable state are only submitted with --force\n\
+                             * changes are submitted parent before child over all repositories")
+    )
     .subcommand(SubCommand::with_name("verify")
                 .about("verify topic")
                 .arg(Arg::with_name("topicname")
@@ -186,6 +206,7 @@ pub fn manage(x: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
         ("abandon", Some(y)) => { abandon(y, config) },
         ("restore", Some(y)) => { restore(y, config) },
         ("verify", Some(y)) => { verify(y, config) },
+        ("submit", Some(y)) => { submit(y, config) },
         _ => {
             println!("{}", x.usage());
             Ok(())
@@ -269,8 +290,9 @@ fn fetch(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
         let _ = history(y, config);
     }
 
-    let mut gerrit = Gerrit::new(config.get_base_url());
-    fetch_topic(&mut gerrit, topicname, local_branch_name, force, tracking_branch_name, closed)
+    let credentials = config.credentials();
+    let gerrit = config.gerrit(credentials.clone())?;
+    fetch_topic(&gerrit, topicname, local_branch_name, force, tracking_branch_name, closed, &*credentials, &config.proxy(), &config.tls())
 }
 
 /// fetch history of a topic
@@ -280,17 +302,21 @@ fn history(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
     }
 
     let topicname = y.value_of("topicname").expect("no or bad topicname").to_owned();
-    let mut gerrit = Gerrit::new(config.get_base_url());
+    let credentials = config.credentials();
+    let proxy = config.proxy();
+    let tls = config.tls();
+    let gerrit = config.gerrit(credentials.clone())?;
 
     let mut changes = gerrit.changes();
-    let query_part = vec!(format!("topic:{}", topicname));
+    let query_part = vec!(Query::topic(&topicname));
 
-    let changeinfos = changes.query_changes(Some(query_part), Some(vec!("ALL_REVISIONS".into(), "ALL_COMMITS".into())))?;
+    let mut changeinfos = changes.query_changes(Some(query_part), Some(vec!(ListChangesOption::ALL_REVISIONS, ListChangesOption::ALL_COMMITS)))?;
 
     if changeinfos.is_empty() {
         println!("topic '{}' not found", topicname);
         return Ok(());
     }
+    complete_fetchinfos(&gerrit, &mut changeinfos);
 
     for ci in changeinfos {
         println!("* working on {} {:20} ({:?})", ci.change_id, ci.subject, ci.status);
@@ -308,6 +334,9 @@ fn history(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
             let topicname = topicname.clone();
             let cistatus = ci.status.clone();
             let dryrun = *config.dry_run();
+            let credentials = credentials.clone();
+            let proxy = proxy.clone();
+            let tls = tls.clone();
 
             children.push(thread::spawn(move || {
                 let is_abandoned = cistatus == entities::ChangeInfoChangeStatus::ABANDONED;
@@ -318,7 +347,7 @@ fn history(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
 
                 for (fetchtype, fetchinfo) in &revisioninfo.fetch {
                     if fetchtype.starts_with("http") {
-                        match do_fetch_from_repo(fetchinfo, &topicname, TagOrBranch::Tag, None, false, dryrun) {
+                        match do_fetch_from_repo(fetchinfo, &topicname, TagOrBranch::Tag, None, false, dryrun, &*credentials, &proxy, &tls) {
                             Err(x) => {
                                 outstr.push_str(&format!("FAILED: {}", x));
                             },
@@ -346,7 +375,7 @@ enum TagOrBranch {
 
 
 /// fetch via fetchinfo entity a tag or branch, and for branches it can set tracking information.
-fn do_fetch_from_repo(fetchinfo: &entities::FetchInfo, topic: &str, tag_or_branch: TagOrBranch, tracking_branch_name: Option<&str>, force: bool, dryrun: bool) -> GGRResult<String>
+fn do_fetch_from_repo(fetchinfo: &entities::FetchInfo, topic: &str, tag_or_branch: TagOrBranch, tracking_branch_name: Option<&str>, force: bool, dryrun: bool, credentials: &dyn CredentialProvider, proxy: &ProxySettings, tls: &TlsSettings) -> GGRResult<String>
 {
     debug!("history fetch {:?}", fetchinfo);
 
@@ -369,48 +398,24 @@ fn do_fetch_from_repo(fetchinfo: &entities::FetchInfo, topic: &str, tag_or_branc
         },
     };
 
+    tls.check_git2_fetch(&fetchinfo.url)?;
+
     /* we have found the rpeository. we can now fetch and tag the revision. */
+    let git2_credentials = credentials::Git2Credentials::new(credentials);
     let mut cb = git2::RemoteCallbacks::new();
     cb.credentials(|url, username, allowed| {
         debug!("credential callback: {} / {:?} / {:?}", url, username, allowed);
-
-        let homefolder = env::home_dir().ok_or(git2::Error::from_str("set HOME environment variable for searching of netrc"))?;
-        let mut netrcfile = PathBuf::new();
-        netrcfile.push(homefolder);
-        netrcfile.push(".netrc");
-
-        if !netrcfile.exists() {
-            return Err(git2::Error::from_str(&format!("cannot find .netrc file at {:?}", netrcfile.as_path())));
-        }
-
-        debug!("found .netrc file");
-
-        let f = File::open(netrcfile.as_path())
-            .map_err(|x| { git2::Error::from_str(&format!("file: {}", x)) } )?;
-        let reader = BufReader::new(f);
-
-        let netrc = netrc::Netrc::parse(reader)
-            .map_err(|x| { git2::Error::from_str(&format!("{:?}", x)) } )?;
-
-        let repourl = url::Url::parse(url)
-            .map_err(|x| { git2::Error::from_str(&format!("{}", x)) } )?;
-        for (_, &(ref machinehost, ref machine)) in netrc.hosts.iter().enumerate() {
-            debug!("check machinehost: {}", machinehost);
-            if repourl.host_str() == Some(machinehost) {
-                let password = machine.password.as_ref().ok_or(git2::Error::from_str(&format!("no password for machine {} in netrc", machinehost)))?;
-                let passwordplace = iter::repeat("*").take(password.len()).collect::<String>();
-                debug!("use credentials for login: '{}', with password (hidden): '{}'", &machine.login, passwordplace);
-                return git2::Cred::userpass_plaintext(&machine.login, password);
-            }
-        }
-
-        Err(git2::Error::from_str(&format!("no correct netrc entry for repository {} found.", url)))
+        git2_credentials.callback(url, username)
     });
 
+    let mut remote = repo.find_remote("origin")?;
+    let proxyoptions = proxy.git2_proxy_options(remote.url().unwrap_or(""));
+
     let mut fetchoptions = git2::FetchOptions::new();
     fetchoptions.prune(git2::FetchPrune::Off)
         .update_fetchhead(false)
         .download_tags(git2::AutotagOption::None)
+        .proxy_options(proxyoptions)
         .remote_callbacks(cb);
 
     // TODO: check tag exists
@@ -418,8 +423,9 @@ fn do_fetch_from_repo(fetchinfo: &entities::FetchInfo, topic: &str, tag_or_branc
     let workdir = repo.workdir().ok_or(format!("no workdir for '{}' found", repo.path().to_string_lossy()))?
         .file_name().unwrap();
     if !dryrun {
-        match repo.find_remote("origin")?.fetch(&[&refspecs], Some(&mut fetchoptions), Some("")) {
+        match remote.fetch(&[&refspecs], Some(&mut fetchoptions), Some("")) {
             Ok(_) => {
+                git2_credentials.approve();
                 if tag_or_branch == TagOrBranch::Branch {
                     if let Ok(mut branch) = repo.find_branch(&name, git2::BranchType::Local) {
                         let _ = branch.set_upstream(tracking_branch_name);
@@ -519,129 +525,103 @@ fn reviewer(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
     let topicname = y.value_of("topicname").expect("you need a topicname");
     let verbose = y.is_present("verbose");
 
-    let mut gerrit = Gerrit::new(config.get_base_url());
-    if let Ok(cis) = gerrit.changes().query_changes(Some(vec!(&format!("topic:{}", topicname)[..])), None) {
-
-        let mut children = Vec::new();
+    let gerrit = config.gerrit(config.credentials())?;
+    if let Ok(cis) = gerrit.changes().query_changes(Some(vec!(Query::topic(topicname))), None) {
+        // one thread per change, all share the session of `gerrit`
+        let gerrit = &gerrit;
 
         // manipulate reviewer for topic
         if let Some(ref reviewerlist) = y.values_of_lossy("reviewers") {
-            for ci in cis {
-                let reviewerlist = reviewerlist.clone();
-                let mut gerrit = gerrit.clone();
-                children.push(thread::spawn(move || {
-                    for reviewer in reviewerlist {
-                        let remove = reviewer.starts_with('~');
-
-                        if remove {
-                            let reviewer = &reviewer[1..];
-                            if let Err(res) = gerrit.changes().delete_reviewer(&ci.change_id, reviewer) {
-                                /*
-                                 * delete_changes returnes a empty body and a status code. A empty body
-                                 * cannot deserialized its break with a error message
-                                 * "JsonError(ErrorImpl { code: EofWhileParsingValue, line: 1, column: 0 })"
-                                 *
-                                 * Now we destructure the objects and check for status code 204 (no
-                                 * content) and overwrite this to be okay.
-                                 */
-                                match res {
-                                    GGRError::GerritApiError(ref x) => {
-                                        match *x {
-                                            GerritError::GerritApi(ref status, ref text) => {
-                                                if *status >= 400 {
-                                                    println!("{}, ({}: {})", reviewer, status, text);
-                                                } else {
-                                                    println!("* {:5.5} [{:20.20}] reviewer '{}' removed", ci.change_id, ci.subject, reviewer);
-                                                }
-                                            },
-                                            ref err => {
-                                                println!("Other error: {:?}", err);
-                                            },
-                                        }
+            thread::scope(|scope| {
+                for ci in &cis {
+                    scope.spawn(move || {
+                        for reviewer in reviewerlist {
+                            let remove = reviewer.starts_with('~');
+
+                            if remove {
+                                let reviewer = &reviewer[1..];
+                                match gerrit.changes().delete_reviewer(&ChangeId::from(ci), reviewer) {
+                                    Ok(()) => {
+                                        println!("* {:5.5} [{:20.20}] reviewer '{}' removed", ci.change_id, ci.subject, reviewer);
                                     },
-                                    x => { println!("Other error: {:?}", x);}
-                                };
-
+                                    Err(GGRError::GerritApiError(GerritError::NotFound(_))) => {
+                                        println!("* {:5.5} [{:20.20}] reviewer '{}' not found", ci.change_id, ci.subject, reviewer);
+                                    },
+                                    Err(x) => {
+                                        println!("{}, ({})", reviewer, x);
+                                    },
+                                }
                             } else {
-                                println!("* {:5.5} [{:20.20}] reviewer '{}' removed", ci.change_id, ci.subject, reviewer);
-                            };
-                        } else {
-                            match gerrit.changes().add_reviewer(&ci.change_id, &reviewer) {
-                                Ok(addreviewerresult) => {
-
-                                    match addreviewerresult.reviewers {
-                                        Some(reviewerret) => {
-                                            for r in reviewerret {
-                                                println!("* {:5.5} [{:20.20}] reviewer {}, {}, {}: added",
+                                match gerrit.changes().add_reviewer(&ChangeId::from(ci), &reviewer) {
+                                    Ok(addreviewerresult) => {
+
+                                        match addreviewerresult.reviewers {
+                                            Some(reviewerret) => {
+                                                for r in reviewerret {
+                                                    println!("* {:5.5} [{:20.20}] reviewer {}, {}, {}: added",
+                                                             ci.change_id,
+                                                             ci.subject,
+                                                             r.name.unwrap_or_else(|| "unkown name".into()),
+                                                             r.email.unwrap_or_else(|| "unkown mail".into()),
+                                                             r._account_id.unwrap_or(99999999));
+                                                }
+                                            },
+                                            None => {
+                                                println!("* {:5.5} [{:20.20}] reviewer '{}' not added: {}",
                                                          ci.change_id,
                                                          ci.subject,
-                                                         r.name.unwrap_or_else(|| "unkown name".into()),
-                                                         r.email.unwrap_or_else(|| "unkown mail".into()),
-                                                         r._account_id.unwrap_or(99999999));
-                                            }
-                                        },
-                                        None => {
-                                            println!("* {:5.5} [{:20.20}] reviewer '{}' not added: {}",
-                                                     ci.change_id,
-                                                     ci.subject,
-                                                     reviewer,
-                                                     addreviewerresult.error.unwrap_or_else(|| "No error message from gerrit server provided".into()));
-                                        },
-                                    };
-                                },
-                                Err(e) => {
-                                    println!("Problem to add '{}' as reviewer: {}", reviewer, e);
-                                },
+                                                         reviewer,
+                                                         addreviewerresult.error.unwrap_or_else(|| "No error message from gerrit server provided".into()));
+                                            },
+                                        };
+                                    },
+                                    Err(e) => {
+                                        println!("Problem to add '{}' as reviewer: {}", reviewer, e);
+                                    },
+                                }
                             }
                         }
-                    }
-                }));
-            }
-
-            for child in children {
-                let _ = child.join();
-            }
+                    });
+                }
+            });
 
             return Ok(());
         }
 
         // only list reviewers
         debug!("threads: {}", cis.len());
-        for ci in cis {
-            let mut gerrit = gerrit.clone();
-            children.push(thread::spawn(move || {
-                let mut out = format!("reviewer for '{}':\n", ci.subject);
-                if let Ok(reviewers) = gerrit.changes().get_reviewers(&ci.id) {
-                    let mut reviewer_list = Vec::new();
-                    for reviewer in reviewers {
-                        let (name, email, approval) = (
-                            reviewer.name.unwrap_or_else(|| "unknown".into()),
-                            reviewer.email.unwrap_or_else(|| "unknown".into()),
-                            reviewer.approvals
-                        );
-                        reviewer_list.push(name.clone());
-
-                        if verbose {
-                            for (approvei_label, approve_value) in &approval {
-                                out.push_str(&format!("  * {:20.20} {:20.20} {:>5.5}\n", email, approvei_label.trim(), approve_value.trim()));
+        thread::scope(|scope| {
+            for ci in &cis {
+                scope.spawn(move || {
+                    let mut out = format!("reviewer for '{}':\n", ci.subject);
+                    if let Ok(reviewers) = gerrit.changes().get_reviewers(&ChangeId::from(ci)) {
+                        let mut reviewer_list = Vec::new();
+                        for reviewer in reviewers {
+                            let (name, email, approval) = (
+                                reviewer.name.unwrap_or_else(|| "unknown".into()),
+                                reviewer.email.unwrap_or_else(|| "unknown".into()),
+                                reviewer.approvals
+                            );
+                            reviewer_list.push(name.clone());
+
+                            if verbose {
+                                for (approvei_label, approve_value) in &approval {
+                                    out.push_str(&format!("  * {:20.20} {:20.20} {:>5.5}\n", email, approvei_label.trim(), approve_value.trim()));
+                                }
                             }
                         }
-                    }
-                    if ! verbose {
-                        out.push_str("  ".into());
-                        for reviewer in reviewer_list {
-                            out.push_str(&format!("{}, ", reviewer));
+                        if ! verbose {
+                            out.push_str("  ".into());
+                            for reviewer in reviewer_list {
+                                out.push_str(&format!("{}, ", reviewer));
+                            }
+                            out.push_str("\n".into());
                         }
-                        out.push_str("\n".into());
+                        println!("{}", out);
                     }
-                    println!("{}", out);
-                }
-            }));
-        }
-
-        for child in children {
-            let _ = child.join();
-        }
+                });
+            }
+        });
     } else {
         println!("no changes for '{}' found", topicname);
     }
@@ -656,12 +636,12 @@ fn abandon(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
 
     debug!("abandon topic:{}, message:{}", topicname, message.unwrap_or(""));
 
-    let mut gerrit = Gerrit::new(config.get_base_url());
+    let gerrit = config.gerrit(config.credentials())?;
 
-    if let Ok(cis) = gerrit.changes().query_changes(Some(vec!(&format!("topic:{}", topicname)[..])), None) {
+    if let Ok(cis) = gerrit.changes().query_changes(Some(vec!(Query::topic(topicname))), None) {
         for ci in cis {
 
-            let (abid, absubject, abcause) = match gerrit.changes().abandon_change(&ci.change_id, message, None) {
+            let (abid, absubject, abcause) = match gerrit.changes().abandon_change(&ChangeId::from(&ci), message, None) {
                 Ok(ciret) => (ciret.change_id, ciret.subject, None),
                 Err(x) => (ci.change_id, ci.subject, Some(x)),
             };
@@ -687,12 +667,12 @@ fn restore(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
 
     debug!("restore topic:{}, message:{}", topicname, message.unwrap_or(""));
 
-    let mut gerrit = Gerrit::new(config.get_base_url());
+    let gerrit = config.gerrit(config.credentials())?;
 
-    if let Ok(cis) = gerrit.changes().query_changes(Some(vec!(&format!("topic:{}", topicname)[..])), None) {
+    if let Ok(cis) = gerrit.changes().query_changes(Some(vec!(Query::topic(topicname))), None) {
         for ci in cis {
 
-            let (abid, absubject, abcause) = match gerrit.changes().restore_change(&ci.change_id, message) {
+            let (abid, absubject, abcause) = match gerrit.changes().restore_change(&ChangeId::from(&ci), message) {
                 Ok(ciret) => (ciret.change_id, ciret.subject, None),
                 Err(x) => (ci.change_id, ci.subject, Some(x)),
             };
@@ -711,15 +691,165 @@ fn restore(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
     Ok(())
 }
 
+/// submit a topic
+///
+/// All changes are checked for there submittable and mergeable state before the first change is
+/// submitted. The changes are submitted parent before child. On the first failure no other change
+/// is submitted and a report shows which changes (and projects) are landed.
+fn submit(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
+    let topicname = y.value_of("topicname").expect("problem with topic name");
+    let notify = y.value_of("notify");
+    let force = y.is_present("force");
+
+    let gerrit = config.gerrit(config.credentials())?;
+
+    let query_part = vec!(Query::topic(topicname), Query::status("open"));
+    let changeinfos = gerrit.changes().query_changes(Some(query_part), Some(vec!(ListChangesOption::CURRENT_REVISION, ListChangesOption::CURRENT_COMMIT, ListChangesOption::SUBMITTABLE)))?;
+
+    if changeinfos.is_empty() {
+        println!("no open changes for topic '{}' found", topicname);
+        return Ok(());
+    }
+
+    // check all changes before we submit anything
+    let mut blocked = false;
+    for ci in &changeinfos {
+        let problems = submit_problems(ci, force);
+        if problems.is_empty() {
+            println!("* {:5.5} [{:20.20}] {:20.20} ready", ci.change_id, ci.subject, ci.project);
+        } else {
+            blocked = true;
+            println!("* {:5.5} [{:20.20}] {:20.20} blocked: {}", ci.change_id, ci.subject, ci.project, problems.join(", "));
+        }
+    }
+
+    if blocked {
+        return Err(GGRError::General(format!("topic '{}' is not submittable, nothing submitted", topicname)));
+    }
+
+    let ordered = submit_order(&changeinfos);
+
+    if *config.dry_run() {
+        println!("\nsubmit order (dry-run):");
+        for ci in ordered {
+            println!("* {:5.5} [{:20.20}] {}", ci.change_id, ci.subject, ci.project);
+        }
+        return Ok(());
+    }
+
+    println!("\nsubmit:");
+    let mut landed: Vec<&entities::ChangeInfo> = Vec::new();
+    let mut failed: Option<(&entities::ChangeInfo, GGRError)> = None;
+
+    for ci in &ordered {
+        match gerrit.changes().submit_change(&ChangeId::from(*ci), None, notify) {
+            Ok(_) => {
+                println!("* {:5.5} [{:20.20}] {:20.20} submitted", ci.change_id, ci.subject, ci.project);
+                landed.push(ci);
+            },
+            Err(x) => {
+                // with 'change.submitWholeTopic' the change is already merged through a other
+                // change of this topic
+                let merged = match gerrit.changes().get_change(&ChangeId::from(*ci), None) {
+                    Ok(ref current) => current.status == entities::ChangeInfoChangeStatus::MERGED,
+                    Err(_) => false,
+                };
+
+                if merged {
+                    println!("* {:5.5} [{:20.20}] {:20.20} submitted (together with topic)", ci.change_id, ci.subject, ci.project);
+                    landed.push(ci);
+                } else {
+                    println!("* {:5.5} [{:20.20}] {:20.20} FAILED: {}", ci.change_id, ci.subject, ci.project, x);
+                    failed = Some((ci, x));
+                    break;
+                }
+            },
+        }
+    }
+
+    let (failed_ci, failed_err) = match failed {
+        None => {
+            println!("\ntopic '{}' submitted", topicname);
+            return Ok(());
+        },
+        Some(x) => x,
+    };
+
+    println!("\nPartial submit of topic '{}':", topicname);
+    println!("landed:");
+    for ci in &landed {
+        println!("  * {:20.20} {:5.5} [{:20.20}]", ci.project, ci.change_id, ci.subject);
+    }
+    println!("failed:");
+    println!("  * {:20.20} {:5.5} [{:20.20}]", failed_ci.project, failed_ci.change_id, failed_ci.subject);
+    println!("not submitted:");
+    for ci in ordered.iter().filter(|ci| ci.id != failed_ci.id && !landed.iter().any(|l| l.id == ci.id)) {
+        println!("  * {:20.20} {:5.5} [{:20.20}]", ci.project, ci.change_id, ci.subject);
+    }
+
+    Err(failed_err)
+}
+
+/// returns a list of problems which prevent a submit of this change
+///
+/// A submittable or mergeable state the server did not report is a problem too, unless `force`
+/// is set.
+fn submit_problems(ci: &entities::ChangeInfo, force: bool) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if ci.status != entities::ChangeInfoChangeStatus::NEW {
+        problems.push(format!("status is {:?}", ci.status));
+    }
+    match ci.submittable {
+        Some(false) => problems.push("not submittable (missing approvals or submit rules not satisfied)".into()),
+        None if !force => problems.push("submittable is unknown (use --force to submit anyway)".into()),
+        _ => {},
+    }
+    match ci.mergeable {
+        Some(false) => problems.push("not mergeable (merge conflict)".into()),
+        None if !force => problems.push("mergeable is unknown (use --force to submit anyway)".into()),
+        _ => {},
+    }
+
+    problems
+}
+
+/// returns the changes in submit order, parents before their children
+///
+/// Changes without dependencies within `changes` are ordered by project and change number.
+fn submit_order(changes: &[entities::ChangeInfo]) -> Vec<&entities::ChangeInfo> {
+    let mut pending: Vec<&entities::ChangeInfo> = changes.iter().collect();
+    pending.sort_by(|a, b| (&a.project, a._number).cmp(&(&b.project, b._number)));
+
+    let mut ordered: Vec<&entities::ChangeInfo> = Vec::new();
+
+    while !pending.is_empty() {
+        // a change is ready if none of its parents is a pending change
+        let ready = pending.iter().position(|ci| {
+            let parents = current_parents(ci);
+            !pending.iter().any(|p| {
+                match p.current_revision {
+                    Some(ref rev) => parents.contains(&rev),
+                    None => false,
+                }
+            })
+        });
+
+        // a cycle isn't possible with git commits, but never loop forever
+        let index = ready.unwrap_or(0);
+        ordered.push(pending.remove(index));
+    }
+
+    ordered
+}
+
 /// verify a topic
 fn verify(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
     let topicname = y.value_of("topicname").expect("problem with topicname");
     let message = y.value_of("message");
 
-    let review = {
-        let mut r = entities::ReviewInfo {
-            labels: HashMap::new(),
-        };
+    let labels = {
+        let mut labels: HashMap<String, i8> = HashMap::new();
 
         if let Some(label) = y.value_of("label") {
             if label.contains(':') {
@@ -736,7 +866,7 @@ fn verify(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
                         "2" | "+2" => 2,
                         _ => return Err(GGRError::General("Wrong value for label. Only support for (-2,-1,0,1,2)".into())),
                     };
-                    r.labels.entry(label.into()).or_insert(value);
+                    labels.entry(label.into()).or_insert(value);
                 }
             }
         };
@@ -750,77 +880,65 @@ fn verify(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
                 "2" => 2,
                 _ => return Err(GGRError::General("Wrong code-review parameter".into())),
             };
-            r.labels.entry("Code-Review".into()).or_insert(codereview);
+            labels.entry("Code-Review".into()).or_insert(codereview);
         };
 
-        if r.labels.is_empty() {
+        if labels.is_empty() {
             None
         } else {
-            Some(r)
+            Some(labels)
         }
     };
 
-    let mut gerrit = Gerrit::new(config.get_base_url());
+    let review = entities::ReviewInput {
+        message: message.map(|s| s.to_string()),
+        labels: labels,
+        ..Default::default()
+    };
+
+    let gerrit = config.gerrit(config.credentials())?;
     let mut changes = gerrit.changes();
 
-    if let Ok(changeinfos) = changes.query_changes(Some(vec!(&format!("topic:{}", topicname)[..])), Some(vec!("CURRENT_REVISION"))) {
+    if let Ok(changeinfos) = changes.query_changes(Some(vec!(Query::topic(topicname))), Some(vec!(ListChangesOption::CURRENT_REVISION, ListChangesOption::DETAILED_LABELS))) {
         /* overall review result for the commit */
-        let mut overall_review: HashMap<String /*label*/, (i8,i8) /* min/max */> = HashMap::new();
+        let mut overall_review: HashMap<String /*label*/, (i32,i32) /* min/max */> = HashMap::new();
 
         for ci in changeinfos {
             debug!("{:?}", ci);
-            let (id, changeid, revision, subject) = (
-                ci.id.clone(),
-                ci.change_id.clone(),
-                ci.current_revision.unwrap_or_else(|| "".into()),
-                ci.subject
-            );
+            let id = ChangeId::from(&ci);
+            let revision = match ci.current_revision {
+                Some(ref commit) => RevisionId::Commit(commit.clone()),
+                None => RevisionId::Current,
+            };
+            let (changeid, subject) = (ci.change_id.clone(), ci.subject);
 
             let changes = gerrit.changes();
 
-            if message.is_none() && review.is_none() {
-                // neither review or message is set, we retrieve review information
-
-                match changes.get_reviewers(&id) {
-                    Ok(reviewerinfos) => {
-                        /* a list of reviews for one changeset */
-                        let mut changeinfo_review: HashMap<String /* label */, Vec<String> /* list of reviews */> = HashMap::new();
-
-                        for ri in reviewerinfos {
-                            for (label, review) in ri.approvals {
-                                let review = String::from(review.trim());
-                                let entry = changeinfo_review.entry(label.clone()).or_insert_with(Vec::new);
-                                if let Ok(review_int) = review.parse() {
-                                    entry.push(review);
-
-                                    let overall = overall_review.entry(label.clone()).or_insert((0,0));
-                                    if review_int < overall.0 {
-                                        overall.0 = review_int;
-                                    }
-                                    if review_int > overall.1 {
-                                        overall.1 = review_int;
-                                    }
-                                } else {
-                                    debug!("This review is not convertible to int: {:?}", review);
-                                }
-                            };
-                        };
-
-                        println!("* {:5.5} {}:", changeid, subject);
-                        for (label, review) in changeinfo_review {
-                            let mut sortreview = review.clone();
-                            sortreview.sort();
-                            println!("  {:10.10} -> {:?}", label, sortreview);
-                        };
-                    },
-                    Err(err) => {
-                        println!("Problem to recive reviewers: {}", err);
-                        return Err(err);
+            if review.message.is_none() && review.labels.is_none() {
+                // neither review or message is set, we show the votes of the detailed labels
+                println!("* {:5.5} {}:", changeid, subject);
+                for (label, labelinfo) in ci.labels.unwrap_or_default() {
+                    let mut votes: Vec<String> = labelinfo.all.iter()
+                        .flat_map(|all| all.iter())
+                        .filter_map(|approval| approval.value)
+                        .map(|value| if value == 0 { "0".to_string() } else { format!("{:+}", value) })
+                        .collect();
+                    votes.sort();
+                    println!("  {:10.10} -> {:?}", label, votes);
+
+                    if let Some((min, max)) = labelinfo.vote_range() {
+                        let overall = overall_review.entry(label).or_insert((0,0));
+                        if min < overall.0 {
+                            overall.0 = min;
+                        }
+                        if max > overall.1 {
+                            overall.1 = max;
+                        }
                     }
-                };
+                }
             } else {
                 // message and/or review is set we push them to the gerrit server
-                match changes.set_review(&id, &revision, message, review.clone()) {
+                match changes.set_review(&id, &revision, &review) {
                     Ok(reviewinfo) => println!("* {:5.5} {:20.20}, applied: {:?}", changeid, subject, reviewinfo.labels),
                     Err(err) => println!("* {:5.5} {:20.20}, not applied: {}", changeid, subject, err),
                 };
@@ -843,29 +961,67 @@ fn verify(y: &clap::ArgMatches, config: &config::Config) -> GGRResult<()> {
 /// Convenient function to fetch topic `topicname` to branch `local_branch_name`.
 ///
 /// If branch exists and `force` is true, the branch is moving to new position.
-fn fetch_topic(gerrit: &mut Gerrit, topicname: &str, local_branch_name: &str, force: bool, tracking_branch_name: Option<&str>, closed: bool) -> GGRResult<()> {
+fn fetch_topic(gerrit: &Gerrit, topicname: &str, local_branch_name: &str, force: bool, tracking_branch_name: Option<&str>, closed: bool, credentials: &dyn CredentialProvider, proxy: &ProxySettings, tls: &TlsSettings) -> GGRResult<()> {
     trace!("fetch_topic: topicname:{} local_branch_name:{} force:{} tracking_branch_name:{:?} closed:{}",
            topicname, local_branch_name, force, tracking_branch_name, closed);
 
     let mut changes = gerrit.changes();
 
-    let mut query_part = vec!(format!("topic:{}", topicname));
+    let mut query_part = vec!(Query::topic(topicname));
     if !closed {
-        query_part.push("status:open".into());
+        query_part.push(Query::status("open"));
     }
 
-    let changeinfos = changes.query_changes(Some(query_part), Some(vec!("CURRENT_REVISION".into(), "CURRENT_COMMIT".into())))?;
+    let mut changeinfos = changes.query_changes(Some(query_part), Some(vec!(ListChangesOption::CURRENT_REVISION, ListChangesOption::CURRENT_COMMIT)))?;
     if changeinfos.is_empty() {
         println!("topic '{}' not found", topicname);
         return Ok(());
     }
-    fetch_changeinfos(&changeinfos, force, local_branch_name, tracking_branch_name)
+    complete_fetchinfos(gerrit, &mut changeinfos);
+    fetch_changeinfos(&changeinfos, force, local_branch_name, tracking_branch_name, credentials, proxy, tls)
+}
+
+/// adds a http `FetchInfo` to all revisions without one
+///
+/// Without the `download-commands` plugin the server has no download schemes and the `fetch` map
+/// of the revisions is empty. The patch sets are then fetched from `<gerrit-url>/<project>` with
+/// the ref of the revision.
+pub fn complete_fetchinfos(gerrit: &Gerrit, changeinfos: &mut [entities::ChangeInfo]) {
+    match gerrit.config().get_server_info() {
+        Ok(ref info) if info.download_scheme("http").is_some() => return,
+        Ok(_) => debug!("no http download scheme, the download-commands plugin is missing"),
+        Err(x) => warn!("server info not received ({}), fill missing fetch informations", x),
+    }
+
+    let prefix = if gerrit.is_authenticated() { "/a" } else { "" };
+    let base = format!("{}{}", gerrit.url().as_str().trim_end_matches('/'), prefix);
+
+    for ci in changeinfos.iter_mut() {
+        let number = ci._number;
+        let url = format!("{}/{}", base, ci.project);
+        if let Some(ref mut revisions) = ci.revisions {
+            for revisioninfo in revisions.values_mut() {
+                if revisioninfo.fetch.keys().any(|fetchtype| fetchtype.starts_with("http")) {
+                    continue;
+                }
+
+                let reference = revisioninfo.reference.clone()
+                    .unwrap_or_else(|| format!("refs/changes/{:02}/{}/{}", number % 100, number, revisioninfo._number));
+                revisioninfo.fetch.insert("http".into(), entities::FetchInfo {
+                    url: url.clone(),
+                    reference: reference,
+                    commands: None,
+                    extra: Default::default(),
+                });
+            }
+        }
+    }
 }
 
 /// Convenient function to pull one or more `changeids`
 ///
 /// all ancestore commits are pulled from gerrit server too.
-pub fn fetch_changeinfos(changeinfos: &[entities::ChangeInfo], force: bool, local_branch_name: &str, tracking_branch_name: Option<&str>) -> GGRResult<()> {
+pub fn fetch_changeinfos(changeinfos: &[entities::ChangeInfo], force: bool, local_branch_name: &str, tracking_branch_name: Option<&str>, credentials: &dyn CredentialProvider, proxy: &ProxySettings, tls: &TlsSettings) -> GGRResult<()> {
     let project_tip = project_tip(changeinfos).unwrap();
 
     // try to fetch topic for main_repo and all submodules
@@ -874,7 +1030,7 @@ pub fn fetch_changeinfos(changeinfos: &[entities::ChangeInfo], force: bool, loca
         // check for root repository
         if let Ok(main_repo) = git2::Repository::open(".") {
             // check changes on root repository
-            match fetch_from_repo(&main_repo, changeinfos, force, local_branch_name, &p_name, &p_tip, tracking_branch_name) {
+            match fetch_from_repo(&main_repo, changeinfos, force, local_branch_name, &p_name, &p_tip, tracking_branch_name, credentials, proxy, tls) {
                 Ok((true, x)) => {
                     println!("OK ({})", x);
                     continue;
@@ -895,7 +1051,7 @@ pub fn fetch_changeinfos(changeinfos: &[entities::ChangeInfo], force: bool, loca
             if let Ok(smodules) = main_repo.submodules() {
                 for smodule in smodules {
                     if let Ok(sub_repo) = smodule.open() {
-                        match fetch_from_repo(&sub_repo, changeinfos, force, local_branch_name, &p_name, &p_tip, tracking_branch_name) {
+                        match fetch_from_repo(&sub_repo, changeinfos, force, local_branch_name, &p_name, &p_tip, tracking_branch_name, credentials, proxy, tls) {
                             Ok((true, _)) => {
                                 println!("OK");
                                 continue 'next_ptip;
@@ -930,7 +1086,7 @@ pub fn fetch_changeinfos(changeinfos: &[entities::ChangeInfo], force: bool, loca
 ///
 /// returns `true` if something is pulled, and `false` if no pull was executed. The String object
 /// is a status message.
-fn fetch_from_repo(repo: &git2::Repository, ci: &[entities::ChangeInfo], force: bool, local_branch_name: &str, project_name: &str, project_tip: &str, tracking_branch_name: Option<&str>) -> GGRResult<(bool, String)> {
+fn fetch_from_repo(repo: &git2::Repository, ci: &[entities::ChangeInfo], force: bool, local_branch_name: &str, project_name: &str, project_tip: &str, tracking_branch_name: Option<&str>, credentials: &dyn CredentialProvider, proxy: &ProxySettings, tls: &TlsSettings) -> GGRResult<(bool, String)> {
     trace!("repo-path:{:?}, project_name:{}, project_tip:{}", repo.path().file_name(), project_name, project_tip);
     if repo.is_bare() {
         return Err(GGRError::General(format!("repository path '{:?}' is bare, we need a workdir", repo.path())));
@@ -959,7 +1115,7 @@ fn fetch_from_repo(repo: &git2::Repository, ci: &[entities::ChangeInfo], force:
 
                         for (fetchtype, fetchinfo) in &revisioninfo.fetch {
                             if fetchtype.starts_with("http") {
-                                match do_fetch_from_repo(fetchinfo, local_branch_name, TagOrBranch::Branch, tracking_branch_name, force, false) {
+                                match do_fetch_from_repo(fetchinfo, local_branch_name, TagOrBranch::Branch, tracking_branch_name, force, false, credentials, proxy, tls) {
                                     Err(x) => return Err(x),
                                     Ok(x) => {
                                         return Ok((true, x))
@@ -994,19 +1150,7 @@ fn project_tip(changes: &[entities::ChangeInfo]) -> GGRResult<HashMap<String, St
         let mut list_all_parents = Vec::new();
         // fill a list with all parents
         for element in changes {
-            if let Some(ref cur_revision) = element.current_revision {
-                if let Some(ref revisions) = element.revisions {
-                    if let Some(cur_revision) = revisions.get(cur_revision) {
-                        if let Some(ref commit) = cur_revision.commit {
-                            if let Some(ref parents) = commit.parents {
-                                for p in parents {
-                                    list_all_parents.push(&p.commit);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+            list_all_parents.append(&mut current_parents(element));
         }
 
         /* TODO: rewrite */
@@ -1026,6 +1170,29 @@ fn project_tip(changes: &[entities::ChangeInfo]) -> GGRResult<HashMap<String, St
     Ok(project_tip)
 }
 
+/// returns the parent commits of the current revision of a change
+///
+/// Needs `CURRENT_REVISION` and `CURRENT_COMMIT` on the query of the change.
+fn current_parents(change: &entities::ChangeInfo) -> Vec<&String> {
+    let mut out = Vec::new();
+
+    if let Some(ref cur_revision) = change.current_revision {
+        if let Some(ref revisions) = change.revisions {
+            if let Some(cur_revision) = revisions.get(cur_revision) {
+                if let Some(ref commit) = cur_revision.commit {
+                    if let Some(ref parents) = commit.parents {
+                        for p in parents {
+                            out.push(&p.commit);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    out
+}
+
 pub fn entity_from_commit<'ci>(changes: &'ci [entities::ChangeInfo], commit: &str) -> GGRResult<&'ci entities::ChangeInfo> {
     for element in changes {
         if let Some(ref revisions) = element.revisions {
@@ -1151,3 +1318,58 @@ fn test_url_to_projectname() {
     assert_eq!(url_to_projectname("n/i/k/o/lause"), Some("lause"));
     assert_eq!(url_to_projectname(""), None);
 }
+
+#[test]
+fn test_submit_order() {
+    use serde_json;
+
+    fn change(project: &str, number: u64, revision: &str, parent: &str) -> entities::ChangeInfo {
+        let json = serde_json::json!({
+            "id": format!("{}~master~I{}", project, number),
+            "project": project,
+            "branch": "master",
+            "change_id": format!("I{}", number),
+            "subject": format!("change {}", number),
+            "status": "NEW",
+            "created": "2017-01-01 00:00:00.000000000",
+            "updated": "2017-01-01 00:00:00.000000000",
+            "insertions": 1,
+            "deletions": 0,
+            "_number": number,
+            "owner": {},
+            "current_revision": revision,
+            "revisions": {
+                revision: {
+                    "_number": 1,
+                    "fetch": {},
+                    "commit": { "parents": [ { "commit": parent, "subject": "parent" } ] }
+                }
+            }
+        });
+
+        serde_json::from_value(json).unwrap()
+    }
+
+    // child changes have lower change numbers than there parents
+    let changes = vec!(
+        change("sub", 1, "c3", "c2"),
+        change("main", 2, "m1", "base"),
+        change("sub", 3, "c2", "c1"),
+        change("sub", 4, "c1", "base"),
+    );
+
+    let order: Vec<u64> = submit_order(&changes).iter().map(|ci| ci._number).collect();
+    assert_eq!(order, vec!(2, 4, 3, 1));
+
+    // the server did not report submittable and mergeable
+    let mut unknown = change("main", 5, "m2", "m1");
+    assert_eq!(submit_problems(&unknown, false), vec!("submittable is unknown (use --force to submit anyway)",
+                                                      "mergeable is unknown (use --force to submit anyway)"));
+    assert!(submit_problems(&unknown, true).is_empty());
+
+    unknown.submittable = Some(true);
+    unknown.mergeable = Some(false);
+    assert_eq!(submit_problems(&unknown, true), vec!("not mergeable (merge conflict)"));
+    unknown.mergeable = Some(true);
+    assert!(submit_problems(&unknown, false).is_empty());
+}
@@ -0,0 +1,116 @@
+//! `ggr` commands against recorded cassettes of `tests/fixtures`, without a gerrit server
+//!
+//! The cassettes are recorded with `GGR_CASSETTE=record:<path>` from the in-process fake gerrit
+//! server, `cargo test --test replay -- --ignored` records them again.
+
+extern crate fakegerrit;
+extern crate serde_json;
+
+use fakegerrit::{AuthScheme, FakeGerrit, TempDir};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+/// runs ggr in `home` with the `.ggr.conf` there and the cassette switch, returns stdout
+fn ggr(home: &Path, cassette: &str, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_gerrit-rust"))
+        .current_dir(home)
+        .env("HOME", home)
+        .env("GGR_CASSETTE", cassette)
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "ggr {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    assert!(!stdout.contains("Error:"), "ggr {:?} failed: {}", args, stdout);
+    stdout
+}
+
+/// a home directory with a `.ggr.conf` for `url`
+fn home(url: &str) -> TempDir {
+    let home = TempDir::new("ggr-replay").unwrap();
+    fs::write(home.path().join(".ggr.conf"), format!("api = \"{}\"\nroot = true\ndryrun = false\n", url)).unwrap();
+    home
+}
+
+const QUERY: &'static [&'static str] = &["changes", "query", "-r", "status:open", "project:tools"];
+const REVIEWER: &'static [&'static str] = &["topic", "reviewer", "--verbose", "feature x+y"];
+
+/// records the cassettes of all tests from the fake gerrit server
+#[test]
+#[ignore]
+fn record_fixtures() {
+    let seed = fs::read_to_string(format!("{}/libgerrit/tests/fixtures/fakegerrit.json", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let server = FakeGerrit::start(&seed, AuthScheme::Digest).unwrap();
+    let jane = r#"[{"_account_id": 1000001, "name": "Jane Reviewer", "email": "jane@example.com",
+                    "username": "jane", "approvals": {"Code-Review": "+2", "Verified": "+1"}}]"#;
+    server.model().reviewers.insert("tools~master~I0000000000000000000000000000000000000021".into(),
+                                    serde_json::from_str(jane).unwrap());
+
+    fs::create_dir_all(fixture("")).unwrap();
+    let home = home(server.url());
+    fs::write(home.path().join(".netrc"), "machine 127.0.0.1 login admin password secret\n").unwrap();
+
+    ggr(home.path(), &format!("record:{}", fixture("changes-query.json")), QUERY);
+    ggr(home.path(), &format!("record:{}", fixture("topic-reviewer.json")), REVIEWER);
+}
+
+#[test]
+fn test_replay_changes_query() {
+    let home = home("http://localhost:8080");
+    let out = ggr(home.path(), &format!("replay:{}", fixture("changes-query.json")), QUERY);
+
+    let changes: serde_json::Value = serde_json::from_str(out.trim()).unwrap();
+    let numbers: Vec<u64> = changes.as_array().unwrap().iter().map(|ci| ci["_number"].as_u64().unwrap()).collect();
+    assert_eq!(numbers, vec!(21, 22, 23));
+}
+
+#[test]
+fn test_replay_changes_query_selector() {
+    let home = home("http://localhost:8080");
+    let mut args = QUERY.iter().filter(|&&arg| arg != "-r").cloned().collect::<Vec<_>>();
+    args.extend(&["-f", r"^\[\d+\]\._number$"]);
+    let out = ggr(home.path(), &format!("replay:{}", fixture("changes-query.json")), &args);
+
+    assert_eq!(out.trim().lines().collect::<Vec<_>>(), vec!("[0]._number 21", "[1]._number 22", "[2]._number 23"));
+}
+
+#[test]
+fn test_replay_changes_query_fieldslist() {
+    let home = home("http://localhost:8080");
+    let mut args = QUERY.iter().filter(|&&arg| arg != "-r").cloned().collect::<Vec<_>>();
+    args.push("-l");
+    let out = ggr(home.path(), &format!("replay:{}", fixture("changes-query.json")), &args);
+
+    assert!(out.starts_with("3 -> "), "{}", out);
+    assert!(out.contains(" _number(3) "), "{}", out);
+}
+
+#[test]
+fn test_replay_topic_reviewer() {
+    let home = home("http://localhost:8080");
+    let out = ggr(home.path(), &format!("replay:{}", fixture("topic-reviewer.json")), REVIEWER);
+
+    assert!(out.contains("reviewer for 'change 21 of feature x+y'"), "{}", out);
+    assert!(out.contains("reviewer for 'change 22 of feature x+y'"), "{}", out);
+    assert!(out.lines().any(|line| line.contains("jane@example.com") && line.contains("Code-Review") && line.contains("+2")), "{}", out);
+}
+
+#[test]
+fn test_replay_unrecorded_request() {
+    let home = home("http://localhost:8080");
+    let output = Command::new(env!("CARGO_BIN_EXE_gerrit-rust"))
+        .current_dir(home.path())
+        .env("HOME", home.path())
+        .env("GGR_CASSETTE", format!("replay:{}", fixture("changes-query.json")))
+        .args(&["changes", "query", "-r", "status:merged"])
+        .output()
+        .unwrap();
+
+    assert!(String::from_utf8_lossy(&output.stdout).contains("no recorded interaction"));
+}
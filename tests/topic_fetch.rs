@@ -0,0 +1,76 @@
+//! end-to-end test of `ggr topic fetch` against the in-process fake gerrit server
+
+extern crate fakegerrit;
+extern crate serde_json;
+
+use fakegerrit::{AuthScheme, FakeGerrit, TempDir};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// runs git in `dir` and returns its trimmed stdout
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git").current_dir(dir)
+        .args(&["-c", "user.name=Admin", "-c", "user.email=admin@example.com"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+#[test]
+fn test_topic_fetch() {
+    let seed = r#"{
+        "version": "2.13.5",
+        "users": { "admin": "secret" },
+        "accounts": [ { "_account_id": 1000000, "name": "Admin", "username": "admin" } ]
+    }"#;
+    let server = FakeGerrit::start(seed, AuthScheme::Digest).unwrap();
+    server.create_project("tools").unwrap();
+
+    let home = TempDir::new("ggr-home").unwrap();
+    fs::write(home.path().join(".netrc"), "machine 127.0.0.1 login admin password secret\n").unwrap();
+
+    // upload a base commit and a change on top of it
+    let upload = home.path().join("upload");
+    let push_url = server.url().replace("http://", "http://admin:secret@") + "/a/tools";
+    git(home.path(), &["init", "--quiet", "upload"]);
+    fs::write(upload.join("README"), "base\n").unwrap();
+    git(&upload, &["add", "README"]);
+    git(&upload, &["commit", "--quiet", "-m", "base"]);
+    git(&upload, &["push", "--quiet", &push_url, "HEAD:refs/heads/master"]);
+    fs::write(upload.join("README"), "feature\n").unwrap();
+    git(&upload, &["commit", "--quiet", "-am", "feature"]);
+    git(&upload, &["push", "--quiet", &push_url, "HEAD:refs/changes/01/1/1"]);
+    let revision = git(&upload, &["rev-parse", "HEAD"]);
+
+    let change = format!(r#"{{
+        "id": "tools~master~I0000000000000000000000000000000000000001",
+        "project": "tools", "branch": "master", "topic": "feature",
+        "change_id": "I0000000000000000000000000000000000000001",
+        "subject": "feature", "status": "NEW",
+        "created": "2017-03-01 10:00:00.000000000", "updated": "2017-03-01 10:00:00.000000000",
+        "insertions": 1, "deletions": 1, "_number": 1,
+        "owner": {{ "_account_id": 1000000 }},
+        "current_revision": "{rev}",
+        "revisions": {{ "{rev}": {{ "_number": 1,
+            "fetch": {{ "http": {{ "url": "{url}/tools", "ref": "refs/changes/01/1/1" }} }} }} }}
+    }}"#, rev = revision, url = server.url());
+    server.model().changes.push(serde_json::from_str(&change).unwrap());
+
+    // work clone with a ggr configuration
+    let work = home.path().join("tools");
+    git(home.path(), &["clone", "--quiet", &format!("{}/tools", server.url()), "tools"]);
+    fs::write(work.join(".ggr.conf"), format!("api = \"{}\"\nroot = true\ndryrun = false\n", server.url())).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_gerrit-rust"))
+        .current_dir(&work)
+        .env("HOME", home.path())
+        .args(&["topic", "fetch", "feature"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "ggr topic fetch failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    assert_eq!(git(&work, &["rev-parse", "refs/heads/feature"]), revision);
+}